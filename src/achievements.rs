@@ -0,0 +1,422 @@
+use std::collections::BTreeSet;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::locale::Locale;
+use crate::menu::MenuInput;
+use crate::palette::{Palette, PaletteKind};
+use crate::profiles::Profiles;
+use crate::{
+    AppState, ClearEvent, CpuControl, GameMode, GameSet, LocalControl, MatchOver, PlayerId,
+    Players, active_players,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+const ACHIEVEMENTS_FILE: &str = "achievements.json";
+const TOAST_SECONDS: f32 = 3.0;
+const GARBAGE_GOAL: u32 = 100;
+const SURVIVE_SECONDS: f32 = 5.0 * 60.0;
+
+pub struct AchievementsPlugin;
+
+impl Plugin for AchievementsPlugin {
+    fn build(&self, app: &mut App) {
+        let achievements = Achievements::load(app.world().resource::<Profiles>());
+        app.insert_resource(achievements)
+            .add_event::<AchievementUnlocked>()
+            .add_systems(OnEnter(AppState::Achievements), setup_gallery)
+            .add_systems(OnExit(AppState::Achievements), cleanup_gallery)
+            .add_systems(
+                Update,
+                handle_gallery_input.run_if(in_state(AppState::Achievements)),
+            )
+            .add_systems(
+                Update,
+                (track_clears, track_survival, track_match_end).in_set(GameSet::Presentation),
+            )
+            .add_systems(Update, (spawn_toasts, update_toasts).chain());
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Achievement {
+    FirstWin,
+    Chain4,
+    Chain7,
+    Garbage100,
+    Survive5Minutes,
+    CleanWin,
+}
+
+impl Achievement {
+    pub const ALL: [Achievement; 6] = [
+        Achievement::FirstWin,
+        Achievement::Chain4,
+        Achievement::Chain7,
+        Achievement::Garbage100,
+        Achievement::Survive5Minutes,
+        Achievement::CleanWin,
+    ];
+
+    fn key(self) -> &'static str {
+        match self {
+            Achievement::FirstWin => "first_win",
+            Achievement::Chain4 => "chain4",
+            Achievement::Chain7 => "chain7",
+            Achievement::Garbage100 => "garbage100",
+            Achievement::Survive5Minutes => "survive5",
+            Achievement::CleanWin => "clean_win",
+        }
+    }
+
+    pub fn name(self, locale: &Locale) -> String {
+        locale
+            .get(&format!("achievement.{}.name", self.key()))
+            .to_string()
+    }
+
+    fn description(self, locale: &Locale) -> String {
+        locale
+            .get(&format!("achievement.{}.desc", self.key()))
+            .to_string()
+    }
+}
+
+pub fn palette_requirement(kind: PaletteKind) -> Option<Achievement> {
+    match kind {
+        PaletteKind::Neon => Some(Achievement::Chain4),
+        PaletteKind::Gold => Some(Achievement::CleanWin),
+        _ => None,
+    }
+}
+
+pub fn speed_requirement(level: u32) -> Option<Achievement> {
+    match level {
+        0..=5 => None,
+        6..=8 => Some(Achievement::Survive5Minutes),
+        _ => Some(Achievement::Chain7),
+    }
+}
+
+#[derive(Resource, Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Achievements {
+    pub unlocked: BTreeSet<Achievement>,
+    pub garbage_sent: u32,
+}
+
+impl Achievements {
+    pub fn has(&self, achievement: Achievement) -> bool {
+        self.unlocked.contains(&achievement)
+    }
+
+    pub fn meets(&self, requirement: Option<Achievement>) -> bool {
+        requirement.is_none_or(|achievement| self.has(achievement))
+    }
+
+    fn unlock(
+        &mut self,
+        achievement: Achievement,
+        events: &mut EventWriter<AchievementUnlocked>,
+    ) -> bool {
+        if !self.unlocked.insert(achievement) {
+            return false;
+        }
+        events.send(AchievementUnlocked(achievement));
+        true
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(profiles: &Profiles) -> Self {
+        let path = profiles.dir().join(ACHIEVEMENTS_FILE);
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|err| {
+                warn!(
+                    "ignoring unreadable achievements file {}: {err}",
+                    path.display()
+                );
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn load(_profiles: &Profiles) -> Self {
+        Self::default()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save(&self, profiles: &Profiles) {
+        let path = profiles.dir().join(ACHIEVEMENTS_FILE);
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let Ok(contents) = serde_json::to_string_pretty(self) else {
+            return;
+        };
+        if let Err(err) = std::fs::write(&path, contents) {
+            warn!("could not save achievements to {}: {err}", path.display());
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn save(&self, _profiles: &Profiles) {}
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct AchievementUnlocked(pub Achievement);
+
+fn human_players(
+    mode: GameMode,
+    local: LocalControl,
+    cpu: CpuControl,
+) -> impl Iterator<Item = PlayerId> {
+    active_players(mode)
+        .iter()
+        .copied()
+        .filter(move |id| !cpu.controls(*id) && local.0.is_none_or(|local| local == *id))
+}
+
+fn track_clears(
+    mut clears: EventReader<ClearEvent>,
+    mode: Res<GameMode>,
+    local: Res<LocalControl>,
+    cpu: Res<CpuControl>,
+    profiles: Res<Profiles>,
+    mut achievements: ResMut<Achievements>,
+    mut unlocked: EventWriter<AchievementUnlocked>,
+) {
+    let mut changed = false;
+    for event in clears.read() {
+        if !human_players(*mode, *local, *cpu).any(|id| id == event.player) {
+            continue;
+        }
+        if event.chain >= 4 {
+            changed |= achievements.unlock(Achievement::Chain4, &mut unlocked);
+        }
+        if event.chain >= 7 {
+            changed |= achievements.unlock(Achievement::Chain7, &mut unlocked);
+        }
+    }
+    if changed {
+        achievements.save(&profiles);
+    }
+}
+
+fn track_survival(
+    players: Res<Players>,
+    mode: Res<GameMode>,
+    local: Res<LocalControl>,
+    cpu: Res<CpuControl>,
+    profiles: Res<Profiles>,
+    mut achievements: ResMut<Achievements>,
+    mut unlocked: EventWriter<AchievementUnlocked>,
+) {
+    if achievements.has(Achievement::Survive5Minutes) {
+        return;
+    }
+    if human_players(*mode, *local, *cpu).any(|id| players.get(id).elapsed >= SURVIVE_SECONDS)
+        && achievements.unlock(Achievement::Survive5Minutes, &mut unlocked)
+    {
+        achievements.save(&profiles);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn track_match_end(
+    players: Res<Players>,
+    match_over: Res<MatchOver>,
+    mode: Res<GameMode>,
+    local: Res<LocalControl>,
+    cpu: Res<CpuControl>,
+    profiles: Res<Profiles>,
+    mut recorded: Local<bool>,
+    mut achievements: ResMut<Achievements>,
+    mut unlocked: EventWriter<AchievementUnlocked>,
+) {
+    if !match_over.active {
+        *recorded = false;
+        return;
+    }
+    if *recorded {
+        return;
+    }
+    *recorded = true;
+    for id in human_players(*mode, *local, *cpu) {
+        let player = players.get(id);
+        achievements.garbage_sent = achievements
+            .garbage_sent
+            .saturating_add(player.garbage_sent);
+        if match_over.winner == Some(id) {
+            achievements.unlock(Achievement::FirstWin, &mut unlocked);
+            if player.garbage_received == 0 {
+                achievements.unlock(Achievement::CleanWin, &mut unlocked);
+            }
+        }
+    }
+    if achievements.garbage_sent >= GARBAGE_GOAL {
+        achievements.unlock(Achievement::Garbage100, &mut unlocked);
+    }
+    achievements.save(&profiles);
+}
+
+#[derive(Component)]
+struct Toast {
+    timer: Timer,
+}
+
+fn spawn_toasts(
+    mut commands: Commands,
+    mut events: EventReader<AchievementUnlocked>,
+    palette: Res<Palette>,
+    locale: Res<Locale>,
+    toasts: Query<(), With<Toast>>,
+) {
+    for (stacked, AchievementUnlocked(achievement)) in (toasts.iter().count()..).zip(events.read())
+    {
+        commands
+            .spawn(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(16.0),
+                    bottom: Val::Px(16.0 + stacked as f32 * 64.0),
+                    padding: UiRect::all(Val::Px(10.0)),
+                    flex_direction: FlexDirection::Column,
+                    ..Default::default()
+                },
+                background_color: BackgroundColor(palette.overlay),
+                z_index: ZIndex::Global(20),
+                ..Default::default()
+            })
+            .insert(Toast {
+                timer: Timer::from_seconds(TOAST_SECONDS, TimerMode::Once),
+            })
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section(
+                    locale.get("achievements.unlocked"),
+                    TextStyle {
+                        font: Default::default(),
+                        font_size: 14.0,
+                        color: palette.text,
+                    },
+                ));
+                parent.spawn(TextBundle::from_section(
+                    achievement.name(&locale),
+                    TextStyle {
+                        font: Default::default(),
+                        font_size: 22.0,
+                        color: palette.accent,
+                    },
+                ));
+            });
+    }
+}
+
+fn update_toasts(mut commands: Commands, time: Res<Time>, mut toasts: Query<(Entity, &mut Toast)>) {
+    for (entity, mut toast) in &mut toasts {
+        if toast.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+#[derive(Resource)]
+struct GalleryRoot(Entity);
+
+fn setup_gallery(
+    mut commands: Commands,
+    achievements: Res<Achievements>,
+    palette: Res<Palette>,
+    locale: Res<Locale>,
+) {
+    let root = commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(0.0),
+                top: Val::Percent(0.0),
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(12.0),
+                ..Default::default()
+            },
+            background_color: BackgroundColor(palette.overlay),
+            ..Default::default()
+        })
+        .id();
+
+    commands.entity(root).with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            locale.get("achievements.title"),
+            TextStyle {
+                font: Default::default(),
+                font_size: 42.0,
+                color: palette.heading,
+            },
+        ));
+
+        for achievement in Achievement::ALL {
+            let unlocked = achievements.has(achievement);
+            parent.spawn(TextBundle {
+                text: Text::from_sections([
+                    TextSection::new(
+                        format!("{}\n", achievement.name(&locale)),
+                        TextStyle {
+                            font: Default::default(),
+                            font_size: 24.0,
+                            color: if unlocked {
+                                palette.accent
+                            } else {
+                                palette.text
+                            },
+                        },
+                    ),
+                    TextSection::new(
+                        achievement.description(&locale),
+                        TextStyle {
+                            font: Default::default(),
+                            font_size: 16.0,
+                            color: palette.text,
+                        },
+                    ),
+                ])
+                .with_justify(JustifyText::Center),
+                ..Default::default()
+            });
+        }
+
+        parent.spawn(TextBundle::from_section(
+            locale.format(
+                "achievements.progress",
+                &[
+                    ("unlocked", &achievements.unlocked.len()),
+                    ("total", &Achievement::ALL.len()),
+                    ("garbage", &achievements.garbage_sent),
+                ],
+            ),
+            TextStyle {
+                font: Default::default(),
+                font_size: 18.0,
+                color: palette.text,
+            },
+        ));
+    });
+
+    commands.insert_resource(GalleryRoot(root));
+}
+
+fn cleanup_gallery(mut commands: Commands, root: Res<GalleryRoot>) {
+    commands.entity(root.0).despawn_recursive();
+    commands.remove_resource::<GalleryRoot>();
+}
+
+fn handle_gallery_input(input: MenuInput, mut next_state: ResMut<NextState<AppState>>) {
+    if input.nav().cancel {
+        next_state.set(AppState::Title);
+    }
+}
@@ -0,0 +1,226 @@
+use std::f32::consts::TAU;
+use std::time::Duration;
+
+use bevy::audio::{AddAudioSource, Decodable, Source};
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::cli::LaunchOptions;
+use crate::records::RecordBroken;
+use crate::{ClearEvent, GameSet, GarbageCounterEvent, LevelUpEvent, apply_launch_options};
+
+const SAMPLE_RATE: u32 = 44_100;
+const NOTE_SECONDS: f32 = 0.09;
+const STINGER_VOLUME: f32 = 0.3;
+
+pub struct StingersPlugin;
+
+impl Plugin for StingersPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_audio_source::<Stinger>()
+            .init_resource::<VoicePack>()
+            .add_systems(
+                Startup,
+                (build_stingers, load_voice_pack.after(apply_launch_options)),
+            )
+            .add_systems(Update, play_stingers.in_set(GameSet::Presentation));
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum StingerTier {
+    Chain2,
+    Chain3,
+    Chain4,
+    Combo4,
+    Combo5,
+    Combo6,
+    Counter,
+    LevelUp,
+    Record,
+}
+
+impl StingerTier {
+    const ALL: [StingerTier; 9] = [
+        StingerTier::Chain2,
+        StingerTier::Chain3,
+        StingerTier::Chain4,
+        StingerTier::Combo4,
+        StingerTier::Combo5,
+        StingerTier::Combo6,
+        StingerTier::Counter,
+        StingerTier::LevelUp,
+        StingerTier::Record,
+    ];
+
+    fn for_clear(chain: u32, cleared: u32) -> Option<Self> {
+        match (chain, cleared) {
+            (4.., _) => Some(StingerTier::Chain4),
+            (3, _) => Some(StingerTier::Chain3),
+            (2, _) => Some(StingerTier::Chain2),
+            (_, 6..) => Some(StingerTier::Combo6),
+            (_, 5) => Some(StingerTier::Combo5),
+            (_, 4) => Some(StingerTier::Combo4),
+            _ => None,
+        }
+    }
+
+    fn file_stem(self) -> &'static str {
+        match self {
+            StingerTier::Chain2 => "chain2",
+            StingerTier::Chain3 => "chain3",
+            StingerTier::Chain4 => "chain4",
+            StingerTier::Combo4 => "combo4",
+            StingerTier::Combo5 => "combo5",
+            StingerTier::Combo6 => "combo6",
+            StingerTier::Counter => "counter",
+            StingerTier::LevelUp => "level_up",
+            StingerTier::Record => "record",
+        }
+    }
+
+    fn notes(self) -> &'static [f32] {
+        match self {
+            StingerTier::Chain2 => &[523.25, 659.25],
+            StingerTier::Chain3 => &[523.25, 659.25, 783.99],
+            StingerTier::Chain4 => &[523.25, 659.25, 783.99, 1046.5, 1318.5],
+            StingerTier::Combo4 => &[392.0, 493.88],
+            StingerTier::Combo5 => &[440.0, 554.37, 659.25],
+            StingerTier::Combo6 => &[493.88, 622.25, 739.99, 987.77],
+            StingerTier::Counter => &[880.0, 659.25, 440.0, 880.0],
+            StingerTier::LevelUp => &[392.0, 523.25, 659.25, 783.99, 659.25, 1046.5],
+            StingerTier::Record => &[523.25, 523.25, 659.25, 783.99, 1046.5, 783.99, 1046.5],
+        }
+    }
+}
+
+#[derive(Asset, TypePath)]
+struct Stinger {
+    notes: &'static [f32],
+}
+
+impl Decodable for Stinger {
+    type DecoderItem = f32;
+    type Decoder = StingerDecoder;
+
+    fn decoder(&self) -> Self::Decoder {
+        StingerDecoder {
+            notes: self.notes,
+            sample: 0,
+        }
+    }
+}
+
+struct StingerDecoder {
+    notes: &'static [f32],
+    sample: u32,
+}
+
+impl Iterator for StingerDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let note_len = (NOTE_SECONDS * SAMPLE_RATE as f32) as u32;
+        let frequency = *self.notes.get((self.sample / note_len) as usize)?;
+        let progress = (self.sample % note_len) as f32 / note_len as f32;
+        let time = self.sample as f32 / SAMPLE_RATE as f32;
+        self.sample += 1;
+        let envelope = (1.0 - progress).powi(2);
+        Some((time * frequency * TAU).sin() * envelope * STINGER_VOLUME)
+    }
+}
+
+impl Source for StingerDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(Duration::from_secs_f32(
+            NOTE_SECONDS * self.notes.len() as f32,
+        ))
+    }
+}
+
+#[derive(Resource)]
+struct Stingers(HashMap<StingerTier, Handle<Stinger>>);
+
+#[derive(Resource, Default)]
+struct VoicePack {
+    clips: HashMap<StingerTier, Handle<AudioSource>>,
+}
+
+fn build_stingers(mut commands: Commands, mut assets: ResMut<Assets<Stinger>>) {
+    let handles = StingerTier::ALL
+        .into_iter()
+        .map(|tier| {
+            (
+                tier,
+                assets.add(Stinger {
+                    notes: tier.notes(),
+                }),
+            )
+        })
+        .collect();
+    commands.insert_resource(Stingers(handles));
+}
+
+fn load_voice_pack(
+    options: Res<LaunchOptions>,
+    asset_server: Res<AssetServer>,
+    mut pack: ResMut<VoicePack>,
+) {
+    let Some(name) = &options.voice_pack else {
+        return;
+    };
+    pack.clips = StingerTier::ALL
+        .into_iter()
+        .map(|tier| {
+            let path = format!("voice/{name}/{}.ogg", tier.file_stem());
+            (tier, asset_server.load(path))
+        })
+        .collect();
+}
+
+#[allow(clippy::too_many_arguments)]
+fn play_stingers(
+    mut commands: Commands,
+    mut clear_events: EventReader<ClearEvent>,
+    mut counter_events: EventReader<GarbageCounterEvent>,
+    mut level_events: EventReader<LevelUpEvent>,
+    mut record_events: EventReader<RecordBroken>,
+    stingers: Res<Stingers>,
+    pack: Res<VoicePack>,
+    clips: Res<Assets<AudioSource>>,
+) {
+    let tiers = clear_events
+        .read()
+        .filter_map(|event| StingerTier::for_clear(event.chain, event.cleared))
+        .chain(counter_events.read().map(|_| StingerTier::Counter))
+        .chain(level_events.read().map(|_| StingerTier::LevelUp))
+        .chain(record_events.read().map(|_| StingerTier::Record));
+    for tier in tiers {
+        match pack.clips.get(&tier).filter(|clip| clips.contains(*clip)) {
+            Some(clip) => {
+                commands.spawn(AudioBundle {
+                    source: clip.clone(),
+                    settings: PlaybackSettings::DESPAWN,
+                });
+            }
+            None => {
+                commands.spawn(AudioSourceBundle {
+                    source: stingers.0[&tier].clone(),
+                    settings: PlaybackSettings::DESPAWN,
+                });
+            }
+        }
+    }
+}
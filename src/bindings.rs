@@ -0,0 +1,330 @@
+use bevy::prelude::*;
+
+use crate::PlayerId;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyAction {
+    Left,
+    Right,
+    Up,
+    Down,
+    Swap,
+    Dash,
+    Rotate,
+    Raise,
+}
+
+impl KeyAction {
+    pub const ALL: [KeyAction; 8] = [
+        KeyAction::Left,
+        KeyAction::Right,
+        KeyAction::Up,
+        KeyAction::Down,
+        KeyAction::Swap,
+        KeyAction::Dash,
+        KeyAction::Rotate,
+        KeyAction::Raise,
+    ];
+}
+
+// One keyboard player's keys. Raise is optional because only ZEN uses it and
+// P2 never plays ZEN.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PlayerKeys {
+    pub left: KeyCode,
+    pub right: KeyCode,
+    pub up: KeyCode,
+    pub down: KeyCode,
+    pub swap: KeyCode,
+    pub dash: KeyCode,
+    pub rotate: KeyCode,
+    pub raise: Option<KeyCode>,
+}
+
+impl PlayerKeys {
+    pub fn key(&self, action: KeyAction) -> Option<KeyCode> {
+        match action {
+            KeyAction::Left => Some(self.left),
+            KeyAction::Right => Some(self.right),
+            KeyAction::Up => Some(self.up),
+            KeyAction::Down => Some(self.down),
+            KeyAction::Swap => Some(self.swap),
+            KeyAction::Dash => Some(self.dash),
+            KeyAction::Rotate => Some(self.rotate),
+            KeyAction::Raise => self.raise,
+        }
+    }
+}
+
+pub const P1_KEYS: PlayerKeys = PlayerKeys {
+    left: KeyCode::ArrowLeft,
+    right: KeyCode::ArrowRight,
+    up: KeyCode::ArrowUp,
+    down: KeyCode::ArrowDown,
+    swap: KeyCode::Space,
+    dash: KeyCode::KeyZ,
+    rotate: KeyCode::KeyV,
+    raise: Some(KeyCode::KeyX),
+};
+
+pub const P2_KEYS: PlayerKeys = PlayerKeys {
+    left: KeyCode::KeyA,
+    right: KeyCode::KeyD,
+    up: KeyCode::KeyW,
+    down: KeyCode::KeyS,
+    swap: KeyCode::ShiftLeft,
+    dash: KeyCode::KeyQ,
+    rotate: KeyCode::KeyE,
+    raise: None,
+};
+
+pub fn keys_for(player: PlayerId) -> &'static PlayerKeys {
+    match player {
+        PlayerId::P1 => &P1_KEYS,
+        PlayerId::P2 => &P2_KEYS,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MenuAction {
+    Up,
+    Down,
+    Left,
+    Right,
+    Confirm,
+    Cancel,
+}
+
+impl MenuAction {
+    pub const ALL: [MenuAction; 6] = [
+        MenuAction::Up,
+        MenuAction::Down,
+        MenuAction::Left,
+        MenuAction::Right,
+        MenuAction::Confirm,
+        MenuAction::Cancel,
+    ];
+
+    // Menus borrow every keyboard player's directions and swap, so the results
+    // screen that opens mid-game answers to the same keys the boards did.
+    fn player_action(self) -> Option<KeyAction> {
+        match self {
+            MenuAction::Up => Some(KeyAction::Up),
+            MenuAction::Down => Some(KeyAction::Down),
+            MenuAction::Left => Some(KeyAction::Left),
+            MenuAction::Right => Some(KeyAction::Right),
+            MenuAction::Confirm => Some(KeyAction::Swap),
+            MenuAction::Cancel => None,
+        }
+    }
+
+    fn fixed_keys(self) -> &'static [KeyCode] {
+        match self {
+            MenuAction::Confirm => &[KeyCode::Enter],
+            MenuAction::Cancel => &[KeyCode::Escape, KeyCode::Backspace],
+            _ => &[],
+        }
+    }
+}
+
+pub fn menu_keys(layouts: &[(PlayerId, PlayerKeys)], action: MenuAction) -> Vec<KeyCode> {
+    let borrowed = action
+        .player_action()
+        .into_iter()
+        .flat_map(|player_action| {
+            layouts
+                .iter()
+                .filter_map(move |(_, keys)| keys.key(player_action))
+        });
+    action
+        .fixed_keys()
+        .iter()
+        .copied()
+        .chain(borrowed)
+        .collect()
+}
+
+// Keys that do something mid-match whoever presses them, so no player's
+// action may sit on one.
+pub const RESERVED: &[(KeyCode, &str)] = &[
+    (KeyCode::Escape, "pause"),
+    (KeyCode::Tab, "pause"),
+    (KeyCode::Backspace, "pause"),
+    (KeyCode::Enter, "fullscreen with Alt"),
+    (KeyCode::AltLeft, "fullscreen with Enter"),
+    (KeyCode::AltRight, "fullscreen with Enter"),
+    (KeyCode::F2, "observer view"),
+    (KeyCode::F3, "debug overlay"),
+    (KeyCode::F4, "debug frame hold"),
+    (KeyCode::F5, "debug frame step"),
+    (KeyCode::F9, "input trace"),
+    (KeyCode::F11, "clip"),
+    (KeyCode::F12, "screenshot"),
+    (KeyCode::Backquote, "debug console"),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Conflict {
+    Shared {
+        key: KeyCode,
+        first: (PlayerId, KeyAction),
+        second: (PlayerId, KeyAction),
+    },
+    Reserved {
+        key: KeyCode,
+        binding: (PlayerId, KeyAction),
+        used_for: &'static str,
+    },
+    Menu {
+        key: KeyCode,
+        binding: (PlayerId, KeyAction),
+        menu: MenuAction,
+    },
+}
+
+// Every key a remapping has to reject: one key on two actions, whether they
+// belong to the same player or to both sharing the keyboard, any action on a
+// key the match itself listens to, and any action whose key would do something
+// else in a menu.
+pub fn conflicts(layouts: &[(PlayerId, PlayerKeys)]) -> Vec<Conflict> {
+    let bound: Vec<(KeyCode, (PlayerId, KeyAction))> = layouts
+        .iter()
+        .flat_map(|(id, keys)| {
+            KeyAction::ALL
+                .iter()
+                .filter_map(move |action| Some((keys.key(*action)?, (*id, *action))))
+        })
+        .collect();
+    let mut found = Vec::new();
+    for (i, (key, first)) in bound.iter().enumerate() {
+        if let Some((_, used_for)) = RESERVED.iter().find(|(reserved, _)| reserved == key) {
+            found.push(Conflict::Reserved {
+                key: *key,
+                binding: *first,
+                used_for,
+            });
+        }
+        for menu in MenuAction::ALL {
+            if menu.player_action() != Some(first.1) && menu_keys(layouts, menu).contains(key) {
+                found.push(Conflict::Menu {
+                    key: *key,
+                    binding: *first,
+                    menu,
+                });
+            }
+        }
+        for (other, second) in &bound[i + 1..] {
+            if other == key {
+                found.push(Conflict::Shared {
+                    key: *key,
+                    first: *first,
+                    second: *second,
+                });
+            }
+        }
+    }
+    found
+}
+
+pub fn default_layouts() -> [(PlayerId, PlayerKeys); 2] {
+    [(PlayerId::P1, P1_KEYS), (PlayerId::P2, P2_KEYS)]
+}
+
+pub fn audit_bindings() {
+    for conflict in conflicts(&default_layouts()) {
+        match conflict {
+            Conflict::Shared { key, first, second } => warn!(
+                "{key:?} is bound to both {} {:?} and {} {:?}",
+                first.0.label(),
+                first.1,
+                second.0.label(),
+                second.1
+            ),
+            Conflict::Reserved {
+                key,
+                binding,
+                used_for,
+            } => warn!(
+                "{key:?} is bound to {} {:?} but also triggers {used_for}",
+                binding.0.label(),
+                binding.1
+            ),
+            Conflict::Menu { key, binding, menu } => warn!(
+                "{key:?} is bound to {} {:?} but means {menu:?} in menus",
+                binding.0.label(),
+                binding.1
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_bindings_never_overlap() {
+        assert_eq!(conflicts(&default_layouts()), Vec::new());
+    }
+
+    #[test]
+    fn menus_follow_both_players_keys() {
+        let layouts = default_layouts();
+        assert_eq!(
+            menu_keys(&layouts, MenuAction::Up),
+            [KeyCode::ArrowUp, KeyCode::KeyW]
+        );
+        assert_eq!(
+            menu_keys(&layouts, MenuAction::Confirm),
+            [KeyCode::Enter, KeyCode::Space, KeyCode::ShiftLeft]
+        );
+        let p2 = PlayerKeys {
+            up: KeyCode::KeyI,
+            ..P2_KEYS
+        };
+        let layouts = [(PlayerId::P1, P1_KEYS), (PlayerId::P2, p2)];
+        assert_eq!(
+            menu_keys(&layouts, MenuAction::Up),
+            [KeyCode::ArrowUp, KeyCode::KeyI]
+        );
+    }
+
+    #[test]
+    fn shared_and_reserved_keys_are_reported() {
+        let p2 = PlayerKeys {
+            swap: KeyCode::Space,
+            rotate: KeyCode::Tab,
+            ..P2_KEYS
+        };
+        let found = conflicts(&[(PlayerId::P1, P1_KEYS), (PlayerId::P2, p2)]);
+        assert_eq!(
+            found,
+            vec![
+                Conflict::Shared {
+                    key: KeyCode::Space,
+                    first: (PlayerId::P1, KeyAction::Swap),
+                    second: (PlayerId::P2, KeyAction::Swap),
+                },
+                Conflict::Reserved {
+                    key: KeyCode::Tab,
+                    binding: (PlayerId::P2, KeyAction::Rotate),
+                    used_for: "pause",
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn keys_that_mean_something_else_in_menus_are_reported() {
+        let p2 = PlayerKeys {
+            dash: KeyCode::Enter,
+            ..P2_KEYS
+        };
+        let found = conflicts(&[(PlayerId::P1, P1_KEYS), (PlayerId::P2, p2)]);
+        assert!(found.contains(&Conflict::Menu {
+            key: KeyCode::Enter,
+            binding: (PlayerId::P2, KeyAction::Dash),
+            menu: MenuAction::Confirm,
+        }));
+    }
+}
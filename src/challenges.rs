@@ -0,0 +1,455 @@
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::game::Block;
+use crate::garbage::GarbageRules;
+use crate::locale::Locale;
+use crate::menu::{Menu, MenuEntry, MenuEvent, MenuPlugin, MenuSet};
+use crate::palette::Palette;
+use crate::profiles::Profiles;
+use crate::{
+    AppState, ClearEvent, CpuControl, GRID_H, GRID_W, GameEntity, GameMode, GameSet, MatchOver,
+    MatchOverTimer, MatchRules, PlayerId, PlayerPhase, PlayerState, Players, SoloMode,
+    local_start_state,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+const RECORDS_FILE: &str = "challenges.json";
+const MAX_STARS: u8 = 3;
+const MIN_BOARD_SIDE: usize = 3;
+
+pub struct ChallengesPlugin;
+
+impl Plugin for ChallengesPlugin {
+    fn build(&self, app: &mut App) {
+        let records = ChallengeRecords::load(app.world().resource::<Profiles>());
+        app.insert_resource(records)
+            .init_resource::<ChallengeRun>()
+            .add_plugins(MenuPlugin::<ChallengeItem>::default())
+            .add_systems(OnEnter(AppState::Challenges), setup_select)
+            .add_systems(OnExit(AppState::Challenges), cleanup_select)
+            .add_systems(
+                Update,
+                handle_select_input
+                    .after(MenuSet)
+                    .run_if(in_state(AppState::Challenges)),
+            )
+            .add_systems(
+                Update,
+                (track_challenge, update_banner)
+                    .chain()
+                    .in_set(GameSet::Presentation),
+            );
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Goal {
+    Chain { length: u32 },
+    ClearBlocks { count: u32 },
+    ClearGarbage,
+    ClearStones,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Challenge {
+    id: String,
+    #[serde(default)]
+    pub speed: u32,
+    #[serde(default)]
+    garbage_rows: usize,
+    #[serde(default)]
+    stones: Vec<[usize; 2]>,
+    #[serde(default)]
+    width: Option<usize>,
+    #[serde(default)]
+    height: Option<usize>,
+    #[serde(default)]
+    time_limit: Option<f32>,
+    goal: Goal,
+    stars: [f32; 2],
+}
+
+impl Challenge {
+    fn describe(&self, locale: &Locale) -> String {
+        let goal = match self.goal {
+            Goal::Chain { length } => locale.format("challenge.chain", &[("length", &length)]),
+            Goal::ClearBlocks { count } => {
+                locale.format("challenge.clear_blocks", &[("count", &count)])
+            }
+            Goal::ClearGarbage => locale.get("challenge.clear_garbage").to_string(),
+            Goal::ClearStones => locale.get("challenge.clear_stones").to_string(),
+        };
+        match self.time_limit {
+            Some(seconds) => locale.format(
+                "challenge.within",
+                &[("goal", &goal), ("seconds", &seconds)],
+            ),
+            None => goal,
+        }
+    }
+
+    pub fn board_size(&self) -> (usize, usize) {
+        (
+            self.width.unwrap_or(GRID_W).max(MIN_BOARD_SIDE),
+            self.height.unwrap_or(GRID_H).max(MIN_BOARD_SIDE),
+        )
+    }
+
+    fn stars_for(&self, seconds: f32) -> u8 {
+        let bonus = self.stars.iter().filter(|limit| seconds <= **limit).count();
+        1 + bonus as u8
+    }
+
+    fn progress(&self, player: &PlayerState) -> String {
+        match self.goal {
+            Goal::ClearBlocks { count } => format!("{}/{count}", player.blocks_cleared.min(count)),
+            Goal::Chain { .. } | Goal::ClearGarbage | Goal::ClearStones => String::new(),
+        }
+    }
+}
+
+pub fn all() -> &'static [Challenge] {
+    static CHALLENGES: OnceLock<Vec<Challenge>> = OnceLock::new();
+    CHALLENGES.get_or_init(|| {
+        serde_json::from_str(include_str!("../assets/challenges.json")).unwrap_or_else(|err| {
+            warn!("ignoring broken challenge list: {err}");
+            Vec::new()
+        })
+    })
+}
+
+pub fn prepare(challenge: &Challenge, player: &mut PlayerState) {
+    for &[x, y] in &challenge.stones {
+        if player.grid.in_bounds(x, y) {
+            player.grid.set(x, y, Some(Block::Stone { cracked: false }));
+        }
+    }
+    let rows = vec![vec![true; player.grid.width]; challenge.garbage_rows];
+    if !rows.is_empty() && player.grid.insert_garbage_rows_from_top(&rows) {
+        player.phase = PlayerPhase::DroppingGarbage;
+    }
+}
+
+pub fn stars_label(stars: u8) -> String {
+    let stars = stars.min(MAX_STARS) as usize;
+    format!(
+        "{}{}",
+        "*".repeat(stars),
+        "-".repeat(MAX_STARS as usize - stars)
+    )
+}
+
+#[derive(Resource, Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChallengeRecords {
+    best: BTreeMap<String, u8>,
+}
+
+impl ChallengeRecords {
+    fn stars(&self, challenge: &Challenge) -> u8 {
+        self.best(&challenge.id)
+    }
+
+    pub fn best(&self, id: &str) -> u8 {
+        self.best.get(id).copied().unwrap_or(0)
+    }
+
+    pub fn record(&mut self, id: &str, stars: u8, profiles: &Profiles) {
+        if stars > self.best(id) {
+            self.best.insert(id.to_string(), stars);
+            self.save(profiles);
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(profiles: &Profiles) -> Self {
+        let path = profiles.dir().join(RECORDS_FILE);
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|err| {
+                warn!(
+                    "ignoring unreadable challenge records {}: {err}",
+                    path.display()
+                );
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn load(_profiles: &Profiles) -> Self {
+        Self::default()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save(&self, profiles: &Profiles) {
+        let path = profiles.dir().join(RECORDS_FILE);
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let Ok(contents) = serde_json::to_string_pretty(self) else {
+            return;
+        };
+        if let Err(err) = std::fs::write(&path, contents) {
+            warn!(
+                "could not save challenge records to {}: {err}",
+                path.display()
+            );
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn save(&self, _profiles: &Profiles) {}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ChallengeResult {
+    Passed(u8),
+    Failed,
+}
+
+#[derive(Resource, Default)]
+struct ChallengeRun {
+    result: Option<ChallengeResult>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct ChallengeItem(usize);
+
+#[derive(Resource)]
+struct SelectRoot(Entity);
+
+#[derive(Component)]
+struct ChallengeBanner;
+
+fn active_challenge(rules: &MatchRules, mode: GameMode) -> Option<&'static Challenge> {
+    match rules.solo {
+        SoloMode::Challenge(index) if mode == GameMode::OnePlayer => all().get(index),
+        _ => None,
+    }
+}
+
+fn setup_select(
+    mut commands: Commands,
+    records: Res<ChallengeRecords>,
+    palette: Res<Palette>,
+    locale: Res<Locale>,
+) {
+    let root = commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(0.0),
+                top: Val::Percent(0.0),
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(16.0),
+                ..Default::default()
+            },
+            background_color: BackgroundColor(palette.overlay),
+            ..Default::default()
+        })
+        .id();
+
+    commands.entity(root).with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            locale.get("challenge.title"),
+            TextStyle {
+                font: Default::default(),
+                font_size: 42.0,
+                color: palette.heading,
+            },
+        ));
+
+        let entries = all()
+            .iter()
+            .enumerate()
+            .map(|(index, challenge)| {
+                MenuEntry::new(
+                    ChallengeItem(index),
+                    format!(
+                        "{}  {}",
+                        challenge.describe(&locale),
+                        stars_label(records.stars(challenge))
+                    ),
+                )
+            })
+            .collect();
+        parent.spawn((
+            TextBundle {
+                text: Text::default().with_justify(JustifyText::Center),
+                ..Default::default()
+            },
+            Menu::new(entries),
+        ));
+
+        parent.spawn(TextBundle {
+            text: Text::from_section(
+                locale.get("challenge.hint"),
+                TextStyle {
+                    font: Default::default(),
+                    font_size: 18.0,
+                    color: palette.text,
+                },
+            )
+            .with_justify(JustifyText::Center),
+            ..Default::default()
+        });
+    });
+
+    commands.insert_resource(SelectRoot(root));
+}
+
+fn cleanup_select(mut commands: Commands, root: Res<SelectRoot>) {
+    commands.entity(root.0).despawn_recursive();
+    commands.remove_resource::<SelectRoot>();
+}
+
+fn handle_select_input(
+    mut events: EventReader<MenuEvent<ChallengeItem>>,
+    gamepads: Res<Gamepads>,
+    mut mode: ResMut<GameMode>,
+    mut cpu: ResMut<CpuControl>,
+    mut rules: ResMut<MatchRules>,
+    mut garbage_rules: ResMut<GarbageRules>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for event in events.read() {
+        match *event {
+            MenuEvent::Confirm(ChallengeItem(index)) => {
+                *mode = GameMode::OnePlayer;
+                *cpu = CpuControl::default();
+                rules.versus = None;
+                rules.solo = SoloMode::Challenge(index);
+                *garbage_rules = GarbageRules::default();
+                next_state.set(local_start_state(&gamepads));
+            }
+            MenuEvent::Cancel => next_state.set(AppState::Title),
+            MenuEvent::Adjust(..) => {}
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn track_challenge(
+    mut clears: EventReader<ClearEvent>,
+    players: Res<Players>,
+    rules: Res<MatchRules>,
+    mode: Res<GameMode>,
+    profiles: Res<Profiles>,
+    mut match_over: ResMut<MatchOver>,
+    mut match_over_timer: ResMut<MatchOverTimer>,
+    mut records: ResMut<ChallengeRecords>,
+    mut run: ResMut<ChallengeRun>,
+) {
+    let longest_chain = clears
+        .read()
+        .filter(|event| event.player == PlayerId::P1)
+        .map(|event| event.chain)
+        .max()
+        .unwrap_or(0);
+    let Some(challenge) = active_challenge(&rules, *mode) else {
+        return;
+    };
+    if !match_over.active {
+        run.result = None;
+    } else {
+        if run.result.is_none() {
+            run.result = Some(ChallengeResult::Failed);
+        }
+        return;
+    }
+    let player = &players.p1;
+    let passed = match challenge.goal {
+        Goal::Chain { length } => longest_chain >= length,
+        Goal::ClearBlocks { count } => player.blocks_cleared >= count,
+        Goal::ClearGarbage => player.phase == PlayerPhase::Idle && !player.grid.has_garbage(),
+        Goal::ClearStones => player.phase == PlayerPhase::Idle && !player.grid.has_stones(),
+    };
+    let result = if passed {
+        ChallengeResult::Passed(challenge.stars_for(player.elapsed))
+    } else if challenge
+        .time_limit
+        .is_some_and(|limit| player.elapsed >= limit)
+    {
+        ChallengeResult::Failed
+    } else {
+        return;
+    };
+    run.result = Some(result);
+    match_over.active = true;
+    match_over.winner = passed.then_some(PlayerId::P1);
+    match_over_timer.seconds = 0.0;
+    if let ChallengeResult::Passed(stars) = result {
+        records.record(&challenge.id, stars, &profiles);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_banner(
+    mut commands: Commands,
+    players: Res<Players>,
+    rules: Res<MatchRules>,
+    mode: Res<GameMode>,
+    run: Res<ChallengeRun>,
+    palette: Res<Palette>,
+    locale: Res<Locale>,
+    mut banners: Query<&mut Text, With<ChallengeBanner>>,
+) {
+    let Some(challenge) = active_challenge(&rules, *mode) else {
+        return;
+    };
+    let Ok(mut text) = banners.get_single_mut() else {
+        commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        top: Val::Px(12.0),
+                        width: Val::Percent(100.0),
+                        justify_content: JustifyContent::Center,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                GameEntity,
+            ))
+            .with_children(|parent| {
+                parent.spawn((
+                    TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font: Default::default(),
+                            font_size: 24.0,
+                            color: palette.heading,
+                        },
+                    )
+                    .with_text_justify(JustifyText::Center),
+                    ChallengeBanner,
+                ));
+            });
+        return;
+    };
+    let value = match run.result {
+        Some(ChallengeResult::Passed(stars)) => {
+            locale.format("challenge.passed", &[("stars", &stars_label(stars))])
+        }
+        Some(ChallengeResult::Failed) => locale.get("challenge.failed").to_string(),
+        None => format!(
+            "{}  {}",
+            challenge.describe(&locale),
+            challenge.progress(&players.p1)
+        ),
+    };
+    if text.sections[0].value != value {
+        text.sections[0].value = value;
+    }
+}
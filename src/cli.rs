@@ -0,0 +1,218 @@
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+
+use crate::{GameMode, PlayerId, SoloMode};
+
+const USAGE: &str = "usage: tetanus-attack [--mode 1p|2p|cpu|bots|survival|marathon] [--seed N] [--speed LEVEL] \
+[--deadzone 0..1] [--no-rumble] [--voice-pack NAME] [--headless [--frames N] [--games N] | --tui] [--bot-think FRAMES[,FRAMES]] [--play-inputs FILE] \
+[--audience CHANNEL [--audience-server HOST:PORT]]";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LaunchMode {
+    OnePlayer,
+    TwoPlayer,
+    Cpu,
+    Bots,
+    Survival,
+    Marathon,
+}
+
+impl LaunchMode {
+    pub fn game_mode(self) -> GameMode {
+        match self {
+            LaunchMode::OnePlayer | LaunchMode::Survival | LaunchMode::Marathon => {
+                GameMode::OnePlayer
+            }
+            LaunchMode::TwoPlayer | LaunchMode::Cpu | LaunchMode::Bots => GameMode::TwoPlayer,
+        }
+    }
+
+    pub fn solo_mode(self) -> SoloMode {
+        match self {
+            LaunchMode::Survival => SoloMode::Survival,
+            LaunchMode::Marathon => SoloMode::Marathon,
+            _ => SoloMode::Endless,
+        }
+    }
+
+    pub fn cpu_players(self) -> &'static [PlayerId] {
+        match self {
+            LaunchMode::Cpu => &[PlayerId::P2],
+            LaunchMode::Bots => &[PlayerId::P1, PlayerId::P2],
+            _ => &[],
+        }
+    }
+}
+
+#[derive(Resource, Clone, Debug, Default)]
+pub struct LaunchOptions {
+    pub mode: Option<LaunchMode>,
+    pub seed: Option<u64>,
+    pub speed: Option<u32>,
+    pub deadzone: Option<f32>,
+    pub no_rumble: bool,
+    pub voice_pack: Option<String>,
+    pub headless: bool,
+    pub tui: bool,
+    pub frames: Option<u64>,
+    pub games: Option<u32>,
+    pub bot_think: Option<[u64; 2]>,
+    pub play_inputs: Option<PathBuf>,
+    pub audience: Option<String>,
+    pub audience_server: Option<String>,
+}
+
+impl LaunchOptions {
+    pub fn from_env() -> Self {
+        match Self::parse(std::env::args().skip(1)) {
+            Ok(Some(options)) => options,
+            Ok(None) => {
+                println!("{USAGE}");
+                std::process::exit(0);
+            }
+            Err(message) => {
+                eprintln!("{message}\n{USAGE}");
+                std::process::exit(2);
+            }
+        }
+    }
+
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Option<Self>, String> {
+        let mut options = LaunchOptions::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+                None => (arg, None),
+            };
+            let mut value = || {
+                inline
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| format!("{flag} needs a value"))
+            };
+            match flag.as_str() {
+                "--help" | "-h" => return Ok(None),
+                "--headless" => options.headless = true,
+                "--tui" => options.tui = true,
+                "--no-rumble" => options.no_rumble = true,
+                "--mode" => {
+                    options.mode = Some(match value()?.as_str() {
+                        "1p" => LaunchMode::OnePlayer,
+                        "2p" => LaunchMode::TwoPlayer,
+                        "cpu" => LaunchMode::Cpu,
+                        "bots" => LaunchMode::Bots,
+                        "survival" => LaunchMode::Survival,
+                        "marathon" => LaunchMode::Marathon,
+                        other => return Err(format!("unknown mode `{other}`")),
+                    });
+                }
+                "--seed" => options.seed = Some(parse_number(&flag, &value()?)?),
+                "--speed" => options.speed = Some(parse_number(&flag, &value()?)?),
+                "--deadzone" => {
+                    let deadzone: f32 = parse_number(&flag, &value()?)?;
+                    if !(0.0..1.0).contains(&deadzone) {
+                        return Err(format!("--deadzone must be in 0..1, got {deadzone}"));
+                    }
+                    options.deadzone = Some(deadzone);
+                }
+                "--voice-pack" => options.voice_pack = Some(value()?),
+                "--frames" => options.frames = Some(parse_number(&flag, &value()?)?),
+                "--games" => options.games = Some(parse_number(&flag, &value()?)?),
+                "--bot-think" => {
+                    let value = value()?;
+                    let (p1, p2) = value.split_once(',').unwrap_or((&value, &value));
+                    options.bot_think = Some([parse_number(&flag, p1)?, parse_number(&flag, p2)?]);
+                }
+                "--play-inputs" => options.play_inputs = Some(PathBuf::from(value()?)),
+                "--audience" => options.audience = Some(value()?),
+                "--audience-server" => options.audience_server = Some(value()?),
+                _ => return Err(format!("unknown argument `{flag}`")),
+            }
+        }
+        if options.frames.is_some() && !options.headless {
+            return Err("--frames only applies to --headless runs".to_string());
+        }
+        if options.games.is_some() && !options.headless {
+            return Err("--games only applies to --headless runs".to_string());
+        }
+        if options.headless && options.tui {
+            return Err("--headless and --tui can't be combined".to_string());
+        }
+        if options.audience_server.is_some() && options.audience.is_none() {
+            return Err("--audience-server needs --audience".to_string());
+        }
+        Ok(Some(options))
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{flag} expects a number, got `{value}`"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Option<LaunchOptions>, String> {
+        LaunchOptions::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn every_mode_parses() {
+        let modes = [
+            ("1p", LaunchMode::OnePlayer),
+            ("2p", LaunchMode::TwoPlayer),
+            ("cpu", LaunchMode::Cpu),
+            ("bots", LaunchMode::Bots),
+            ("survival", LaunchMode::Survival),
+            ("marathon", LaunchMode::Marathon),
+        ];
+        for (name, mode) in modes {
+            let options = parse(&["--mode", name]).unwrap().unwrap();
+            assert_eq!(options.mode, Some(mode));
+            let inline = parse(&[&format!("--mode={name}")]).unwrap().unwrap();
+            assert_eq!(inline.mode, Some(mode));
+        }
+        assert_eq!(parse(&["--mode", "3p"]).unwrap_err(), "unknown mode `3p`");
+    }
+
+    #[test]
+    fn flag_without_value_is_rejected() {
+        assert_eq!(parse(&["--seed"]).unwrap_err(), "--seed needs a value");
+    }
+
+    #[test]
+    fn unknown_flag_is_rejected() {
+        assert_eq!(
+            parse(&["--turbo"]).unwrap_err(),
+            "unknown argument `--turbo`"
+        );
+    }
+
+    #[test]
+    fn numbers_are_checked() {
+        assert_eq!(
+            parse(&["--seed", "seven"]).unwrap_err(),
+            "--seed expects a number, got `seven`"
+        );
+        assert_eq!(
+            parse(&["--headless", "--bot-think", "8,x"]).unwrap_err(),
+            "--bot-think expects a number, got `x`"
+        );
+        let options = parse(&["--headless", "--bot-think", "8,12", "--seed=7"])
+            .unwrap()
+            .unwrap();
+        assert_eq!(options.bot_think, Some([8, 12]));
+        assert_eq!(options.seed, Some(7));
+    }
+
+    #[test]
+    fn help_stops_parsing() {
+        assert!(parse(&["--help", "--turbo"]).unwrap().is_none());
+        assert!(parse(&["-h"]).unwrap().is_none());
+    }
+}
@@ -0,0 +1,195 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+
+use crate::palette::Palette;
+use crate::settings::data_dir;
+use crate::{AppState, GameMode, GameSet, Players, active_players};
+
+const CLIP_DIR: &str = "clips";
+const CLIP_FPS: u16 = 10;
+const CLIP_SECONDS: usize = 10;
+const CLIP_CELL_PX: usize = 12;
+const CLIP_GAP_PX: usize = 24;
+
+pub struct ClipsPlugin;
+
+impl Plugin for ClipsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ClipBuffer::default())
+            .add_systems(OnEnter(AppState::Title), clear_clip_buffer)
+            .add_systems(
+                Update,
+                (record_clip_frame, export_clip)
+                    .chain()
+                    .in_set(GameSet::Presentation),
+            );
+    }
+}
+
+struct BoardFrame {
+    width: usize,
+    height: usize,
+    pixels: Vec<[u8; 4]>,
+}
+
+struct ClipFrame {
+    elapsed: f32,
+    boards: Vec<BoardFrame>,
+}
+
+#[derive(Resource)]
+struct ClipBuffer {
+    frames: VecDeque<ClipFrame>,
+    timer: Timer,
+}
+
+impl Default for ClipBuffer {
+    fn default() -> Self {
+        Self {
+            frames: VecDeque::new(),
+            timer: Timer::from_seconds(1.0 / CLIP_FPS as f32, TimerMode::Repeating),
+        }
+    }
+}
+
+fn clear_clip_buffer(mut buffer: ResMut<ClipBuffer>) {
+    buffer.frames.clear();
+}
+
+// Boards are sampled as cell colours rather than read back from the GPU, so a clip
+// is just the boards re-rendered at a fixed cell size.
+fn record_clip_frame(
+    time: Res<Time>,
+    players: Res<Players>,
+    mode: Res<GameMode>,
+    palette: Res<Palette>,
+    mut buffer: ResMut<ClipBuffer>,
+) {
+    if !buffer.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let elapsed = players.p1.elapsed;
+    if buffer
+        .frames
+        .back()
+        .is_some_and(|last| last.elapsed > elapsed)
+    {
+        buffer.frames.clear();
+    }
+    let empty = rgba(palette.grid_cell);
+    let boards = active_players(*mode)
+        .iter()
+        .map(|id| {
+            let grid = &players.get(*id).grid;
+            let mut pixels = vec![empty; grid.width * grid.height];
+            for (x, y, block) in grid.iter_cells() {
+                if let Some(block) = block {
+                    pixels[(grid.height - 1 - y) * grid.width + x] = rgba(palette.cell(block));
+                }
+            }
+            BoardFrame {
+                width: grid.width,
+                height: grid.height,
+                pixels,
+            }
+        })
+        .collect();
+    buffer.frames.push_back(ClipFrame { elapsed, boards });
+    while buffer.frames.len() > CLIP_SECONDS * CLIP_FPS as usize {
+        buffer.frames.pop_front();
+    }
+}
+
+fn export_clip(keys: Res<ButtonInput<KeyCode>>, buffer: Res<ClipBuffer>, palette: Res<Palette>) {
+    if !keys.just_pressed(KeyCode::F11) || buffer.frames.is_empty() {
+        return;
+    }
+    let dir = data_dir().join(CLIP_DIR);
+    if let Err(err) = std::fs::create_dir_all(&dir) {
+        warn!("could not create {}: {err}", dir.display());
+        return;
+    }
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let path = dir.join(format!(
+        "tetanus-{}-{:03}.png",
+        now.as_secs(),
+        now.subsec_millis()
+    ));
+    match write_apng(&path, &buffer.frames, rgba(palette.background)) {
+        Ok(()) => info!(
+            "saved {} frame clip to {}",
+            buffer.frames.len(),
+            path.display()
+        ),
+        Err(err) => warn!("could not save clip to {}: {err}", path.display()),
+    }
+}
+
+fn write_apng(
+    path: &Path,
+    frames: &VecDeque<ClipFrame>,
+    background: [u8; 4],
+) -> Result<(), png::EncodingError> {
+    let boards = frames
+        .iter()
+        .map(|frame| frame.boards.len())
+        .max()
+        .unwrap_or(0);
+    let mut widths = vec![0; boards];
+    let mut height = 0;
+    for frame in frames {
+        for (index, board) in frame.boards.iter().enumerate() {
+            widths[index] = widths[index].max(board.width);
+            height = height.max(board.height);
+        }
+    }
+    let image_w = widths.iter().sum::<usize>() * CLIP_CELL_PX + CLIP_GAP_PX * (boards + 1);
+    let image_h = height * CLIP_CELL_PX + CLIP_GAP_PX * 2;
+
+    let mut encoder = png::Encoder::new(
+        BufWriter::new(File::create(path)?),
+        image_w as u32,
+        image_h as u32,
+    );
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_animated(frames.len() as u32, 0)?;
+    encoder.set_frame_delay(1, CLIP_FPS)?;
+    let mut writer = encoder.write_header()?;
+
+    let mut image = vec![0u8; image_w * image_h * 4];
+    for frame in frames {
+        for pixel in image.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&background);
+        }
+        let mut left = CLIP_GAP_PX;
+        for (board, board_w) in frame.boards.iter().zip(&widths) {
+            // Boards sit on the bottom edge, matching how a resized grid keeps its floor.
+            let top = CLIP_GAP_PX + (height - board.height) * CLIP_CELL_PX;
+            for (index, color) in board.pixels.iter().enumerate() {
+                let cell_x = left + index % board.width * CLIP_CELL_PX;
+                let cell_y = top + index / board.width * CLIP_CELL_PX;
+                for py in cell_y + 1..cell_y + CLIP_CELL_PX {
+                    let row = (py * image_w + cell_x + 1) * 4;
+                    for chunk in image[row..row + (CLIP_CELL_PX - 1) * 4].chunks_exact_mut(4) {
+                        chunk.copy_from_slice(color);
+                    }
+                }
+            }
+            left += board_w * CLIP_CELL_PX + CLIP_GAP_PX;
+        }
+        writer.write_image_data(&image)?;
+    }
+    writer.finish()
+}
+
+fn rgba(color: Color) -> [u8; 4] {
+    color.to_srgba().to_u8_array()
+}
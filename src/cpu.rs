@@ -0,0 +1,158 @@
+use bevy::prelude::*;
+use rand::prelude::*;
+
+use crate::game::{Cursor, Grid, SwapCmd};
+use crate::{
+    CommandQueue, CpuControl, GameMode, MatchOver, PlayerCommand, PlayerId, PlayerPhase, Players,
+    SimClock, active_players,
+};
+
+pub const CPU_THINK_FRAMES: u64 = 12;
+const CPU_ACTION_BUDGET: usize = 1;
+const BOT_SEED_SALTS: [u64; 2] = [0x626f_745f_7031, 0x626f_745f_7032];
+
+// What a bot gets to see each tick its board is idle. Everything is borrowed
+// read-only, so a bot can only act through the commands it returns.
+pub struct BoardSnapshot<'a> {
+    pub grid: &'a Grid,
+    pub cursor: Cursor,
+    pub frame: u64,
+}
+
+pub trait BotController: Send + Sync + 'static {
+    // Returns the cursor and swap commands to play this tick; anything past `budget`
+    // is dropped.
+    fn think(&mut self, board: &BoardSnapshot, budget: usize) -> Vec<PlayerCommand>;
+}
+
+#[derive(Resource)]
+pub struct CpuBots {
+    p1: Box<dyn BotController>,
+    p2: Box<dyn BotController>,
+}
+
+impl CpuBots {
+    pub fn new(p1: impl BotController, p2: impl BotController) -> Self {
+        Self {
+            p1: Box::new(p1),
+            p2: Box::new(p2),
+        }
+    }
+
+    // Each bot draws its thinking jitter from its own stream, so two bots on the
+    // same boards drift apart instead of mirroring each other.
+    pub fn greedy(think_frames: [u64; 2], seed: u64) -> Self {
+        Self::new(
+            GreedyBot::new(think_frames[0], seed ^ BOT_SEED_SALTS[0]),
+            GreedyBot::new(think_frames[1], seed ^ BOT_SEED_SALTS[1]),
+        )
+    }
+
+    fn get_mut(&mut self, id: PlayerId) -> &mut dyn BotController {
+        match id {
+            PlayerId::P1 => self.p1.as_mut(),
+            PlayerId::P2 => self.p2.as_mut(),
+        }
+    }
+}
+
+impl Default for CpuBots {
+    fn default() -> Self {
+        Self::greedy([CPU_THINK_FRAMES; 2], 0)
+    }
+}
+
+pub fn cpu_commands(
+    cpu: Res<CpuControl>,
+    mode: Res<GameMode>,
+    clock: Res<SimClock>,
+    players: Res<Players>,
+    match_over: Res<MatchOver>,
+    mut bots: ResMut<CpuBots>,
+    mut queue: ResMut<CommandQueue>,
+) {
+    if match_over.active {
+        return;
+    }
+    for id in active_players(*mode) {
+        let player = players.get(*id);
+        if !cpu.controls(*id) || player.phase != PlayerPhase::Idle {
+            continue;
+        }
+        let board = BoardSnapshot {
+            grid: &player.grid,
+            cursor: player.cursor,
+            frame: clock.frame,
+        };
+        let commands = bots.get_mut(*id).think(&board, CPU_ACTION_BUDGET);
+        queue.scheduled.entry(clock.frame).or_default().extend(
+            commands
+                .into_iter()
+                .take(CPU_ACTION_BUDGET)
+                .map(|command| (*id, command)),
+        );
+    }
+}
+
+// The built-in opponent: about every `think_frames` ticks, give or take a quarter,
+// it steps toward the first swap that makes a match, so a lower interval plays
+// faster.
+pub struct GreedyBot {
+    pub think_frames: u64,
+    last_think: Option<u64>,
+    wait: u64,
+    rng: StdRng,
+}
+
+impl GreedyBot {
+    pub fn new(think_frames: u64, seed: u64) -> Self {
+        Self {
+            think_frames,
+            last_think: None,
+            wait: 0,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl BotController for GreedyBot {
+    fn think(&mut self, board: &BoardSnapshot, _budget: usize) -> Vec<PlayerCommand> {
+        // The clock restarts with every match, so an earlier frame starts over.
+        if self
+            .last_think
+            .is_some_and(|last| board.frame >= last && board.frame - last < self.wait)
+        {
+            return Vec::new();
+        }
+        self.last_think = Some(board.frame);
+        let frames = self.think_frames.max(1);
+        let jitter = frames / 4;
+        self.wait = frames - jitter + self.rng.gen_range(0..=jitter * 2);
+        find_matching_swap(board.grid)
+            .map(|target| step_toward(board.cursor, target))
+            .into_iter()
+            .collect()
+    }
+}
+
+fn find_matching_swap(grid: &Grid) -> Option<Cursor> {
+    grid.iter_cells()
+        .filter(|&(x, _, _)| x + 1 < grid.width)
+        .find(|&(x, y, _)| {
+            let mut preview = grid.clone();
+            preview.swap_in_bounds(SwapCmd::right_of(x, y)) && preview.has_matches()
+        })
+        .map(|(x, y, _)| Cursor::new(x, y))
+}
+
+fn step_toward(cursor: Cursor, target: Cursor) -> PlayerCommand {
+    if cursor.y != target.y {
+        let dy = if target.y > cursor.y { 1 } else { -1 };
+        PlayerCommand::Move { dx: 0, dy }
+    } else if cursor.x != target.x {
+        let dx = if target.x > cursor.x { 1 } else { -1 };
+        PlayerCommand::Move { dx, dy: 0 }
+    } else {
+        PlayerCommand::Swap
+    }
+}
@@ -0,0 +1,213 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use rand::random;
+
+use crate::locale::Locale;
+use crate::palette::Palette;
+use crate::settings::Settings;
+use crate::{
+    GameEntity, GameMode, GameSet, MatchOver, MatchOverTimer, MatchResultEvent, PlayerId,
+    PlayerViews, RESULTS_MENU_DELAY, active_players, player_view,
+};
+
+const INTRO_SECONDS: f32 = 0.6;
+const BOB_HEIGHT: f32 = 6.0;
+const BOB_HZ: f32 = 0.8;
+const LOSER_ALPHA: f32 = 0.6;
+const OVERSHOOT: f32 = 1.7;
+// Nobody has a character yet, so every winner draws from the same quotes.
+const WIN_QUOTES: usize = 6;
+const DEFEAT_QUOTES: usize = 4;
+
+pub struct CutscenePlugin;
+
+impl Plugin for CutscenePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ResultQuote>().add_systems(
+            Update,
+            (start_cutscene, animate_cutscene, show_quote)
+                .chain()
+                .in_set(GameSet::Presentation),
+        );
+    }
+}
+
+#[derive(Resource, Default)]
+struct ResultQuote(Option<String>);
+
+#[derive(Component)]
+struct ResultLabel {
+    won: bool,
+    start: Vec3,
+    seconds: f32,
+}
+
+#[derive(Component)]
+struct QuoteText;
+
+fn quote_for(winner: Option<PlayerId>, locale: &Locale) -> String {
+    match winner {
+        Some(id) => {
+            let key = format!("quote.win.{}", random::<usize>() % WIN_QUOTES);
+            locale.format(
+                "cutscene.quote",
+                &[("player", &id.label()), ("quote", &locale.get(&key))],
+            )
+        }
+        None => locale
+            .get(&format!(
+                "quote.defeat.{}",
+                random::<usize>() % DEFEAT_QUOTES
+            ))
+            .to_string(),
+    }
+}
+
+fn start_cutscene(
+    mut commands: Commands,
+    mut results: EventReader<MatchResultEvent>,
+    mode: Res<GameMode>,
+    views: Res<PlayerViews>,
+    palette: Res<Palette>,
+    locale: Res<Locale>,
+    mut quote: ResMut<ResultQuote>,
+) {
+    for result in results.read() {
+        quote.0 = Some(quote_for(result.winner, &locale));
+        for &id in active_players(*mode) {
+            let Some(view) = player_view(&views, id) else {
+                continue;
+            };
+            let won = result.winner == Some(id);
+            let (key, color) = if won {
+                ("cutscene.win", palette.heading)
+            } else {
+                ("cutscene.lose", palette.warning)
+            };
+            let start = view.origin.extend(6.0);
+            commands.spawn((
+                Text2dBundle {
+                    text: Text::from_section(
+                        locale.get(key),
+                        TextStyle {
+                            font: Default::default(),
+                            font_size: 56.0,
+                            color,
+                        },
+                    ),
+                    transform: Transform::from_translation(start).with_scale(Vec3::ZERO),
+                    ..Default::default()
+                },
+                ResultLabel {
+                    won,
+                    start,
+                    seconds: 0.0,
+                },
+                GameEntity,
+            ));
+        }
+    }
+}
+
+// The labels pop in with a little overshoot; afterwards the winner's keeps
+// bobbing and the loser's dims.
+fn animate_cutscene(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<Settings>,
+    match_over: Res<MatchOver>,
+    mut labels: Query<(Entity, &mut ResultLabel, &mut Transform, &mut Text)>,
+) {
+    for (entity, mut label, mut transform, mut text) in &mut labels {
+        if !match_over.active {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        label.seconds += time.delta_seconds();
+        if settings.reduced_motion {
+            transform.scale = Vec3::ONE;
+            transform.translation = label.start;
+        } else {
+            let t = (label.seconds / INTRO_SECONDS).min(1.0);
+            transform.scale = Vec3::splat(back_out(t));
+            let bob = if label.won && t >= 1.0 {
+                (label.seconds * BOB_HZ * TAU).sin() * BOB_HEIGHT
+            } else {
+                0.0
+            };
+            transform.translation = label.start + Vec3::new(0.0, bob, 0.0);
+        }
+        if !label.won {
+            let t = (label.seconds / INTRO_SECONDS).min(1.0);
+            let color = text.sections[0].style.color;
+            text.sections[0].style.color = color.with_alpha(1.0 - (1.0 - LOSER_ALPHA) * t);
+        }
+    }
+}
+
+fn back_out(t: f32) -> f32 {
+    let t = t - 1.0;
+    1.0 + (OVERSHOOT + 1.0) * t * t * t + OVERSHOOT * t * t
+}
+
+// The quote waits for the results menu and sits just above it.
+fn show_quote(
+    mut commands: Commands,
+    match_over: Res<MatchOver>,
+    match_over_timer: Res<MatchOverTimer>,
+    quote: Res<ResultQuote>,
+    palette: Res<Palette>,
+    roots: Query<Entity, With<QuoteText>>,
+) {
+    let due = match_over.active && match_over_timer.seconds >= RESULTS_MENU_DELAY;
+    let Some(value) = quote.0.as_ref().filter(|_| due) else {
+        for root in &roots {
+            commands.entity(root).despawn_recursive();
+        }
+        return;
+    };
+    if !roots.is_empty() {
+        return;
+    }
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Percent(24.0),
+                    width: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            QuoteText,
+            GameEntity,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        padding: UiRect::all(Val::Px(10.0)),
+                        max_width: Val::Percent(60.0),
+                        ..Default::default()
+                    },
+                    background_color: BackgroundColor(palette.pause_overlay),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(
+                        TextBundle::from_section(
+                            value.clone(),
+                            TextStyle {
+                                font: Default::default(),
+                                font_size: 22.0,
+                                color: palette.text,
+                            },
+                        )
+                        .with_text_justify(JustifyText::Center),
+                    );
+                });
+        });
+}
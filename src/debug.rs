@@ -0,0 +1,325 @@
+use bevy::diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin};
+use bevy::input::ButtonState;
+use bevy::input::InputSystem;
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::prelude::*;
+
+use crate::game::Block;
+use crate::profiling;
+use crate::tuning::Tuning;
+use crate::{
+    GameMode, GameSet, PlayerId, PlayerPhase, PlayerState, Players, SimClock, active_players,
+    raise_stack_once, resize_board, set_rise_level,
+};
+
+const CONSOLE_HISTORY: usize = 6;
+const CONSOLE_HELP: &str = "commands: spawn <p1|p2> <x> <y> <R|G|B|Y|P|g|c|*|s|x>, garbage <p1|p2> <units>, rise <p1|p2>, speed <p1|p2> <level>, resize <p1|p2> <width> <height>";
+
+pub struct DebugPlugin;
+
+impl Plugin for DebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(FrameTimeDiagnosticsPlugin)
+            .init_resource::<DebugConsole>()
+            .add_systems(Startup, spawn_debug_ui)
+            .add_systems(PreUpdate, swallow_game_keys.after(InputSystem))
+            .add_systems(Update, (toggle_debug_overlay, update_debug_overlay).chain())
+            .add_systems(Update, frame_step_controls.in_set(GameSet::Input))
+            .add_systems(
+                Update,
+                (
+                    read_console_input.in_set(GameSet::Input),
+                    update_console_text.in_set(GameSet::Presentation),
+                ),
+            );
+    }
+}
+
+#[derive(Resource, Default)]
+struct DebugConsole {
+    open: bool,
+    input: String,
+    history: Vec<String>,
+}
+
+impl DebugConsole {
+    fn log(&mut self, line: String) {
+        self.history.push(line);
+        if self.history.len() > CONSOLE_HISTORY {
+            self.history.remove(0);
+        }
+    }
+}
+
+#[derive(Component)]
+struct DebugOverlayText;
+
+#[derive(Component)]
+struct DebugConsoleText;
+
+fn spawn_debug_ui(mut commands: Commands) {
+    let style = TextStyle {
+        font: Default::default(),
+        font_size: 14.0,
+        color: Color::srgb(0.6, 1.0, 0.6),
+    };
+    commands.spawn((
+        TextBundle {
+            text: Text::from_section("", style.clone()),
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(8.0),
+                top: Val::Px(8.0),
+                ..Default::default()
+            },
+            background_color: BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+            visibility: Visibility::Hidden,
+            z_index: ZIndex::Global(100),
+            ..Default::default()
+        },
+        DebugOverlayText,
+    ));
+    commands.spawn((
+        TextBundle {
+            text: Text::from_section("", style),
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(8.0),
+                bottom: Val::Px(8.0),
+                ..Default::default()
+            },
+            background_color: BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+            visibility: Visibility::Hidden,
+            z_index: ZIndex::Global(100),
+            ..Default::default()
+        },
+        DebugConsoleText,
+    ));
+}
+
+fn toggle_debug_overlay(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut overlay: Query<&mut Visibility, With<DebugOverlayText>>,
+) {
+    if !keys.just_pressed(KeyCode::F3) {
+        return;
+    }
+    for mut visibility in &mut overlay {
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Visible,
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+fn update_debug_overlay(
+    diagnostics: Res<DiagnosticsStore>,
+    players: Res<Players>,
+    mode: Res<GameMode>,
+    clock: Res<SimClock>,
+    mut overlay: Query<(&mut Text, &Visibility), With<DebugOverlayText>>,
+) {
+    let Ok((mut text, visibility)) = overlay.get_single_mut() else {
+        return;
+    };
+    if *visibility == Visibility::Hidden {
+        return;
+    }
+    let fps = diagnostics
+        .get(&FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.smoothed())
+        .unwrap_or_default();
+    let held = if clock.held {
+        "  FRAME STEP (F5 advance, F4 resume)"
+    } else {
+        ""
+    };
+    let mut lines = vec![format!("FPS {fps:.0}  tick {}{held}", clock.frame)];
+    let timings: Vec<String> = profiling::TIMED
+        .iter()
+        .map(|(label, path)| {
+            let millis = diagnostics
+                .get(path)
+                .and_then(|timing| timing.smoothed())
+                .unwrap_or_default();
+            format!("{label} {millis:.3}ms")
+        })
+        .collect();
+    lines.push(timings.join("  "));
+    for id in active_players(*mode) {
+        lines.push(player_debug_line(*id, players.get(*id)));
+    }
+    text.sections[0].value = lines.join("\n");
+}
+
+fn frame_step_controls(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut clock: ResMut<SimClock>,
+    mut overlay: Query<&mut Visibility, With<DebugOverlayText>>,
+) {
+    if keys.just_pressed(KeyCode::F4) {
+        clock.held = !clock.held;
+        clock.queued_steps = 0;
+        if clock.held {
+            for mut visibility in &mut overlay {
+                *visibility = Visibility::Visible;
+            }
+        }
+    }
+    if clock.held && keys.just_pressed(KeyCode::F5) {
+        clock.queued_steps += 1;
+    }
+}
+
+fn player_debug_line(id: PlayerId, player: &PlayerState) -> String {
+    let phase = if player.phase == PlayerPhase::Idle && player.rise_paused {
+        "rise paused"
+    } else {
+        player.phase.label()
+    };
+    format!(
+        "{} {phase:<11} level {} rise {:.2}/{:.2}s clear {:.2}s pause {:.2}s topped {:.2}s | chain {} x{} | garbage in {} out {}",
+        id.label(),
+        player.rise_level,
+        player.rise_timer.elapsed_secs(),
+        player.rise_timer.duration().as_secs_f32(),
+        player
+            .pending_clears
+            .front()
+            .map_or(0.0, |pending| pending.timer.elapsed_secs()),
+        player.rise_pause_timer.elapsed_secs(),
+        player.topped_seconds,
+        if player.chain_index > 0 { "on" } else { "off" },
+        player.chain_index,
+        player.garbage_incoming(),
+        player.garbage_outgoing,
+    )
+}
+
+fn swallow_game_keys(console: Res<DebugConsole>, mut keys: ResMut<ButtonInput<KeyCode>>) {
+    if console.open {
+        keys.reset_all();
+    }
+}
+
+fn read_console_input(
+    mut events: EventReader<KeyboardInput>,
+    mut console: ResMut<DebugConsole>,
+    mut players: ResMut<Players>,
+    mode: Res<GameMode>,
+    tuning: Res<Tuning>,
+) {
+    for event in events.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+        if event.key_code == KeyCode::Backquote {
+            console.open = !console.open;
+            console.input.clear();
+            continue;
+        }
+        if !console.open {
+            continue;
+        }
+        match &event.logical_key {
+            Key::Enter => {
+                let line = std::mem::take(&mut console.input);
+                let reply = run_command(&line, &mut players, *mode, &tuning);
+                console.log(format!("> {line}"));
+                console.log(reply);
+            }
+            Key::Backspace => {
+                console.input.pop();
+            }
+            Key::Escape => console.open = false,
+            Key::Space => console.input.push(' '),
+            Key::Character(chars) => console.input.push_str(chars),
+            _ => {}
+        }
+    }
+}
+
+fn run_command(line: &str, players: &mut Players, mode: GameMode, tuning: &Tuning) -> String {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let Some((command, args)) = words.split_first() else {
+        return CONSOLE_HELP.to_string();
+    };
+    let player_id = match args.first().map(|word| word.to_ascii_lowercase()) {
+        Some(word) if word == "p1" => PlayerId::P1,
+        Some(word) if word == "p2" && mode == GameMode::TwoPlayer => PlayerId::P2,
+        _ => return CONSOLE_HELP.to_string(),
+    };
+    let player = players.get_mut(player_id);
+    let number = |index: usize| args.get(index).and_then(|word| word.parse::<u32>().ok());
+    match (*command, args.len()) {
+        ("spawn", 4) => {
+            let (Some(x), Some(y)) = (number(1), number(2)) else {
+                return "spawn: x and y must be numbers".to_string();
+            };
+            let (x, y) = (x as usize, y as usize);
+            let Some(block) = args[3].chars().next().and_then(Block::from_symbol) else {
+                return "spawn: unknown block".to_string();
+            };
+            if !player.grid.in_bounds(x, y) {
+                return "spawn: out of bounds".to_string();
+            }
+            player.grid.set(x, y, Some(block));
+            player.phase = PlayerPhase::Falling;
+            format!("spawned {} at {x},{y}", block.symbol())
+        }
+        ("garbage", 2) => {
+            let Some(units) = number(1) else {
+                return "garbage: units must be a number".to_string();
+            };
+            player.garbage_queue.push_back(units);
+            format!("queued {units} garbage")
+        }
+        ("rise", 1) => {
+            if raise_stack_once(player) {
+                "rise: stack is topped out".to_string()
+            } else {
+                "raised stack".to_string()
+            }
+        }
+        ("speed", 2) => {
+            let Some(level) = number(1) else {
+                return "speed: level must be a number".to_string();
+            };
+            set_rise_level(player, level, tuning);
+            format!("speed level {level}")
+        }
+        ("resize", 3) => {
+            let (Some(width), Some(height)) = (number(1), number(2)) else {
+                return "resize: width and height must be numbers".to_string();
+            };
+            if width < 3 || height < 3 {
+                return "resize: board must be at least 3x3".to_string();
+            }
+            if !resize_board(player, width as usize, height as usize) {
+                return "resize: that would cut through garbage".to_string();
+            }
+            format!("board is now {width}x{height}")
+        }
+        _ => CONSOLE_HELP.to_string(),
+    }
+}
+
+fn update_console_text(
+    console: Res<DebugConsole>,
+    mut text_query: Query<(&mut Text, &mut Visibility), With<DebugConsoleText>>,
+) {
+    if !console.is_changed() {
+        return;
+    }
+    let Ok((mut text, mut visibility)) = text_query.get_single_mut() else {
+        return;
+    };
+    *visibility = if console.open {
+        Visibility::Visible
+    } else {
+        Visibility::Hidden
+    };
+    let mut lines = console.history.clone();
+    lines.push(format!("> {}_", console.input));
+    text.sections[0].value = lines.join("\n");
+}
@@ -0,0 +1,321 @@
+use bevy::input::gamepad::GamepadConnectionEvent;
+use bevy::prelude::*;
+
+use crate::locale::Locale;
+use crate::palette::Palette;
+use crate::settings::alt_held;
+use crate::{AppState, CpuControl, GameInitialized, GameMode, LocalControl, MatchOver, PlayerId};
+
+pub struct DevicesPlugin;
+
+impl Plugin for DevicesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlayerDevices>()
+            .init_resource::<LostController>()
+            .add_systems(OnEnter(AppState::Devices), setup_devices)
+            .add_systems(OnExit(AppState::Devices), cleanup_devices)
+            .add_systems(OnEnter(AppState::Game), assign_free_gamepads)
+            .add_systems(OnExit(AppState::Pause), forget_lost_controller)
+            .add_systems(
+                Update,
+                (handle_devices_input, refresh_devices_text)
+                    .chain()
+                    .run_if(in_state(AppState::Devices)),
+            )
+            .add_systems(Update, watch_gamepad_connections)
+            .add_systems(
+                Update,
+                take_over_lost_controller.run_if(in_state(AppState::Pause)),
+            );
+    }
+}
+
+#[derive(Resource, Default, Clone, Copy, Debug)]
+pub struct PlayerDevices {
+    pub p1: Option<Gamepad>,
+    pub p2: Option<Gamepad>,
+    confirmed: bool,
+}
+
+impl PlayerDevices {
+    pub fn get(&self, id: PlayerId) -> Option<Gamepad> {
+        match id {
+            PlayerId::P1 => self.p1,
+            PlayerId::P2 => self.p2,
+        }
+    }
+
+    fn slot_mut(&mut self, id: PlayerId) -> &mut Option<Gamepad> {
+        match id {
+            PlayerId::P1 => &mut self.p1,
+            PlayerId::P2 => &mut self.p2,
+        }
+    }
+
+    pub fn gamepad_for(&self, id: PlayerId, local: LocalControl) -> Option<Gamepad> {
+        match local.0 {
+            Some(local_id) if local_id == id => self.p1,
+            Some(_) => None,
+            None => self.get(id),
+        }
+    }
+
+    pub fn owner(&self, gamepad: Gamepad) -> Option<PlayerId> {
+        PlayerId::ALL
+            .into_iter()
+            .find(|id| self.get(*id) == Some(gamepad))
+    }
+
+    fn claim(&mut self, gamepad: Gamepad, slots: &[PlayerId]) {
+        if self.owner(gamepad).is_some() {
+            return;
+        }
+        if let Some(id) = slots.iter().find(|id| self.get(**id).is_none()) {
+            *self.slot_mut(*id) = Some(gamepad);
+        }
+    }
+
+    pub fn release(&mut self, gamepad: Gamepad) -> Option<PlayerId> {
+        let id = self.owner(gamepad)?;
+        *self.slot_mut(id) = None;
+        Some(id)
+    }
+}
+
+pub fn local_slots(mode: GameMode, local: LocalControl, cpu: CpuControl) -> &'static [PlayerId] {
+    if mode == GameMode::TwoPlayer && local.0.is_none() && !cpu.any() {
+        &[PlayerId::P1, PlayerId::P2]
+    } else {
+        &[PlayerId::P1]
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct LostController(pub Option<PlayerId>);
+
+#[derive(Resource)]
+struct DevicesScreen {
+    root: Entity,
+    list: Entity,
+}
+
+fn setup_devices(mut commands: Commands, palette: Res<Palette>, locale: Res<Locale>) {
+    let root = commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(0.0),
+                top: Val::Percent(0.0),
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(16.0),
+                ..Default::default()
+            },
+            background_color: BackgroundColor(palette.overlay),
+            z_index: ZIndex::Global(10),
+            ..Default::default()
+        })
+        .id();
+
+    let mut list = Entity::PLACEHOLDER;
+    commands.entity(root).with_children(|parent| {
+        parent.spawn(TextBundle {
+            text: Text::from_section(
+                locale.get("devices.title"),
+                TextStyle {
+                    font: Default::default(),
+                    font_size: 42.0,
+                    color: palette.heading,
+                },
+            ),
+            ..Default::default()
+        });
+
+        list = parent
+            .spawn(TextBundle {
+                text: Text::default().with_justify(JustifyText::Center),
+                ..Default::default()
+            })
+            .id();
+
+        parent.spawn(TextBundle {
+            text: Text::from_section(
+                locale.get("devices.hint"),
+                TextStyle {
+                    font: Default::default(),
+                    font_size: 18.0,
+                    color: palette.text,
+                },
+            )
+            .with_justify(JustifyText::Center),
+            ..Default::default()
+        });
+    });
+
+    commands.insert_resource(DevicesScreen { root, list });
+}
+
+fn cleanup_devices(mut commands: Commands, screen: Res<DevicesScreen>) {
+    commands.entity(screen.root).despawn_recursive();
+    commands.remove_resource::<DevicesScreen>();
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_devices_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    buttons: Res<ButtonInput<GamepadButton>>,
+    gamepads: Res<Gamepads>,
+    mode: Res<GameMode>,
+    local: Res<LocalControl>,
+    cpu: Res<CpuControl>,
+    initialized: Res<GameInitialized>,
+    mut devices: ResMut<PlayerDevices>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let slots = local_slots(*mode, *local, *cpu);
+    let mut start = (keys.just_pressed(KeyCode::Enter) && !alt_held(&keys))
+        || keys.just_pressed(KeyCode::Space);
+    let mut back = keys.just_pressed(KeyCode::Escape) || keys.just_pressed(KeyCode::Backspace);
+    for gamepad in gamepads.iter() {
+        if buttons.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::South)) {
+            devices.claim(gamepad, slots);
+        }
+        if buttons.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::East)) {
+            back |= devices.release(gamepad).is_none();
+        }
+        start |= buttons.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::Start));
+    }
+    if start {
+        devices.confirmed = true;
+        next_state.set(AppState::Game);
+    } else if back {
+        next_state.set(if initialized.0 {
+            AppState::Pause
+        } else {
+            AppState::Title
+        });
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn refresh_devices_text(
+    screen: Res<DevicesScreen>,
+    devices: Res<PlayerDevices>,
+    gamepads: Res<Gamepads>,
+    mode: Res<GameMode>,
+    local: Res<LocalControl>,
+    cpu: Res<CpuControl>,
+    palette: Res<Palette>,
+    locale: Res<Locale>,
+    mut text_query: Query<&mut Text>,
+) {
+    let Ok(mut text) = text_query.get_mut(screen.list) else {
+        return;
+    };
+    text.sections = local_slots(*mode, *local, *cpu)
+        .iter()
+        .map(|id| {
+            let device = match devices.get(*id) {
+                Some(gamepad) => locale.format(
+                    "devices.pad",
+                    &[
+                        (
+                            "name",
+                            &gamepads
+                                .name(gamepad)
+                                .unwrap_or(locale.get("devices.unnamed")),
+                        ),
+                        ("id", &gamepad.id),
+                    ],
+                ),
+                None => locale.get("devices.keyboard").to_string(),
+            };
+            TextSection::new(
+                format!("{}  {device}\n", id.label()),
+                TextStyle {
+                    font: Default::default(),
+                    font_size: 28.0,
+                    color: palette.menu_item(devices.get(*id).is_some()),
+                },
+            )
+        })
+        .collect();
+}
+
+fn assign_free_gamepads(
+    gamepads: Res<Gamepads>,
+    mode: Res<GameMode>,
+    local: Res<LocalControl>,
+    cpu: Res<CpuControl>,
+    mut devices: ResMut<PlayerDevices>,
+) {
+    if devices.confirmed {
+        return;
+    }
+    let slots = local_slots(*mode, *local, *cpu);
+    for gamepad in gamepads.iter() {
+        devices.claim(gamepad, slots);
+    }
+}
+
+fn watch_gamepad_connections(
+    mut events: EventReader<GamepadConnectionEvent>,
+    state: Res<State<AppState>>,
+    local: Res<LocalControl>,
+    match_over: Res<MatchOver>,
+    mut devices: ResMut<PlayerDevices>,
+    mut lost: ResMut<LostController>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for event in events.read() {
+        if event.connected() {
+            let Some(id) = lost.0 else {
+                continue;
+            };
+            if devices.owner(event.gamepad).is_none() {
+                *devices.slot_mut(id) = Some(event.gamepad);
+                lost.0 = None;
+                if *state.get() == AppState::Pause {
+                    next_state.set(AppState::Game);
+                }
+            }
+            continue;
+        }
+        let Some(id) = devices.release(event.gamepad) else {
+            continue;
+        };
+        if *state.get() == AppState::Game && local.0.is_none() && !match_over.active {
+            lost.0 = Some(id);
+            next_state.set(AppState::Pause);
+        }
+    }
+}
+
+fn take_over_lost_controller(
+    buttons: Res<ButtonInput<GamepadButton>>,
+    gamepads: Res<Gamepads>,
+    mut devices: ResMut<PlayerDevices>,
+    mut lost: ResMut<LostController>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let Some(id) = lost.0 else {
+        return;
+    };
+    for gamepad in gamepads.iter() {
+        if devices.owner(gamepad).is_none()
+            && buttons.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::South))
+        {
+            *devices.slot_mut(id) = Some(gamepad);
+            lost.0 = None;
+            next_state.set(AppState::Game);
+            return;
+        }
+    }
+}
+
+fn forget_lost_controller(mut lost: ResMut<LostController>) {
+    lost.0 = None;
+}
@@ -0,0 +1,71 @@
+use bevy::prelude::*;
+
+use crate::{GameMode, MatchOver, MatchRules, Players};
+
+const RECENT_SECONDS: f32 = 30.0;
+const HEAVY_DAMAGE_UNITS: u32 = 12;
+const EASE_PER_STRUGGLE: f32 = 0.08;
+const PUSH_PER_CHAIN: f32 = 0.05;
+const MIN_RISE_SCALE: f32 = 0.75;
+const MAX_RISE_SCALE: f32 = 1.3;
+const SCALE_PER_SECOND: f32 = 0.05;
+
+#[derive(Resource, Default)]
+pub struct AdaptiveDifficulty {
+    elapsed: f32,
+    in_danger: bool,
+    garbage_received: u32,
+    chains: u32,
+    struggles: Vec<f32>,
+    chain_times: Vec<f32>,
+}
+
+impl AdaptiveDifficulty {
+    fn target_scale(&self) -> f32 {
+        let eased = self.struggles.len() as f32 * EASE_PER_STRUGGLE;
+        let pushed = self.chain_times.len() as f32 * PUSH_PER_CHAIN;
+        (1.0 - eased + pushed).clamp(MIN_RISE_SCALE, MAX_RISE_SCALE)
+    }
+}
+
+// Looks back over the last half minute of a solo game: every big garbage drop or
+// fresh trip into the danger rows eases the rise a little, and every x2+ chain
+// tightens it again. The rise speed drifts toward that target rather than jumping.
+pub fn adapt_difficulty(
+    mut players: ResMut<Players>,
+    rules: Res<MatchRules>,
+    mode: Res<GameMode>,
+    match_over: Res<MatchOver>,
+    mut state: ResMut<AdaptiveDifficulty>,
+) {
+    if !rules.adaptive_difficulty || *mode != GameMode::OnePlayer || match_over.active {
+        return;
+    }
+    let player = &mut players.p1;
+    let now = player.elapsed;
+    if now < state.elapsed {
+        *state = AdaptiveDifficulty::default();
+    }
+    let step = now - state.elapsed;
+    state.elapsed = now;
+
+    if player.garbage_received >= state.garbage_received + HEAVY_DAMAGE_UNITS {
+        state.struggles.push(now);
+    }
+    state.garbage_received = player.garbage_received;
+    if player.in_danger && !state.in_danger {
+        state.struggles.push(now);
+    }
+    state.in_danger = player.in_danger;
+    let chains = player.chain_counts[1..].iter().sum();
+    if chains > state.chains {
+        state.chain_times.push(now);
+    }
+    state.chains = chains;
+
+    state.struggles.retain(|time| now - time < RECENT_SECONDS);
+    state.chain_times.retain(|time| now - time < RECENT_SECONDS);
+    let target = state.target_scale();
+    let max_step = SCALE_PER_SECOND * step;
+    player.rise_scale += (target - player.rise_scale).clamp(-max_step, max_step);
+}
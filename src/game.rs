@@ -1,5 +1,14 @@
+use std::collections::VecDeque;
+
+use bevy::log::info_span;
 use bevy::prelude::Resource;
 use rand::prelude::*;
+use rand::rngs::StdRng;
+
+pub const MAX_COLORS: u8 = 5;
+const REVEAL_STEPS: u8 = 2;
+const SWAP_LOCK_TICKS: u8 = 4;
+const GARBAGE_SEED_SALT: u64 = 0x6761_7262_6167_6521;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum BlockColor {
@@ -10,23 +19,119 @@ pub enum BlockColor {
     Purple,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ItemKind {
+    Shuffle,
+    Freeze,
+    Scramble,
+    Ice,
+}
+
+impl ItemKind {
+    pub const ALL: [ItemKind; 4] = [
+        ItemKind::Shuffle,
+        ItemKind::Freeze,
+        ItemKind::Scramble,
+        ItemKind::Ice,
+    ];
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BlockStatus {
+    #[default]
+    Plain,
+    Iced,
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum Block {
-    Normal { color: BlockColor },
-    Garbage { cracked: bool },
+    Normal {
+        color: BlockColor,
+        status: BlockStatus,
+    },
+    Garbage {
+        cracked: bool,
+    },
+    Bomb,
+    Item {
+        color: BlockColor,
+        item: ItemKind,
+    },
+    Stone {
+        cracked: bool,
+    },
 }
 
 impl Block {
+    pub fn normal(color: BlockColor) -> Block {
+        Block::Normal {
+            color,
+            status: BlockStatus::Plain,
+        }
+    }
+
     pub fn color(self) -> Option<BlockColor> {
         match self {
-            Block::Normal { color } => Some(color),
-            Block::Garbage { .. } => None,
+            Block::Normal { color, .. } | Block::Item { color, .. } => Some(color),
+            Block::Garbage { .. } | Block::Bomb | Block::Stone { .. } => None,
         }
     }
 
     pub fn is_garbage(self) -> bool {
         matches!(self, Block::Garbage { .. })
     }
+
+    pub fn is_stone(self) -> bool {
+        matches!(self, Block::Stone { .. })
+    }
+
+    pub fn is_iced(self) -> bool {
+        matches!(
+            self,
+            Block::Normal {
+                status: BlockStatus::Iced,
+                ..
+            }
+        )
+    }
+
+    pub fn swappable(self) -> bool {
+        !self.is_garbage() && !self.is_stone() && !self.is_iced()
+    }
+
+    pub fn from_symbol(symbol: char) -> Option<Block> {
+        let color = match symbol {
+            'R' => BlockColor::Red,
+            'G' => BlockColor::Green,
+            'B' => BlockColor::Blue,
+            'Y' => BlockColor::Yellow,
+            'P' => BlockColor::Purple,
+            'g' => return Some(Block::Garbage { cracked: false }),
+            'c' => return Some(Block::Garbage { cracked: true }),
+            '*' => return Some(Block::Bomb),
+            's' => return Some(Block::Stone { cracked: false }),
+            'x' => return Some(Block::Stone { cracked: true }),
+            _ => return None,
+        };
+        Some(Block::normal(color))
+    }
+
+    pub fn symbol(self) -> char {
+        match self {
+            Block::Normal { color, .. } | Block::Item { color, .. } => match color {
+                BlockColor::Red => 'R',
+                BlockColor::Green => 'G',
+                BlockColor::Blue => 'B',
+                BlockColor::Yellow => 'Y',
+                BlockColor::Purple => 'P',
+            },
+            Block::Garbage { cracked: false } => 'g',
+            Block::Garbage { cracked: true } => 'c',
+            Block::Bomb => '*',
+            Block::Stone { cracked: false } => 's',
+            Block::Stone { cracked: true } => 'x',
+        }
+    }
 }
 
 #[derive(Resource, Clone, Copy, Debug)]
@@ -40,12 +145,20 @@ impl Cursor {
         Self { x, y }
     }
 
-    pub fn move_by(&mut self, dx: isize, dy: isize, width: usize, height: usize) -> bool {
-        if width < 2 || height == 0 {
+    pub fn move_by(
+        &mut self,
+        dx: isize,
+        dy: isize,
+        span: (usize, usize),
+        size: (usize, usize),
+    ) -> bool {
+        let (span_x, span_y) = (span.0.max(1), span.1.max(1));
+        let (width, height) = size;
+        if width < span_x || height < span_y {
             return false;
         }
-        let max_x = width - 2;
-        let max_y = height - 1;
+        let max_x = width - span_x;
+        let max_y = height - span_y;
         let nx = (self.x as isize + dx).clamp(0, max_x as isize) as usize;
         let ny = (self.y as isize + dy).clamp(0, max_y as isize) as usize;
         let changed = nx != self.x || ny != self.y;
@@ -72,13 +185,49 @@ impl SwapCmd {
             by: y,
         }
     }
+
+    pub fn above(x: usize, y: usize) -> Self {
+        Self {
+            ax: x,
+            ay: y,
+            bx: x,
+            by: y + 1,
+        }
+    }
+}
+
+// Cells busy with an animation. Swapped cells hold for a few simulation ticks,
+// popping cells until their clear lands and converted garbage until its reveal ends.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reservation {
+    Swap(u8),
+    Pop,
+    Convert,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Motion {
+    hover: u8,
+    reveal: u8,
+    falling: bool,
+    moved: bool,
 }
 
-#[derive(Resource)]
+#[derive(Resource, Clone)]
 pub struct Grid {
     pub width: usize,
     pub height: usize,
     cells: Vec<Option<Block>>,
+    motion: Vec<Motion>,
+    reserved: Vec<Option<Reservation>>,
+    hover_steps: u8,
+    colors: u8,
+    bomb_rate: f64,
+    item_rate: f64,
+    next_row: Vec<Block>,
+    scripted_rows: VecDeque<Vec<Block>>,
+    rng: StdRng,
+    garbage_rng: StdRng,
 }
 
 impl Grid {
@@ -87,13 +236,165 @@ impl Grid {
             width,
             height,
             cells: vec![None; width * height],
+            motion: vec![Motion::default(); width * height],
+            reserved: vec![None; width * height],
+            hover_steps: 0,
+            colors: MAX_COLORS,
+            bomb_rate: 0.0,
+            item_rate: 0.0,
+            next_row: Vec::new(),
+            scripted_rows: VecDeque::new(),
+            rng: StdRng::from_entropy(),
+            garbage_rng: StdRng::from_entropy(),
+        }
+    }
+
+    // Keeps the bottom-left corner in place: rows past the new height drop off the
+    // top and columns past the new width drop off the right. A shrink that would
+    // cut garbage is refused, since a slab can't lose part of itself.
+    pub fn resize(&mut self, width: usize, height: usize) -> bool {
+        if (width, height) == (self.width, self.height) {
+            return true;
+        }
+        if self
+            .iter_cells()
+            .any(|(x, y, cell)| (x >= width || y >= height) && cell.is_some_and(Block::is_garbage))
+        {
+            return false;
+        }
+        let mut cells = vec![None; width * height];
+        for y in 0..height.min(self.height) {
+            for x in 0..width.min(self.width) {
+                cells[y * width + x] = self.get(x, y);
+            }
         }
+        let width_changed = width != self.width;
+        self.cells = cells;
+        self.motion = vec![Motion::default(); width * height];
+        self.reserved = vec![None; width * height];
+        self.width = width;
+        self.height = height;
+        if width_changed && !self.next_row.is_empty() {
+            self.generate_next_row();
+        }
+        true
+    }
+
+    pub fn set_hover_steps(&mut self, steps: u8) {
+        self.hover_steps = steps;
+    }
+
+    pub fn set_color_count(&mut self, colors: u8) {
+        self.colors = colors.clamp(2, MAX_COLORS);
+    }
+
+    pub fn set_bomb_rate(&mut self, rate: f64) {
+        self.bomb_rate = rate.clamp(0.0, 1.0);
+    }
+
+    pub fn set_item_rate(&mut self, rate: f64) {
+        self.item_rate = rate.clamp(0.0, 1.0);
+    }
+
+    pub fn reservation(&self, x: usize, y: usize) -> Option<Reservation> {
+        self.reserved[self.idx(x, y)]
+    }
+
+    pub fn is_reserved(&self, x: usize, y: usize) -> bool {
+        self.reservation(x, y).is_some()
+    }
+
+    pub fn has_reservations(&self) -> bool {
+        self.reserved.iter().any(Option::is_some)
+    }
+
+    pub fn tick_reservations(&mut self) {
+        for reserved in &mut self.reserved {
+            if let Some(Reservation::Swap(ticks)) = reserved {
+                *ticks -= 1;
+                if *ticks == 0 {
+                    *reserved = None;
+                }
+            }
+        }
+    }
+
+    // A carried block keeps moving, so only the cells it has left stay locked.
+    pub fn release_swap(&mut self, x: usize, y: usize) {
+        let idx = self.idx(x, y);
+        if let Some(Reservation::Swap(_)) = self.reserved[idx] {
+            self.reserved[idx] = None;
+        }
+    }
+
+    pub fn in_motion(&self, x: usize, y: usize) -> bool {
+        let motion = self.motion[self.idx(x, y)];
+        motion.hover > 0 || motion.reveal > 0 || motion.falling
+    }
+
+    pub fn reveal_steps(&self, x: usize, y: usize) -> u8 {
+        self.motion[self.idx(x, y)].reveal
+    }
+
+    pub fn just_fell(&self, x: usize, y: usize) -> bool {
+        self.motion[self.idx(x, y)].moved
+    }
+
+    // Garbage holes come from their own stream, so the nth piece a board receives
+    // lands the same way however many rows it has raised or items it has rolled.
+    pub fn reseed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+        self.garbage_rng = StdRng::seed_from_u64(seed ^ GARBAGE_SEED_SALT);
+    }
+
+    pub fn rng_mut(&mut self) -> &mut StdRng {
+        &mut self.rng
+    }
+
+    pub fn garbage_rng(&self) -> &StdRng {
+        &self.garbage_rng
+    }
+
+    pub fn garbage_rng_mut(&mut self) -> &mut StdRng {
+        &mut self.garbage_rng
     }
 
     pub fn get(&self, x: usize, y: usize) -> Option<Block> {
         self.cells[self.idx(x, y)]
     }
 
+    pub fn in_bounds(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height
+    }
+
+    pub fn row(&self, y: usize) -> &[Option<Block>] {
+        let start = self.idx(0, y);
+        &self.cells[start..start + self.width]
+    }
+
+    pub fn column(&self, x: usize) -> impl Iterator<Item = Option<Block>> {
+        (0..self.height).map(move |y| self.get(x, y))
+    }
+
+    pub fn iter_cells(&self) -> impl Iterator<Item = (usize, usize, Option<Block>)> {
+        self.cells
+            .iter()
+            .enumerate()
+            .map(|(idx, cell)| (idx % self.width, idx / self.width, *cell))
+    }
+
+    pub fn count_blocks(&self) -> usize {
+        self.cells.iter().flatten().count()
+    }
+
+    pub fn column_height(&self, x: usize) -> usize {
+        self.column(x)
+            .enumerate()
+            .filter(|(_, cell)| cell.is_some())
+            .last()
+            .map_or(0, |(y, _)| y + 1)
+    }
+
     pub fn set(&mut self, x: usize, y: usize, block: Option<Block>) {
         let idx = self.idx(x, y);
         self.cells[idx] = block;
@@ -103,6 +404,7 @@ impl Grid {
         let a = self.idx(ax, ay);
         let b = self.idx(bx, by);
         self.cells.swap(a, b);
+        self.motion.swap(a, b);
     }
 
     pub fn swap_in_bounds(&mut self, cmd: SwapCmd) -> bool {
@@ -113,63 +415,163 @@ impl Grid {
         {
             return false;
         }
-        if self
-            .get(cmd.ax, cmd.ay)
-            .map(|b| b.is_garbage())
-            .unwrap_or(false)
-            || self
-                .get(cmd.bx, cmd.by)
-                .map(|b| b.is_garbage())
-                .unwrap_or(false)
-        {
+        let locked =
+            |x, y| self.is_reserved(x, y) || self.get(x, y).is_some_and(|b| !b.swappable());
+        if locked(cmd.ax, cmd.ay) || locked(cmd.bx, cmd.by) {
             return false;
         }
         self.swap(cmd.ax, cmd.ay, cmd.bx, cmd.by);
+        for (x, y) in [(cmd.ax, cmd.ay), (cmd.bx, cmd.by)] {
+            let idx = self.idx(x, y);
+            self.reserved[idx] = Some(Reservation::Swap(SWAP_LOCK_TICKS));
+        }
         true
     }
 
     pub fn fill_test_pattern(&mut self) {
         let filled_rows = self.height / 2;
-        let mut rng = thread_rng();
         for y in 0..filled_rows {
             for x in 0..self.width {
-                let mut color = random_color(&mut rng);
+                let mut color = random_color(&mut self.rng, self.colors);
                 for _ in 0..10 {
                     if !self.would_create_match(x, y, color) {
                         break;
                     }
-                    color = random_color(&mut rng);
+                    color = random_color(&mut self.rng, self.colors);
                 }
-                self.set(x, y, Some(Block::Normal { color }));
+                self.set(x, y, Some(Block::normal(color)));
+            }
+        }
+        self.generate_next_row();
+    }
+
+    pub fn next_row(&self) -> &[Block] {
+        &self.next_row
+    }
+
+    // Scripted rows rise in order ahead of random ones; rows of the wrong width are
+    // dropped.
+    pub fn script_rows(&mut self, rows: Vec<Vec<Block>>) {
+        self.scripted_rows = rows
+            .into_iter()
+            .filter(|row| row.len() == self.width)
+            .collect();
+        self.generate_next_row();
+    }
+
+    // Rows are read top to bottom in the same notation as to_rows, with the last
+    // one landing on the floor.
+    pub fn load_rows<S: AsRef<str>>(&mut self, rows: &[S]) {
+        self.clear();
+        for (y, row) in rows.iter().rev().enumerate().take(self.height) {
+            for (x, symbol) in row.as_ref().chars().enumerate().take(self.width) {
+                self.set(x, y, Block::from_symbol(symbol));
             }
         }
     }
 
+    fn generate_next_row(&mut self) {
+        if let Some(row) = self.scripted_rows.pop_front() {
+            self.next_row = row;
+            return;
+        }
+        let mut row = Vec::with_capacity(self.width);
+        for x in 0..self.width {
+            if self.bomb_rate > 0.0 && self.rng.gen_bool(self.bomb_rate) {
+                row.push(Block::Bomb);
+                continue;
+            }
+            let mut color = random_color(&mut self.rng, self.colors);
+            for _ in 0..10 {
+                if !self.next_row_match(&row, x, color) {
+                    break;
+                }
+                color = random_color(&mut self.rng, self.colors);
+            }
+            if self.item_rate > 0.0 && self.rng.gen_bool(self.item_rate) {
+                let item = ItemKind::ALL[self.rng.gen_range(0..ItemKind::ALL.len())];
+                row.push(Block::Item { color, item });
+            } else {
+                row.push(Block::normal(color));
+            }
+        }
+        self.next_row = row;
+    }
+
+    fn next_row_match(&self, row: &[Block], x: usize, color: BlockColor) -> bool {
+        let horizontal =
+            x >= 2 && row[x - 1].color() == Some(color) && row[x - 2].color() == Some(color);
+        let vertical = self.height >= 2
+            && self.get(x, 0).and_then(Block::color) == Some(color)
+            && self.get(x, 1).and_then(Block::color) == Some(color);
+        horizontal || vertical
+    }
+
     pub fn clear(&mut self) {
+        self.scripted_rows.clear();
         self.cells.fill(None);
+        self.motion.fill(Motion::default());
+        self.reserved.fill(None);
     }
 
-    pub fn clear_matches_once_with_stats(&mut self) -> ClearStats {
-        let marks = self.find_matches();
-        if marks.iter().all(|m| !*m) {
-            return ClearStats {
-                cleared: 0,
-                groups: 0,
-                marks,
-            };
-        }
-        let groups = self.count_match_groups(&marks);
+    pub fn to_rows(&self) -> Vec<String> {
+        (0..self.height)
+            .rev()
+            .map(|y| {
+                self.row(y)
+                    .iter()
+                    .map(|cell| cell.map_or('.', |block| block.symbol()))
+                    .collect()
+            })
+            .collect()
+    }
+
+    // Locks the blocks of any matches not already waiting to clear and returns
+    // their marks; each set is cleared on its own with clear_marked.
+    pub fn take_new_matches(&mut self) -> Option<Vec<bool>> {
+        let mut marks = self.find_matches();
+        for (marked, reserved) in marks.iter_mut().zip(&mut self.reserved) {
+            *marked &= *reserved != Some(Reservation::Pop);
+            if *marked {
+                *reserved = Some(Reservation::Pop);
+            }
+        }
+        marks.contains(&true).then_some(marks)
+    }
+
+    pub fn clear_marked(&mut self, mut marks: Vec<bool>) -> ClearStats {
+        for (marked, reserved) in marks.iter().zip(&mut self.reserved) {
+            if *marked {
+                *reserved = None;
+            }
+        }
+        self.hit_stones(&mut marks);
+        self.melt_ice(&marks);
+        let groups = self.match_groups(&marks).len() as u32;
+        let items = self
+            .cells
+            .iter()
+            .zip(&marks)
+            .filter_map(|(cell, marked)| match cell {
+                Some(Block::Item { item, .. }) if *marked => Some(*item),
+                _ => None,
+            })
+            .collect();
         let cleared = self.clear_matches(&marks);
         ClearStats {
             cleared,
             groups,
             marks,
+            items,
         }
     }
 
     pub fn has_matches(&self) -> bool {
         let marks = self.find_matches();
-        marks.iter().any(|m| *m)
+        marks
+            .iter()
+            .zip(&self.reserved)
+            .any(|(marked, reserved)| *marked && *reserved != Some(Reservation::Pop))
     }
 
     pub fn apply_gravity_step(&mut self) -> bool {
@@ -177,17 +579,51 @@ impl Grid {
         if self.height < 2 {
             return false;
         }
+        for motion in &mut self.motion {
+            motion.moved = false;
+        }
         let snapshot = self.cells.clone();
         let mut normal_moves: Vec<(usize, usize, Block)> = Vec::new();
+        let mut hovering = false;
         for x in 0..self.width {
-            for y in 1..self.height {
+            for y in 0..self.height {
                 let idx = self.idx(x, y);
-                let below = self.idx(x, y - 1);
-                if let Some(Block::Normal { .. }) = snapshot[idx] {
-                    if snapshot[below].is_none() {
-                        normal_moves.push((idx, below, snapshot[idx].unwrap()));
+                let Some(block) = snapshot[idx].filter(|block| !block.is_garbage()) else {
+                    continue;
+                };
+                if self.reserved[idx] == Some(Reservation::Pop) {
+                    continue;
+                }
+                if self.motion[idx].reveal > 0 {
+                    self.motion[idx].reveal -= 1;
+                    if self.motion[idx].reveal == 0 {
+                        self.reserved[idx] = None;
+                    }
+                    hovering = true;
+                    continue;
+                }
+                if y == 0 || snapshot[self.idx(x, y - 1)].is_some() {
+                    self.motion[idx] = Motion::default();
+                    continue;
+                }
+                if self.reserved[self.idx(x, y - 1)].is_some() {
+                    hovering = true;
+                    continue;
+                }
+                let motion = &mut self.motion[idx];
+                if !motion.falling && self.hover_steps > 0 {
+                    if motion.hover == 0 {
+                        motion.hover = self.hover_steps;
+                        hovering = true;
+                        continue;
+                    }
+                    motion.hover -= 1;
+                    if motion.hover > 0 {
+                        hovering = true;
+                        continue;
                     }
                 }
+                normal_moves.push((idx, self.idx(x, y - 1), block));
             }
         }
 
@@ -216,11 +652,11 @@ impl Grid {
                                 continue;
                             }
                             let nidx = self.idx(nx, ny);
-                            if !visited[nidx] {
-                                if let Some(Block::Garbage { .. }) = snapshot[nidx] {
-                                    visited[nidx] = true;
-                                    stack.push((nx, ny));
-                                }
+                            if !visited[nidx]
+                                && let Some(Block::Garbage { .. }) = snapshot[nidx]
+                            {
+                                visited[nidx] = true;
+                                stack.push((nx, ny));
                             }
                         }
                     }
@@ -236,7 +672,9 @@ impl Grid {
                             break;
                         }
                         let below = self.idx(cx, cy - 1);
-                        if snapshot[below].is_some() && !in_component[below] {
+                        if (snapshot[below].is_some() || self.reserved[below].is_some())
+                            && !in_component[below]
+                        {
                             can_fall = false;
                             break;
                         }
@@ -257,15 +695,34 @@ impl Grid {
             moved = true;
             for (from, _, _) in normal_moves.iter().chain(garbage_moves.iter()) {
                 self.cells[*from] = None;
+                self.motion[*from] = Motion::default();
+            }
+            for (from, _, _) in &normal_moves {
+                let above = from + self.width;
+                if above < self.cells.len() && snapshot[above].is_some_and(|b| !b.is_garbage()) {
+                    self.motion[above].falling = true;
+                }
             }
-            for (_, to, block) in normal_moves.into_iter().chain(garbage_moves.into_iter()) {
+            for (_, to, block) in normal_moves.into_iter().chain(garbage_moves) {
                 self.cells[to] = Some(block);
+                self.motion[to] = Motion {
+                    falling: !block.is_garbage(),
+                    moved: true,
+                    ..Motion::default()
+                };
             }
         }
-        moved
+        moved || hovering
     }
 
     pub fn has_falling_garbage(&self) -> bool {
+        self.falling_garbage().contains(&true)
+    }
+
+    // Marks every garbage cell whose slab has nothing under it.
+    pub fn falling_garbage(&self) -> Vec<bool> {
+        let _span = info_span!("falling_garbage").entered();
+        let mut falling = vec![false; self.cells.len()];
         let mut visited = vec![false; self.cells.len()];
         for y in 0..self.height {
             for x in 0..self.width {
@@ -290,11 +747,11 @@ impl Grid {
                                 continue;
                             }
                             let nidx = self.idx(nx, ny);
-                            if !visited[nidx] {
-                                if let Some(Block::Garbage { .. }) = self.cells[nidx] {
-                                    visited[nidx] = true;
-                                    stack.push((nx, ny));
-                                }
+                            if !visited[nidx]
+                                && let Some(Block::Garbage { .. }) = self.cells[nidx]
+                            {
+                                visited[nidx] = true;
+                                stack.push((nx, ny));
                             }
                         }
                     }
@@ -316,12 +773,14 @@ impl Grid {
                         }
                     }
                     if can_fall {
-                        return true;
+                        for (cx, cy) in component {
+                            falling[self.idx(cx, cy)] = true;
+                        }
                     }
                 }
             }
         }
-        false
+        falling
     }
 
     fn find_matches(&self) -> Vec<bool> {
@@ -373,14 +832,126 @@ impl Grid {
             }
         }
 
+        self.detonate_bombs(&mut marks);
         marks
     }
 
+    // A match cracks every stone it touches; a stone that was already cracked is
+    // cleared along with the match.
+    fn hit_stones(&mut self, marks: &mut [bool]) {
+        let hit: Vec<(usize, usize, bool)> = self
+            .iter_cells()
+            .filter_map(|(x, y, cell)| match cell {
+                Some(Block::Stone { cracked }) if self.touches_mark(x, y, marks) => {
+                    Some((x, y, cracked))
+                }
+                _ => None,
+            })
+            .collect();
+        for (x, y, cracked) in hit {
+            if cracked {
+                marks[self.idx(x, y)] = true;
+            } else {
+                self.set(x, y, Some(Block::Stone { cracked: true }));
+            }
+        }
+    }
+
+    fn melt_ice(&mut self, marks: &[bool]) {
+        let melted: Vec<(usize, usize, BlockColor)> = self
+            .iter_cells()
+            .filter_map(|(x, y, cell)| match cell {
+                Some(Block::Normal {
+                    color,
+                    status: BlockStatus::Iced,
+                }) if !marks[self.idx(x, y)] && self.touches_mark(x, y, marks) => {
+                    Some((x, y, color))
+                }
+                _ => None,
+            })
+            .collect();
+        for (x, y, color) in melted {
+            self.set(x, y, Some(Block::normal(color)));
+        }
+    }
+
+    // Ices up to `count` settled plain blocks picked at random and returns how many
+    // it managed.
+    pub fn ice_random_blocks(&mut self, count: usize) -> usize {
+        let mut candidates: Vec<(usize, usize, BlockColor)> = self
+            .iter_cells()
+            .filter_map(|(x, y, cell)| match cell {
+                Some(Block::Normal {
+                    color,
+                    status: BlockStatus::Plain,
+                }) if !self.in_motion(x, y) && !self.is_reserved(x, y) => Some((x, y, color)),
+                _ => None,
+            })
+            .collect();
+        candidates.shuffle(&mut self.rng);
+        candidates.truncate(count);
+        for &(x, y, color) in &candidates {
+            self.set(
+                x,
+                y,
+                Some(Block::Normal {
+                    color,
+                    status: BlockStatus::Iced,
+                }),
+            );
+        }
+        candidates.len()
+    }
+
+    fn touches_mark(&self, x: usize, y: usize, marks: &[bool]) -> bool {
+        [(-1, 0), (1, 0), (0, -1), (0, 1)].iter().any(|&(dx, dy)| {
+            match (x.checked_add_signed(dx), y.checked_add_signed(dy)) {
+                (Some(nx), Some(ny)) => self.in_bounds(nx, ny) && marks[self.idx(nx, ny)],
+                _ => false,
+            }
+        })
+    }
+
+    pub fn has_stones(&self) -> bool {
+        self.iter_cells()
+            .any(|(_, _, cell)| cell.is_some_and(Block::is_stone))
+    }
+
+    // A bomb next to a matched block goes off and clears the 3x3 square around it,
+    // setting off any other bomb caught in the blast. Garbage is left to crack.
+    fn detonate_bombs(&self, marks: &mut [bool]) {
+        let mut pending: Vec<(usize, usize)> = self
+            .iter_cells()
+            .filter(|&(x, y, cell)| {
+                matches!(cell, Some(Block::Bomb)) && self.touches_mark(x, y, marks)
+            })
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        let mut exploded = vec![false; marks.len()];
+        while let Some((x, y)) = pending.pop() {
+            let idx = self.idx(x, y);
+            if exploded[idx] {
+                continue;
+            }
+            exploded[idx] = true;
+            marks[idx] = true;
+            for ny in y.saturating_sub(1)..(y + 2).min(self.height) {
+                for nx in x.saturating_sub(1)..(x + 2).min(self.width) {
+                    match self.get(nx, ny) {
+                        Some(Block::Bomb) => pending.push((nx, ny)),
+                        Some(block) if block.color().is_some() => marks[self.idx(nx, ny)] = true,
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+
     fn clear_matches(&mut self, marks: &[bool]) -> u32 {
         let mut cleared = 0;
-        for i in 0..self.cells.len() {
-            if marks[i] {
-                self.cells[i] = None;
+        for (cell, marked) in self.cells.iter_mut().zip(marks) {
+            if *marked {
+                *cell = None;
                 cleared += 1;
             }
         }
@@ -408,39 +979,61 @@ impl Grid {
         if self.top_row_occupied() {
             return;
         }
+        if self.next_row.len() != self.width {
+            self.generate_next_row();
+        }
         for y in (1..self.height).rev() {
             for x in 0..self.width {
                 let below = self.idx(x, y - 1);
                 let here = self.idx(x, y);
                 self.cells[here] = self.cells[below];
+                self.motion[here] = self.motion[below];
+                self.reserved[here] = self.reserved[below];
             }
         }
 
-        let mut rng = thread_rng();
         for x in 0..self.width {
             let idx = self.idx(x, 0);
-            let mut color = random_color(&mut rng);
-            for _ in 0..10 {
-                if !self.would_create_match(x, 0, color) {
-                    break;
-                }
-                color = random_color(&mut rng);
-            }
-            self.cells[idx] = Some(Block::Normal { color });
+            self.cells[idx] = Some(self.next_row[x]);
+            self.motion[idx] = Motion::default();
+            self.reserved[idx] = None;
         }
+        self.generate_next_row();
     }
 
-    pub fn top_row_occupied(&self) -> bool {
-        if self.height == 0 {
+    pub fn scramble_column(&mut self, x: usize) -> bool {
+        let rows: Vec<usize> = (0..self.height)
+            .filter(|&y| !self.is_reserved(x, y) && self.get(x, y).is_some_and(Block::swappable))
+            .collect();
+        if rows.len() < 2 {
             return false;
         }
-        let y = self.height - 1;
-        for x in 0..self.width {
-            if self.get(x, y).is_some() {
-                return true;
-            }
+        let mut blocks: Vec<Option<Block>> = rows.iter().map(|&y| self.get(x, y)).collect();
+        blocks.shuffle(&mut self.rng);
+        for (y, block) in rows.into_iter().zip(blocks) {
+            self.set(x, y, block);
         }
-        false
+        true
+    }
+
+    pub fn highest_occupied_row(&self) -> Option<usize> {
+        (0..self.height)
+            .rev()
+            .find(|&y| self.row(y).iter().any(Option::is_some))
+    }
+
+    pub fn has_garbage(&self) -> bool {
+        self.iter_cells()
+            .any(|(_, _, cell)| cell.is_some_and(Block::is_garbage))
+    }
+
+    pub fn free_rows_at_top(&self) -> usize {
+        self.highest_occupied_row()
+            .map_or(self.height, |y| self.height - 1 - y)
+    }
+
+    pub fn top_row_occupied(&self) -> bool {
+        self.height > 0 && self.row(self.height - 1).iter().any(Option::is_some)
     }
 
     fn would_create_match(&self, x: usize, y: usize, color: BlockColor) -> bool {
@@ -505,19 +1098,21 @@ impl Grid {
         false
     }
 
-    fn count_match_groups(&self, marks: &[bool]) -> u32 {
+    // The connected groups of marked cells, each as the `(x, y)` cells in it.
+    pub fn match_groups(&self, marks: &[bool]) -> Vec<Vec<(usize, usize)>> {
         let mut visited = vec![false; marks.len()];
-        let mut groups = 0;
+        let mut groups = Vec::new();
         for y in 0..self.height {
             for x in 0..self.width {
                 let idx = self.idx(x, y);
                 if !marks[idx] || visited[idx] {
                     continue;
                 }
-                groups += 1;
+                let mut group = Vec::new();
                 let mut stack = vec![(x, y)];
                 visited[idx] = true;
                 while let Some((cx, cy)) = stack.pop() {
+                    group.push((cx, cy));
                     let neighbors = [
                         (cx.wrapping_sub(1), cy, cx > 0),
                         (cx + 1, cy, cx + 1 < self.width),
@@ -535,12 +1130,14 @@ impl Grid {
                         }
                     }
                 }
+                groups.push(group);
             }
         }
         groups
     }
 
     pub fn crack_adjacent_garbage(&mut self, marks: &[bool]) -> u32 {
+        let _span = info_span!("crack_adjacent_garbage").entered();
         let mut cracked = 0;
         let mut visited = vec![false; self.cells.len()];
         for y in 0..self.height {
@@ -570,11 +1167,11 @@ impl Grid {
                                 continue;
                             }
                             let nidx = self.idx(nx, ny);
-                            if !visited[nidx] {
-                                if let Some(Block::Garbage { .. }) = self.cells[nidx] {
-                                    visited[nidx] = true;
-                                    stack.push((nx, ny));
-                                }
+                            if !visited[nidx]
+                                && let Some(Block::Garbage { .. }) = self.cells[nidx]
+                            {
+                                visited[nidx] = true;
+                                stack.push((nx, ny));
                             }
                         }
                     }
@@ -611,21 +1208,25 @@ impl Grid {
         false
     }
 
-    pub fn convert_cracked_garbage(&mut self) -> u32 {
-        let mut rng = thread_rng();
-        let mut converted = 0;
+    // Converted cells stay grey and hold still for a few gravity steps, cascading
+    // left to right, before their new colour counts.
+    pub fn convert_cracked_garbage(&mut self) -> bool {
+        let mut converted = false;
         for y in 0..self.height {
             for x in 0..self.width {
                 if let Some(Block::Garbage { cracked: true }) = self.get(x, y) {
-                    let mut color = random_color(&mut rng);
+                    let mut color = random_color(&mut self.rng, self.colors);
                     for _ in 0..10 {
                         if !self.would_create_match(x, y, color) {
                             break;
                         }
-                        color = random_color(&mut rng);
+                        color = random_color(&mut self.rng, self.colors);
                     }
-                    self.set(x, y, Some(Block::Normal { color }));
-                    converted += 1;
+                    self.set(x, y, Some(Block::normal(color)));
+                    let idx = self.idx(x, y);
+                    self.motion[idx].reveal = REVEAL_STEPS + x as u8;
+                    self.reserved[idx] = Some(Reservation::Convert);
+                    converted = true;
                 }
             }
         }
@@ -648,8 +1249,8 @@ impl Grid {
         let start_y = self.height - rows.len();
         for (row_idx, row) in rows.iter().enumerate() {
             let y = start_y + row_idx;
-            for x in 0..self.width {
-                if row[x] && self.get(x, y).is_some() {
+            for (x, filled) in row.iter().enumerate() {
+                if *filled && self.get(x, y).is_some() {
                     return false;
                 }
             }
@@ -657,8 +1258,8 @@ impl Grid {
 
         for (row_idx, row) in rows.iter().enumerate() {
             let y = start_y + row_idx;
-            for x in 0..self.width {
-                if row[x] {
+            for (x, filled) in row.iter().enumerate() {
+                if *filled {
                     self.set(x, y, Some(Block::Garbage { cracked: false }));
                 }
             }
@@ -671,10 +1272,11 @@ pub struct ClearStats {
     pub cleared: u32,
     pub groups: u32,
     pub marks: Vec<bool>,
+    pub items: Vec<ItemKind>,
 }
 
-fn random_color(rng: &mut ThreadRng) -> BlockColor {
-    match rng.gen_range(0..5) {
+fn random_color(rng: &mut impl Rng, colors: u8) -> BlockColor {
+    match rng.gen_range(0..i32::from(colors)) {
         0 => BlockColor::Red,
         1 => BlockColor::Green,
         2 => BlockColor::Blue,
@@ -682,3 +1284,130 @@ fn random_color(rng: &mut ThreadRng) -> BlockColor {
         _ => BlockColor::Purple,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board(rows: &[&str]) -> Grid {
+        let mut grid = Grid::new(3, 4);
+        grid.load_rows(rows);
+        grid
+    }
+
+    fn colors(cells: impl IntoIterator<Item = Option<Block>>) -> Vec<Option<BlockColor>> {
+        cells
+            .into_iter()
+            .map(|cell| cell.and_then(Block::color))
+            .collect()
+    }
+
+    #[test]
+    fn iter_cells_walks_rows_bottom_up() {
+        let grid = board(&["R..", "GB."]);
+        let coords: Vec<(usize, usize)> = grid.iter_cells().map(|(x, y, _)| (x, y)).collect();
+        assert_eq!(coords.len(), 12);
+        assert_eq!(coords[..4], [(0, 0), (1, 0), (2, 0), (0, 1)]);
+        assert_eq!(coords[11], (2, 3));
+        let filled: Vec<(usize, usize)> = grid
+            .iter_cells()
+            .filter(|(_, _, cell)| cell.is_some())
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        assert_eq!(filled, [(0, 0), (1, 0), (0, 1)]);
+    }
+
+    #[test]
+    fn row_and_column_span_the_board() {
+        let grid = board(&["R..", "GB."]);
+        assert_eq!(
+            colors(grid.row(0).iter().copied()),
+            [Some(BlockColor::Green), Some(BlockColor::Blue), None]
+        );
+        assert!(grid.row(3).iter().all(Option::is_none));
+        assert_eq!(
+            colors(grid.column(0)),
+            [Some(BlockColor::Green), Some(BlockColor::Red), None, None]
+        );
+    }
+
+    #[test]
+    fn height_queries_track_the_stack() {
+        let grid = board(&["R..", "GB."]);
+        assert_eq!(grid.column_height(0), 2);
+        assert_eq!(grid.column_height(1), 1);
+        assert_eq!(grid.column_height(2), 0);
+        assert_eq!(grid.highest_occupied_row(), Some(1));
+        assert_eq!(grid.free_rows_at_top(), 2);
+        assert!(!grid.top_row_occupied());
+        assert_eq!(grid.count_blocks(), 3);
+
+        let empty = Grid::new(3, 4);
+        assert_eq!(empty.highest_occupied_row(), None);
+        assert_eq!(empty.free_rows_at_top(), 4);
+
+        let full = board(&["..R", "...", "...", "..."]);
+        assert!(full.top_row_occupied());
+        assert_eq!(full.free_rows_at_top(), 0);
+    }
+
+    #[test]
+    fn resize_keeps_the_stack_bottom_aligned() {
+        let mut grid = board(&["R..", "GB."]);
+        assert!(grid.resize(5, 6));
+        assert_eq!((grid.width, grid.height), (5, 6));
+        assert_eq!(
+            colors(grid.row(0).iter().copied()),
+            [
+                Some(BlockColor::Green),
+                Some(BlockColor::Blue),
+                None,
+                None,
+                None
+            ]
+        );
+        assert_eq!(
+            colors(grid.column(0)),
+            [
+                Some(BlockColor::Green),
+                Some(BlockColor::Red),
+                None,
+                None,
+                None,
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn resize_drops_blocks_past_the_new_edges() {
+        let mut grid = board(&["..Y", "R..", "GBP"]);
+        assert!(grid.resize(2, 2));
+        assert_eq!(grid.count_blocks(), 3);
+        assert_eq!(
+            colors(grid.row(0).iter().copied()),
+            [Some(BlockColor::Green), Some(BlockColor::Blue)]
+        );
+        assert_eq!(
+            colors(grid.row(1).iter().copied()),
+            [Some(BlockColor::Red), None]
+        );
+    }
+
+    #[test]
+    fn resize_never_cuts_garbage() {
+        let mut grid = board(&["ggg", "GBP"]);
+        assert!(!grid.resize(2, 4));
+        assert_eq!((grid.width, grid.height), (3, 4));
+        assert_eq!(grid.count_blocks(), 6);
+        assert!(grid.resize(3, 2));
+    }
+
+    #[test]
+    fn bounds_stop_at_width_and_height() {
+        let grid = board(&[]);
+        assert!(grid.in_bounds(2, 3));
+        assert!(!grid.in_bounds(3, 0));
+        assert!(!grid.in_bounds(0, 4));
+    }
+}
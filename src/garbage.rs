@@ -0,0 +1,135 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::tuning::Tuning;
+
+const GARBAGE_CHAIN_BONUS: u32 = 2;
+pub const CHAIN_BONUSES: &[u32] = &[1, 2, 3, 4];
+pub const COMBO_THRESHOLDS: &[Option<u32>] = &[None, Some(4), Some(5), Some(6)];
+const COMBO_THRESHOLD: u32 = 4;
+pub const CHAIN_CAPS: &[u32] = &[12, 18, 24, 36, 48];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GarbagePreset {
+    Modern,
+    Classic,
+    Custom,
+}
+
+impl GarbagePreset {
+    pub const ALL: &'static [GarbagePreset] = &[
+        GarbagePreset::Modern,
+        GarbagePreset::Classic,
+        GarbagePreset::Custom,
+    ];
+
+    pub fn label_key(self) -> &'static str {
+        match self {
+            GarbagePreset::Modern => "garbage.modern",
+            GarbagePreset::Classic => "garbage.classic",
+            GarbagePreset::Custom => "garbage.custom",
+        }
+    }
+}
+
+// What happens to a chain's attack past `GarbageRules::chain_cap`: HARD drops
+// it, SOFT keeps counting it for less and less, so the total approaches twice
+// the cap without reaching it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CapCurve {
+    Hard,
+    Soft,
+}
+
+impl CapCurve {
+    pub const ALL: &'static [CapCurve] = &[CapCurve::Hard, CapCurve::Soft];
+
+    pub fn label_key(self) -> &'static str {
+        match self {
+            CapCurve::Hard => "garbage.cap_hard",
+            CapCurve::Soft => "garbage.cap_soft",
+        }
+    }
+}
+
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GarbageRules {
+    pub preset: GarbagePreset,
+    pub chain_bonus: u32,
+    pub combo_threshold: Option<u32>,
+    pub cancel: bool,
+    pub chain_cap: u32,
+    pub cap_curve: CapCurve,
+}
+
+impl Default for GarbageRules {
+    fn default() -> Self {
+        Self::from_preset(GarbagePreset::Modern)
+    }
+}
+
+impl GarbageRules {
+    pub fn from_preset(preset: GarbagePreset) -> Self {
+        let chain_cap = Tuning::builtin().garbage_chain_cap;
+        match preset {
+            GarbagePreset::Modern => Self {
+                preset,
+                chain_bonus: GARBAGE_CHAIN_BONUS,
+                combo_threshold: Some(COMBO_THRESHOLD),
+                cancel: true,
+                chain_cap,
+                cap_curve: CapCurve::Hard,
+            },
+            GarbagePreset::Classic => Self {
+                preset,
+                chain_bonus: GARBAGE_CHAIN_BONUS,
+                combo_threshold: None,
+                cancel: false,
+                chain_cap,
+                cap_curve: CapCurve::Hard,
+            },
+            GarbagePreset::Custom => Self {
+                preset,
+                ..Self::from_preset(GarbagePreset::Modern)
+            },
+        }
+    }
+
+    // Presets take their cap from the match's tuning; a custom ruleset keeps
+    // whatever was picked on the setup screen.
+    pub fn with_tuning(self, tuning: &Tuning) -> Self {
+        match self.preset {
+            GarbagePreset::Custom => self,
+            _ => Self {
+                chain_cap: tuning.garbage_chain_cap,
+                ..self
+            },
+        }
+    }
+
+    pub fn units_for_clear(&self, cleared: u32, groups: u32, chain: u32) -> u32 {
+        let chain_units = self.chain_bonus * chain.saturating_sub(1);
+        let combo_units = match self.combo_threshold {
+            Some(threshold) if cleared >= threshold => {
+                cleared + 1 - threshold + groups.saturating_sub(1)
+            }
+            _ => 0,
+        };
+        chain_units + combo_units
+    }
+
+    // The attack a chain that earned `units` in total actually sends.
+    pub fn capped(&self, units: u32) -> u32 {
+        let cap = self.chain_cap;
+        if units <= cap {
+            return units;
+        }
+        match self.cap_curve {
+            CapCurve::Hard => cap,
+            CapCurve::Soft => {
+                let over = units - cap;
+                cap + over * cap / (over + cap)
+            }
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::headless::HeadlessMatch;
+use crate::locale::Locale;
+use crate::palette::Palette;
+use crate::profiles::Profiles;
+use crate::settings::{BoardLayout, Settings};
+use crate::trace::InputTrace;
+use crate::tuning::Tuning;
+use crate::{
+    GameMode, GameSet, GarbageMotion, MatchOver, MatchResultEvent, MatchRules, PlayerId,
+    PlayerViews, Players, SimClock, SimulationTick, SoloMode, compute_player_origins, move_view,
+    spawn_player_view, update_player_ui, update_player_visuals,
+};
+
+const GHOST_FILE: &str = "marathon-ghost.json";
+const GHOST_ALPHA: f32 = 0.45;
+
+// MARATHON races the profile's best run: its recorded inputs replay on a faded
+// board beside the live one, one simulation tick for every live tick.
+pub struct GhostPlugin;
+
+impl Plugin for GhostPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_non_send_resource::<GhostRun>()
+            .add_systems(SimulationTick, step_ghost.after(GameSet::GarbageResolve))
+            .add_systems(
+                Update,
+                (save_best_run, attach_ghost, draw_ghost)
+                    .chain()
+                    .in_set(GameSet::Presentation),
+            );
+    }
+}
+
+// Reaching a higher level beats any time; at the same level the faster run wins.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct BestRun {
+    level: u32,
+    seconds: f32,
+    trace: String,
+}
+
+impl BestRun {
+    fn beats(&self, other: &BestRun) -> bool {
+        self.level > other.level || (self.level == other.level && self.seconds < other.seconds)
+    }
+
+    fn load(profiles: &Profiles) -> Option<Self> {
+        let path = profiles.dir().join(GHOST_FILE);
+        let contents = std::fs::read_to_string(&path).ok()?;
+        serde_json::from_str(&contents)
+            .map_err(|err| warn!("ignoring unreadable ghost {}: {err}", path.display()))
+            .ok()
+    }
+
+    fn save(&self, profiles: &Profiles) {
+        let path = profiles.dir().join(GHOST_FILE);
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let Ok(contents) = serde_json::to_string(self) else {
+            return;
+        };
+        if let Err(err) = std::fs::write(&path, contents) {
+            warn!("could not save ghost to {}: {err}", path.display());
+        }
+    }
+}
+
+#[derive(Default)]
+struct GhostRun(Option<HeadlessMatch>);
+
+fn marathon(rules: &MatchRules, mode: GameMode) -> bool {
+    mode == GameMode::OnePlayer && rules.solo == SoloMode::Marathon
+}
+
+fn save_best_run(
+    mut results: EventReader<MatchResultEvent>,
+    rules: Res<MatchRules>,
+    mode: Res<GameMode>,
+    players: Res<Players>,
+    trace: Res<InputTrace>,
+    profiles: Res<Profiles>,
+) {
+    if results.read().count() == 0 || !marathon(&rules, *mode) {
+        return;
+    }
+    let player = &players.p1;
+    let run = BestRun {
+        level: player.rise_level,
+        seconds: player.elapsed,
+        trace: trace.to_text(),
+    };
+    if BestRun::load(&profiles).is_none_or(|best| run.beats(&best)) {
+        run.save(&profiles);
+    }
+}
+
+// A live clock behind the ghost's means a new match started, so the ghost starts
+// over from the best run on disk.
+#[allow(clippy::too_many_arguments)]
+fn attach_ghost(
+    mut commands: Commands,
+    mut ghost: NonSendMut<GhostRun>,
+    clock: Res<SimClock>,
+    rules: Res<MatchRules>,
+    tuning: Res<Tuning>,
+    mode: Res<GameMode>,
+    profiles: Res<Profiles>,
+    settings: Res<Settings>,
+    mut views: ResMut<PlayerViews>,
+    palette: Res<Palette>,
+    locale: Res<Locale>,
+    mut transform_query: Query<&mut Transform>,
+) {
+    if !marathon(&rules, *mode) {
+        ghost.0 = None;
+        return;
+    }
+    if ghost
+        .0
+        .as_ref()
+        .is_some_and(|run| run.frame() > clock.frame + 1)
+    {
+        ghost.0 = None;
+    }
+    if ghost.0.is_none() {
+        let Some(best) = BestRun::load(&profiles) else {
+            return;
+        };
+        match InputTrace::parse(&best.trace) {
+            Ok(trace) => ghost.0 = Some(HeadlessMatch::replay(&trace, &tuning)),
+            Err(err) => {
+                warn!("ignoring broken ghost trace: {err}");
+                return;
+            }
+        }
+    }
+    let Some(run) = &ghost.0 else {
+        return;
+    };
+    if views.ghost.is_some() {
+        return;
+    }
+    let (p1_origin, ghost_origin) = compute_player_origins(
+        GameMode::TwoPlayer,
+        views.layout_panel_width(),
+        [PlayerId::P1, PlayerId::P2].map(|id| settings.board_layout(id).panel),
+        views.arrangement,
+    );
+    let layout = BoardLayout {
+        mirrored: settings.board_layout(PlayerId::P1).mirrored,
+        panel: settings.board_layout(PlayerId::P2).panel,
+    };
+    views.ghost = Some(spawn_player_view(
+        &mut commands,
+        &run.players().p1,
+        ghost_origin,
+        layout,
+        views.p1.panel_width,
+        &palette,
+        &locale,
+    ));
+    move_view(&mut views.p1, p1_origin, &mut transform_query);
+}
+
+fn step_ghost(mut ghost: NonSendMut<GhostRun>, clock: Res<SimClock>, match_over: Res<MatchOver>) {
+    let Some(run) = &mut ghost.0 else {
+        return;
+    };
+    if match_over.active {
+        return;
+    }
+    while run.frame() <= clock.frame && !run.match_over().active {
+        run.step();
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn draw_ghost(
+    mut commands: Commands,
+    ghost: NonSend<GhostRun>,
+    mut views: ResMut<PlayerViews>,
+    rules: Res<MatchRules>,
+    settings: Res<Settings>,
+    palette: Res<Palette>,
+    locale: Res<Locale>,
+    mut sprite_query: Query<&mut Sprite>,
+    mut transform_query: Query<&mut Transform>,
+    mut text_query: Query<&mut Text>,
+    mut vis_query: Query<&mut Visibility>,
+) {
+    let (Some(run), Some(view)) = (&ghost.0, &mut views.ghost) else {
+        return;
+    };
+    let player = &run.players().p1;
+    update_player_visuals(
+        &mut commands,
+        player,
+        view,
+        &GarbageMotion::default(),
+        settings.reduced_motion,
+        &palette,
+        &mut sprite_query,
+        &mut transform_query,
+    );
+    let drawn = view
+        .blocks
+        .in_use()
+        .flat_map(|sprite| [sprite.fill, sprite.edge])
+        .chain(view.next_row.iter().copied());
+    for entity in drawn {
+        if let Ok(mut sprite) = sprite_query.get_mut(entity) {
+            let alpha = sprite.color.alpha();
+            sprite.color.set_alpha(alpha * GHOST_ALPHA);
+        }
+    }
+    update_player_ui(
+        PlayerId::P1,
+        player,
+        None,
+        &view.ui,
+        run.match_over(),
+        &rules,
+        &locale,
+        &mut text_query,
+        &mut vis_query,
+    );
+}
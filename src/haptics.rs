@@ -0,0 +1,72 @@
+use std::time::Duration;
+
+use bevy::input::gamepad::{GamepadRumbleIntensity, GamepadRumbleRequest};
+use bevy::prelude::*;
+
+use crate::cli::LaunchOptions;
+use crate::devices::PlayerDevices;
+use crate::{Feedback, FeedbackEvent, GameSet, LocalControl, apply_launch_options};
+
+pub struct HapticsPlugin;
+
+impl Plugin for HapticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HapticsSettings>()
+            .add_systems(Startup, apply_rumble_option.after(apply_launch_options))
+            .add_systems(Update, rumble_on_feedback.in_set(GameSet::Presentation));
+    }
+}
+
+#[derive(Resource)]
+pub struct HapticsSettings {
+    pub enabled: bool,
+}
+
+impl Default for HapticsSettings {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+fn apply_rumble_option(options: Res<LaunchOptions>, mut settings: ResMut<HapticsSettings>) {
+    if options.no_rumble {
+        settings.enabled = false;
+    }
+}
+
+fn rumble_pulse(kind: Feedback) -> (Duration, GamepadRumbleIntensity) {
+    match kind {
+        Feedback::GarbageLanded => (
+            Duration::from_millis(120),
+            GamepadRumbleIntensity::strong_motor(0.6),
+        ),
+        Feedback::Danger => (
+            Duration::from_millis(200),
+            GamepadRumbleIntensity::weak_motor(0.5),
+        ),
+        Feedback::Defeat => (Duration::from_millis(600), GamepadRumbleIntensity::MAX),
+    }
+}
+
+fn rumble_on_feedback(
+    settings: Res<HapticsSettings>,
+    devices: Res<PlayerDevices>,
+    local: Res<LocalControl>,
+    mut events: EventReader<FeedbackEvent>,
+    mut rumble: EventWriter<GamepadRumbleRequest>,
+) {
+    for event in events.read() {
+        if !settings.enabled {
+            continue;
+        }
+        let Some(gamepad) = devices.gamepad_for(event.player, *local) else {
+            continue;
+        };
+        let (duration, intensity) = rumble_pulse(event.kind);
+        rumble.send(GamepadRumbleRequest::Add {
+            gamepad,
+            duration,
+            intensity,
+        });
+    }
+}
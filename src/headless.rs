@@ -0,0 +1,554 @@
+use bevy::ecs::system::RunSystemOnce;
+use bevy::prelude::*;
+
+use crate::cli::{LaunchMode, LaunchOptions};
+use crate::cpu::{CPU_THINK_FRAMES, CpuBots};
+use crate::garbage::GarbageRules;
+use crate::trace::{InputTrace, TraceEntry, read_trace};
+use crate::tuning::{LatestTuning, Tuning};
+use crate::{
+    ClearEvent, CommandQueue, CpuControl, FeedbackEvent, GameMode, GarbageCancelEvent,
+    GarbageCounterEvent, ItemEvent, LevelUpEvent, MatchControl, MatchOver, MatchRules, MatchSeed,
+    PlayerCommand, PlayerId, Players, SimClock, active_players, add_simulation, step_simulation,
+};
+
+const DEFAULT_HEADLESS_FRAMES: u64 = 60 * 60;
+
+// A match driven one simulation tick at a time without a window; --headless runs
+// one to the end and the terminal frontend plays one live.
+pub struct HeadlessMatch {
+    app: App,
+    pub launch: LaunchMode,
+    pub mode: GameMode,
+    pub seed: u64,
+    pub cpu: CpuControl,
+}
+
+impl HeadlessMatch {
+    pub fn new(options: &LaunchOptions) -> Self {
+        Self::with_seed(options, options.seed.unwrap_or_else(rand::random))
+    }
+
+    fn with_seed(options: &LaunchOptions, seed: u64) -> Self {
+        let launch = options.mode.unwrap_or(LaunchMode::OnePlayer);
+        let mut mode = launch.game_mode();
+        let mut seed = seed;
+        let mut cpu = CpuControl::players(launch.cpu_players());
+        let mut rules = MatchRules {
+            start_level: options.speed.unwrap_or(0),
+            solo: launch.solo_mode(),
+            ..Default::default()
+        };
+        let mut garbage = GarbageRules::default();
+        let tuning = Tuning::default();
+        let mut playback = Vec::new();
+        if let Some(path) = &options.play_inputs {
+            match read_trace(path) {
+                Ok(trace) => {
+                    mode = trace.mode;
+                    seed = trace.seed;
+                    cpu = CpuControl::default();
+                    rules = trace.rules;
+                    garbage = trace.garbage;
+                    if trace.tuning != tuning.fingerprint() {
+                        eprintln!(
+                            "input trace {} was recorded with a different tuning table and may diverge",
+                            path.display()
+                        );
+                    }
+                    playback = trace.entries;
+                }
+                Err(err) => {
+                    eprintln!("could not load input trace {}: {err}", path.display());
+                    std::process::exit(1);
+                }
+            }
+        }
+        let bots = CpuBots::greedy(options.bot_think.unwrap_or([CPU_THINK_FRAMES; 2]), seed);
+        Self::build(
+            launch,
+            mode,
+            seed,
+            cpu,
+            Some(bots),
+            rules,
+            garbage,
+            tuning,
+            playback,
+        )
+    }
+
+    // Replays a recorded run on its own, without any window attached, under the
+    // rules its header carries.
+    pub fn replay(trace: &InputTrace, tuning: &Tuning) -> Self {
+        Self::build(
+            LaunchMode::OnePlayer,
+            trace.mode,
+            trace.seed,
+            CpuControl::default(),
+            None,
+            trace.rules,
+            trace.garbage,
+            tuning.clone(),
+            trace.entries.clone(),
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn build(
+        launch: LaunchMode,
+        mode: GameMode,
+        seed: u64,
+        cpu: CpuControl,
+        bots: Option<CpuBots>,
+        rules: MatchRules,
+        garbage: GarbageRules,
+        tuning: Tuning,
+        playback: Vec<TraceEntry>,
+    ) -> Self {
+        let mut app = App::new();
+        add_simulation(&mut app);
+        let world = app.world_mut();
+        world.insert_resource(mode);
+        world.insert_resource(MatchSeed(Some(seed)));
+        world.insert_resource(cpu);
+        if let Some(bots) = bots {
+            world.insert_resource(bots);
+        }
+        world.insert_resource(rules);
+        world.insert_resource(garbage);
+        world.insert_resource(LatestTuning(tuning));
+        world.run_system_once(|mode: Res<GameMode>, mut control: MatchControl| {
+            control.start(*mode);
+        });
+        {
+            let mut queue = world.resource_mut::<CommandQueue>();
+            for entry in playback {
+                queue
+                    .scheduled
+                    .entry(entry.frame)
+                    .or_default()
+                    .push((entry.player, entry.command));
+            }
+        }
+
+        Self {
+            app,
+            launch,
+            mode,
+            seed,
+            cpu,
+        }
+    }
+
+    pub fn push(&mut self, player: PlayerId, command: PlayerCommand) {
+        self.world()
+            .resource_mut::<CommandQueue>()
+            .push(player, command);
+    }
+
+    pub fn step(&mut self) {
+        let world = self.world();
+        step_simulation(world);
+        world.resource_mut::<Events<ClearEvent>>().update();
+        world.resource_mut::<Events<GarbageCancelEvent>>().update();
+        world.resource_mut::<Events<GarbageCounterEvent>>().update();
+        world.resource_mut::<Events<LevelUpEvent>>().update();
+        world.resource_mut::<Events<ItemEvent>>().update();
+        world.resource_mut::<Events<FeedbackEvent>>().update();
+    }
+
+    pub fn players(&self) -> &Players {
+        self.app.world().resource::<Players>()
+    }
+
+    pub fn match_over(&self) -> &MatchOver {
+        self.app.world().resource::<MatchOver>()
+    }
+
+    pub fn frame(&self) -> u64 {
+        self.app.world().resource::<SimClock>().frame
+    }
+
+    pub fn result(&self) -> String {
+        let match_over = self.match_over();
+        match (match_over.active, match_over.winner) {
+            (false, _) => "running".to_string(),
+            (true, Some(winner)) => format!("{} wins", winner.label()),
+            (true, None) if self.mode == GameMode::TwoPlayer => "draw".to_string(),
+            (true, None) => "game over".to_string(),
+        }
+    }
+
+    fn world(&mut self) -> &mut World {
+        self.app.world_mut()
+    }
+}
+
+pub fn run(options: &LaunchOptions) {
+    if let Some(games) = options.games {
+        run_batch(options, games);
+        return;
+    }
+    let mut game = HeadlessMatch::new(options);
+    let frames = options.frames.unwrap_or(DEFAULT_HEADLESS_FRAMES);
+    for _ in 0..frames {
+        game.step();
+        if game.match_over().active {
+            break;
+        }
+    }
+
+    let (launch, mode, seed, cpu) = (game.launch, game.mode, game.seed, game.cpu);
+    let mode_label = match (mode, cpu) {
+        (GameMode::OnePlayer, _) if launch == LaunchMode::Survival => "survival",
+        (GameMode::OnePlayer, _) if launch == LaunchMode::Marathon => "marathon",
+        (GameMode::OnePlayer, _) => "1p",
+        (GameMode::TwoPlayer, _) if launch == LaunchMode::Bots => "bots",
+        (GameMode::TwoPlayer, cpu) if cpu.any() => "cpu",
+        (GameMode::TwoPlayer, _) => "2p",
+    };
+    println!(
+        "seed {seed} mode {mode_label} frames {}/{frames}",
+        game.frame()
+    );
+    let players = game.players();
+    for id in active_players(mode) {
+        let player = players.get(*id);
+        println!(
+            "{} score {} level {} sent {} received {} incoming {} blocks {} tallest {}",
+            id.label(),
+            player.score,
+            player.rise_level,
+            player.garbage_sent,
+            player.garbage_received,
+            player.garbage_incoming(),
+            player.grid.count_blocks(),
+            (0..player.grid.width)
+                .map(|x| player.grid.column_height(x))
+                .max()
+                .unwrap_or(0)
+        );
+        if launch == LaunchMode::Marathon {
+            let splits: Vec<String> = player
+                .level_splits
+                .windows(2)
+                .map(|pair| format!("{:.1}", pair[1] - pair[0]))
+                .collect();
+            println!("{} level splits {}", id.label(), splits.join(" "));
+        }
+    }
+    println!("result {}", game.result());
+}
+
+#[derive(Default)]
+struct BatchStats {
+    wins: u32,
+    best_chains: u32,
+    chains: u32,
+}
+
+// Plays `games` matches on consecutive seeds and prints each result plus the win
+// rates and chain averages, for comparing bot speeds and garbage rules.
+fn run_batch(options: &LaunchOptions, games: u32) {
+    let first_seed = options.seed.unwrap_or_else(rand::random);
+    let frames = options.frames.unwrap_or(DEFAULT_HEADLESS_FRAMES);
+    let mut stats: [BatchStats; 2] = Default::default();
+    let mut draws = 0;
+    let mut undecided = 0;
+    for game_index in 0..games {
+        let seed = first_seed.wrapping_add(game_index as u64);
+        let mut game = HeadlessMatch::with_seed(options, seed);
+        for _ in 0..frames {
+            game.step();
+            if game.match_over().active {
+                break;
+            }
+        }
+        println!(
+            "game {} seed {seed} frames {} result {}",
+            game_index + 1,
+            game.frame(),
+            game.result()
+        );
+        match (game.match_over().active, game.match_over().winner) {
+            (true, Some(winner)) => stats[winner as usize].wins += 1,
+            (true, None) => draws += 1,
+            (false, _) => undecided += 1,
+        }
+        for id in active_players(game.mode) {
+            let player = game.players().get(*id);
+            let entry = &mut stats[*id as usize];
+            entry.best_chains += player.best_chain;
+            entry.chains += player.chain_counts[1..].iter().sum::<u32>();
+        }
+    }
+
+    let games_f = games.max(1) as f32;
+    let mode = options.mode.unwrap_or(LaunchMode::OnePlayer).game_mode();
+    println!(
+        "games {games} draws {draws} ({:.0}%) undecided {undecided} ({:.0}%)",
+        draws as f32 * 100.0 / games_f,
+        undecided as f32 * 100.0 / games_f
+    );
+    for id in active_players(mode) {
+        let entry = &stats[*id as usize];
+        println!(
+            "{} wins {} ({:.0}%) avg best chain {:.2} avg x2+ chains {:.2}",
+            id.label(),
+            entry.wins,
+            entry.wins as f32 * 100.0 / games_f,
+            entry.best_chains as f32 / games_f,
+            entry.chains as f32 / games_f
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::event::ManualEventReader;
+
+    use super::*;
+    use crate::PlayerPhase;
+    use crate::cpu::GreedyBot;
+    use crate::garbage::GarbagePreset;
+
+    const SETTLE_FRAMES: u64 = 60 * 8;
+    const MIRRORED_FRAMES: u64 = 60 * 60 * 10;
+
+    // Boards come from the same row notation as trials, swaps go through the
+    // command queue like real input, and every ClearEvent the ticks emit is kept
+    // with the frame it fired on, as is each phase P1 passes through.
+    struct Harness {
+        sim: HeadlessMatch,
+        clears: Vec<ClearEvent>,
+        cleared_at: Vec<u64>,
+        phases: Vec<PlayerPhase>,
+        reader: ManualEventReader<ClearEvent>,
+    }
+
+    impl Harness {
+        fn versus(p1: &[&str]) -> Self {
+            let mut sim = HeadlessMatch::build(
+                LaunchMode::TwoPlayer,
+                GameMode::TwoPlayer,
+                7,
+                CpuControl::default(),
+                None,
+                MatchRules::default(),
+                GarbageRules::default(),
+                Tuning::default(),
+                Vec::new(),
+            );
+            let mut players = sim.world().resource_mut::<Players>();
+            players.p1.grid.load_rows(p1);
+            players.p2.grid.load_rows::<&str>(&[]);
+            let reader = sim.world().resource::<Events<ClearEvent>>().get_reader();
+            Self {
+                sim,
+                clears: Vec::new(),
+                cleared_at: Vec::new(),
+                phases: vec![PlayerPhase::Idle],
+                reader,
+            }
+        }
+
+        fn with_garbage(mut self, preset: GarbagePreset) -> Self {
+            self.sim
+                .world()
+                .insert_resource(GarbageRules::from_preset(preset));
+            self
+        }
+
+        fn swap(&mut self, x: u8, y: u8) {
+            self.sim.push(PlayerId::P1, PlayerCommand::MoveTo { x, y });
+            self.sim.push(PlayerId::P1, PlayerCommand::Swap);
+        }
+
+        fn step(&mut self) {
+            self.sim.step();
+            let events = self.sim.app.world().resource::<Events<ClearEvent>>();
+            for clear in self.reader.read(events) {
+                self.clears.push(*clear);
+                self.cleared_at.push(self.sim.frame());
+            }
+            let phase = self.sim.players().p1.phase;
+            if self.phases.last() != Some(&phase) {
+                self.phases.push(phase);
+            }
+        }
+
+        fn settle(&mut self) {
+            for _ in 0..SETTLE_FRAMES {
+                self.step();
+            }
+        }
+
+        fn chains(&self) -> Vec<u32> {
+            self.clears.iter().map(|clear| clear.chain).collect()
+        }
+
+        fn scores(&self) -> Vec<u32> {
+            self.clears
+                .iter()
+                .map(|clear| clear.score.total())
+                .collect()
+        }
+
+        fn garbage_sent(&self) -> u32 {
+            self.sim.players().p1.garbage_sent
+        }
+
+        fn garbage_received(&self) -> u32 {
+            self.sim.players().p2.garbage_received
+        }
+    }
+
+    #[test]
+    fn three_match_scores_base_and_sends_nothing() {
+        let mut harness = Harness::versus(&["RRYRGB"]);
+        harness.swap(2, 0);
+        harness.settle();
+        assert_eq!(harness.chains(), [1]);
+        assert_eq!(harness.clears[0].cleared, 3);
+        assert_eq!(harness.scores(), [30]);
+        assert_eq!(harness.garbage_sent(), 0);
+        assert_eq!(harness.garbage_received(), 0);
+    }
+
+    const FOUR_COMBO: &[&str] = &["..R...", "..BR..", "GYRBG.", "YGRGY."];
+
+    #[test]
+    fn four_combo_sends_one_unit() {
+        let mut harness = Harness::versus(FOUR_COMBO);
+        harness.swap(2, 2);
+        harness.settle();
+        assert_eq!(harness.chains(), [1]);
+        assert_eq!(harness.clears[0].cleared, 4);
+        assert_eq!(harness.scores(), [40 + 20]);
+        assert_eq!(harness.garbage_sent(), 1);
+        assert_eq!(harness.garbage_received(), 1);
+    }
+
+    #[test]
+    fn classic_garbage_ignores_combos() {
+        let mut harness = Harness::versus(FOUR_COMBO).with_garbage(GarbagePreset::Classic);
+        harness.swap(2, 2);
+        harness.settle();
+        assert_eq!(harness.scores(), [60]);
+        assert_eq!(harness.garbage_sent(), 0);
+        assert_eq!(harness.garbage_received(), 0);
+    }
+
+    #[test]
+    fn two_chain_sends_chain_bonus_once_chain_ends() {
+        let mut harness = Harness::versus(&["..G...", "..BR..", "YBRGY.", "GGRYB."]);
+        harness.swap(2, 2);
+        harness.settle();
+        assert_eq!(harness.chains(), [1, 2]);
+        assert_eq!(harness.scores(), [30, 30 + 50]);
+        assert_eq!(harness.sim.players().p1.score, 110);
+        assert_eq!(harness.sim.players().p1.best_chain, 2);
+        assert_eq!(harness.garbage_sent(), 2);
+        assert_eq!(harness.garbage_received(), 2);
+    }
+
+    #[test]
+    fn swap_away_from_a_clear_is_not_held_back() {
+        let mut harness = Harness::versus(&["....GB", "....YG", "RRYRBG"]);
+        harness.swap(2, 0);
+        harness.step();
+        assert_eq!(harness.sim.players().p1.phase, PlayerPhase::Clearing);
+        harness.swap(4, 2);
+        harness.step();
+        assert!(harness.sim.players().p1.buffered_swap.is_none());
+        harness.settle();
+        assert_eq!(harness.clears.len(), 2);
+    }
+
+    #[test]
+    fn clear_then_fall_returns_to_idle() {
+        let mut harness = Harness::versus(&["..G...", "RRYRB."]);
+        harness.swap(2, 0);
+        harness.settle();
+        assert_eq!(harness.chains(), [1]);
+        assert_eq!(
+            harness.phases,
+            [
+                PlayerPhase::Idle,
+                PlayerPhase::Clearing,
+                PlayerPhase::Idle,
+                PlayerPhase::Falling,
+                PlayerPhase::Idle,
+            ]
+        );
+    }
+
+    // The cracked slab rests on its two left cells, so once it converts the
+    // right one drops between RR and GG and matches whichever of the two
+    // colours it turned into.
+    #[test]
+    fn chain_continues_through_garbage_conversion() {
+        let mut harness = Harness::versus(&["BBYB..", "gggY..", "RR.GG."]);
+        harness
+            .sim
+            .world()
+            .resource_mut::<Players>()
+            .p1
+            .grid
+            .set_color_count(2);
+        harness.swap(2, 2);
+        harness.settle();
+        assert!(harness.phases.contains(&PlayerPhase::Converting));
+        assert_eq!(harness.chains()[..2], [1, 2]);
+    }
+
+    #[test]
+    fn clears_queued_apart_resolve_in_order_on_their_own_delays() {
+        const GAP: u64 = 5;
+        let mut harness = Harness::versus(&["....GB", "....YG", "RRYRBG"]);
+        harness.swap(2, 0);
+        harness.step();
+        let first_queued = harness.sim.frame();
+        for _ in 1..GAP {
+            harness.step();
+        }
+        harness.swap(4, 2);
+        harness.step();
+        let second_queued = harness.sim.frame();
+        assert_eq!(second_queued - first_queued, GAP);
+        harness.settle();
+        assert_eq!(harness.clears.len(), 2);
+        // The bottom-row clear was made first; the column above it second.
+        assert!(harness.clears[0].centroid.y < harness.clears[1].centroid.y);
+        let delays = [
+            harness.cleared_at[0] - first_queued,
+            harness.cleared_at[1] - second_queued,
+        ];
+        assert!(delays[0] > GAP);
+        assert_eq!(delays[0], delays[1]);
+    }
+
+    // Identical bots on identical boards top out together, which is a draw.
+    #[test]
+    fn mirrored_bots_draw() {
+        let mut sim = HeadlessMatch::build(
+            LaunchMode::Bots,
+            GameMode::TwoPlayer,
+            3,
+            CpuControl::players(LaunchMode::Bots.cpu_players()),
+            Some(CpuBots::new(GreedyBot::new(12, 1), GreedyBot::new(12, 1))),
+            MatchRules {
+                start_level: 9,
+                ..Default::default()
+            },
+            GarbageRules::default(),
+            Tuning::default(),
+            Vec::new(),
+        );
+        while !sim.match_over().active && sim.frame() < MIRRORED_FRAMES {
+            sim.step();
+        }
+        assert_eq!(sim.result(), "draw");
+    }
+}
@@ -0,0 +1,286 @@
+use std::sync::OnceLock;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::AppState;
+use crate::game::Block;
+use crate::locale::Locale;
+use crate::menu::{Menu, MenuEntry, MenuEvent, MenuPlugin, MenuSet};
+use crate::palette::Palette;
+
+const CELL_PX: f32 = 28.0;
+const CELL_GAP: f32 = 2.0;
+const MARK_PX: f32 = 3.0;
+
+// HOW TO PLAY from the title menu. Each page's text comes from the locale files
+// under `howto.<id>.title` and `howto.<id>.body`, and its picture from
+// `assets/howto.json`, so the pages follow the rules without touching code.
+pub struct HowToPlugin;
+
+impl Plugin for HowToPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(MenuPlugin::<HowToItem>::default())
+            .add_systems(OnEnter(AppState::HowTo), setup_how_to)
+            .add_systems(OnExit(AppState::HowTo), cleanup_how_to)
+            .add_systems(
+                Update,
+                (handle_how_to_input, show_page)
+                    .chain()
+                    .after(MenuSet)
+                    .run_if(in_state(AppState::HowTo)),
+            );
+    }
+}
+
+// `board` rows are written top to bottom in block symbols, and `cursor` and
+// `marks` give `[x, y]` cells counted from the bottom-left.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Page {
+    id: String,
+    #[serde(default)]
+    board: Vec<String>,
+    #[serde(default)]
+    cursor: Option<[usize; 2]>,
+    #[serde(default)]
+    marks: Vec<[usize; 2]>,
+}
+
+pub fn pages() -> &'static [Page] {
+    static PAGES: OnceLock<Vec<Page>> = OnceLock::new();
+    PAGES.get_or_init(|| {
+        serde_json::from_str(include_str!("../assets/howto.json")).unwrap_or_else(|err| {
+            warn!("ignoring broken how to play pages: {err}");
+            Vec::new()
+        })
+    })
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum HowToItem {
+    Page,
+    Back,
+}
+
+#[derive(Resource)]
+struct HowToScreen {
+    root: Entity,
+    page: Entity,
+    shown: Option<usize>,
+    current: usize,
+}
+
+fn setup_how_to(mut commands: Commands, palette: Res<Palette>, locale: Res<Locale>) {
+    let root = commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(0.0),
+                top: Val::Percent(0.0),
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(16.0),
+                ..Default::default()
+            },
+            background_color: BackgroundColor(palette.overlay),
+            ..Default::default()
+        })
+        .id();
+
+    let mut page = Entity::PLACEHOLDER;
+    commands.entity(root).with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            locale.get("howto.title"),
+            TextStyle {
+                font: Default::default(),
+                font_size: 42.0,
+                color: palette.heading,
+            },
+        ));
+
+        page = parent
+            .spawn(NodeBundle {
+                style: Style {
+                    align_items: AlignItems::Center,
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(12.0),
+                    max_width: Val::Percent(80.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .id();
+
+        parent.spawn((
+            TextBundle {
+                text: Text::default().with_justify(JustifyText::Center),
+                ..Default::default()
+            },
+            Menu::new(vec![
+                MenuEntry::new(HowToItem::Page, page_label(0, &locale)),
+                MenuEntry::new(HowToItem::Back, locale.get("howto.back")),
+            ]),
+        ));
+
+        parent.spawn(TextBundle::from_section(
+            locale.get("howto.hint"),
+            TextStyle {
+                font: Default::default(),
+                font_size: 16.0,
+                color: palette.text,
+            },
+        ));
+    });
+
+    commands.insert_resource(HowToScreen {
+        root,
+        page,
+        shown: None,
+        current: 0,
+    });
+}
+
+fn cleanup_how_to(mut commands: Commands, screen: Res<HowToScreen>) {
+    commands.entity(screen.root).despawn_recursive();
+    commands.remove_resource::<HowToScreen>();
+}
+
+fn page_label(page: usize, locale: &Locale) -> String {
+    locale.format(
+        "howto.page",
+        &[("page", &(page + 1)), ("pages", &pages().len().max(1))],
+    )
+}
+
+fn handle_how_to_input(
+    mut events: EventReader<MenuEvent<HowToItem>>,
+    mut screen: ResMut<HowToScreen>,
+    locale: Res<Locale>,
+    mut menus: Query<&mut Menu<HowToItem>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let count = pages().len().max(1);
+    for event in events.read() {
+        match event {
+            MenuEvent::Confirm(HowToItem::Page) => screen.current = (screen.current + 1) % count,
+            MenuEvent::Adjust(_, step) => {
+                screen.current =
+                    (screen.current as isize + step).rem_euclid(count as isize) as usize;
+            }
+            MenuEvent::Confirm(HowToItem::Back) | MenuEvent::Cancel => {
+                next_state.set(AppState::Title)
+            }
+        }
+    }
+    let label = page_label(screen.current, &locale);
+    for mut menu in &mut menus {
+        if let Some(entry) = menu
+            .entries_mut()
+            .iter_mut()
+            .find(|entry| entry.id == HowToItem::Page && entry.label != label)
+        {
+            entry.label = label.clone();
+        }
+    }
+}
+
+fn show_page(
+    mut commands: Commands,
+    mut screen: ResMut<HowToScreen>,
+    palette: Res<Palette>,
+    locale: Res<Locale>,
+) {
+    if screen.shown == Some(screen.current) {
+        return;
+    }
+    screen.shown = Some(screen.current);
+    commands.entity(screen.page).despawn_descendants();
+    let Some(page) = pages().get(screen.current) else {
+        return;
+    };
+    commands.entity(screen.page).with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            locale.get(&format!("howto.{}.title", page.id)),
+            TextStyle {
+                font: Default::default(),
+                font_size: 28.0,
+                color: palette.accent,
+            },
+        ));
+        spawn_board(parent, page, &palette);
+        parent.spawn(TextBundle {
+            text: Text::from_section(
+                locale.get(&format!("howto.{}.body", page.id)),
+                TextStyle {
+                    font: Default::default(),
+                    font_size: 18.0,
+                    color: palette.text,
+                },
+            )
+            .with_justify(JustifyText::Center),
+            ..Default::default()
+        });
+    });
+}
+
+fn spawn_board(parent: &mut ChildBuilder, page: &Page, palette: &Palette) {
+    if page.board.is_empty() {
+        return;
+    }
+    let height = page.board.len();
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(CELL_GAP),
+                padding: UiRect::all(Val::Px(CELL_GAP * 2.0)),
+                ..Default::default()
+            },
+            background_color: BackgroundColor(palette.frame),
+            ..Default::default()
+        })
+        .with_children(|board| {
+            for (row, symbols) in page.board.iter().enumerate() {
+                let y = height - 1 - row;
+                board
+                    .spawn(NodeBundle {
+                        style: Style {
+                            column_gap: Val::Px(CELL_GAP),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    })
+                    .with_children(|cells| {
+                        for (x, symbol) in symbols.chars().enumerate() {
+                            let cursor = page
+                                .cursor
+                                .is_some_and(|[cx, cy]| cy == y && (x == cx || x == cx + 1));
+                            let border = if cursor {
+                                palette.cursor
+                            } else if page.marks.contains(&[x, y]) {
+                                Color::WHITE
+                            } else {
+                                Color::NONE
+                            };
+                            cells.spawn(NodeBundle {
+                                style: Style {
+                                    width: Val::Px(CELL_PX),
+                                    height: Val::Px(CELL_PX),
+                                    border: UiRect::all(Val::Px(MARK_PX)),
+                                    ..Default::default()
+                                },
+                                background_color: BackgroundColor(
+                                    Block::from_symbol(symbol)
+                                        .map_or(palette.grid_cell, |block| palette.cell(block)),
+                                ),
+                                border_color: BorderColor(border),
+                                ..Default::default()
+                            });
+                        }
+                    });
+            }
+        });
+}
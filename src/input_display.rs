@@ -0,0 +1,142 @@
+use bevy::prelude::*;
+
+use crate::locale::Locale;
+use crate::palette::Palette;
+use crate::trace::{InputTrace, TraceEntry};
+use crate::{GameEntity, GameMode, GameSet, MatchRules, PlayerCommand, PlayerId, SoloMode};
+
+const HISTORY_ROWS: usize = 14;
+
+// A strip down the left edge listing the player's latest inputs, newest
+// on top, with the simulation frame each landed on and the gap to the previous
+// one. It reads the input trace, so it shows exactly what the simulation applied.
+pub struct InputDisplayPlugin;
+
+impl Plugin for InputDisplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, update_input_display.in_set(GameSet::Presentation));
+    }
+}
+
+#[derive(Component, Default)]
+struct InputStrip {
+    shown: Option<usize>,
+}
+
+fn practice_mode(rules: &MatchRules, mode: GameMode) -> bool {
+    mode == GameMode::OnePlayer
+        && matches!(
+            rules.solo,
+            SoloMode::Zen | SoloMode::Challenge(_) | SoloMode::Trial(_)
+        )
+}
+
+fn command_label(command: PlayerCommand, locale: &Locale) -> String {
+    match command {
+        PlayerCommand::Move { dx, dy } => {
+            let (arrow, cells) = match (dx.signum(), dy.signum()) {
+                (-1, _) => ("<", dx.unsigned_abs()),
+                (1, _) => (">", dx.unsigned_abs()),
+                (_, 1) => ("^", dy.unsigned_abs()),
+                _ => ("v", dy.unsigned_abs()),
+            };
+            if cells > 1 {
+                format!("{arrow} x{cells}")
+            } else {
+                arrow.to_string()
+            }
+        }
+        PlayerCommand::Swap => locale.get("inputs.swap").to_string(),
+        PlayerCommand::MoveTo { x, y } => locale.format("inputs.move_to", &[("x", &x), ("y", &y)]),
+        PlayerCommand::Rotate => locale.get("inputs.rotate").to_string(),
+        PlayerCommand::Raise => locale.get("inputs.raise").to_string(),
+    }
+}
+
+fn history_lines(entries: &[TraceEntry], player: PlayerId, locale: &Locale) -> Vec<String> {
+    let mine: Vec<&TraceEntry> = entries
+        .iter()
+        .filter(|entry| entry.player == player)
+        .collect();
+    let start = mine.len().saturating_sub(HISTORY_ROWS);
+    (start..mine.len())
+        .rev()
+        .map(|index| {
+            let entry = mine[index];
+            let gap = match index.checked_sub(1) {
+                Some(previous) => format!("+{}", entry.frame - mine[previous].frame),
+                None => "-".to_string(),
+            };
+            format!(
+                "{:>6} {:>5}  {}",
+                entry.frame,
+                gap,
+                command_label(entry.command, locale)
+            )
+        })
+        .collect()
+}
+
+fn update_input_display(
+    mut commands: Commands,
+    rules: Res<MatchRules>,
+    mode: Res<GameMode>,
+    trace: Res<InputTrace>,
+    palette: Res<Palette>,
+    locale: Res<Locale>,
+    mut strips: Query<(&mut InputStrip, &mut Text)>,
+) {
+    if !practice_mode(&rules, *mode) {
+        return;
+    }
+    let Ok((mut strip, mut text)) = strips.get_single_mut() else {
+        commands.spawn((
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: Default::default(),
+                    font_size: 16.0,
+                    color: palette.text,
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(12.0),
+                top: Val::Px(64.0),
+                ..Default::default()
+            }),
+            InputStrip::default(),
+            GameEntity,
+        ));
+        return;
+    };
+    if strip.shown == Some(trace.entries.len()) {
+        return;
+    }
+    strip.shown = Some(trace.entries.len());
+    let mut lines = history_lines(&trace.entries, PlayerId::P1, &locale).into_iter();
+    let heading = TextStyle {
+        font: Default::default(),
+        font_size: 16.0,
+        color: palette.heading,
+    };
+    let rest = TextStyle {
+        color: palette.text,
+        ..heading.clone()
+    };
+    text.sections = vec![TextSection::new(
+        format!("{}\n", locale.get("inputs.title")),
+        heading.clone(),
+    )];
+    if let Some(newest) = lines.next() {
+        text.sections.push(TextSection::new(
+            format!("{newest}\n"),
+            TextStyle {
+                color: palette.highlight,
+                ..heading
+            },
+        ));
+    }
+    text.sections
+        .push(TextSection::new(lines.collect::<Vec<_>>().join("\n"), rest));
+}
@@ -0,0 +1,116 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::game::ItemKind;
+use crate::{
+    GameMode, ItemEvent, MatchClock, MatchOver, PlayerPhase, PlayerState, Players, active_players,
+    cursor_span, opponents_of, queue_clear,
+};
+
+pub const ITEM_RATE: f64 = 0.03;
+const ITEM_WARNING_SECONDS: f32 = 2.0;
+const FREEZE_SECONDS: f32 = 5.0;
+const ICE_BLOCKS: usize = 4;
+
+pub fn label_key(item: ItemKind) -> &'static str {
+    match item {
+        ItemKind::Shuffle => "item.shuffle",
+        ItemKind::Freeze => "item.freeze",
+        ItemKind::Scramble => "item.scramble",
+        ItemKind::Ice => "item.ice",
+    }
+}
+
+// Items cleared during a chain travel with its garbage: they are sent when the
+// chain ends and wait out a warning before they hit.
+pub fn deliver_items(
+    mut players: ResMut<Players>,
+    match_over: Res<MatchOver>,
+    mode: Res<GameMode>,
+    mut item_events: EventWriter<ItemEvent>,
+) {
+    if match_over.active || *mode != GameMode::TwoPlayer {
+        return;
+    }
+    for id in active_players(*mode) {
+        let attacker = players.get_mut(*id);
+        if !attacker.chain_ended || attacker.items_outgoing.is_empty() {
+            continue;
+        }
+        let items = std::mem::take(&mut attacker.items_outgoing);
+        let Some(target) = opponents_of(*mode, *id).next() else {
+            continue;
+        };
+        let defender = players.get_mut(target);
+        for item in items {
+            defender.item_queue.push_back((item, ITEM_WARNING_SECONDS));
+            item_events.send(ItemEvent {
+                player: target,
+                item,
+                landed: false,
+            });
+        }
+    }
+}
+
+pub fn apply_incoming_items(
+    clock: Res<MatchClock>,
+    mut players: ResMut<Players>,
+    match_over: Res<MatchOver>,
+    mode: Res<GameMode>,
+    mut item_events: EventWriter<ItemEvent>,
+) {
+    if match_over.active {
+        return;
+    }
+    let seconds = clock.delta.as_secs_f32();
+    for id in active_players(*mode) {
+        let player = players.get_mut(*id);
+        player.item_freeze = (player.item_freeze - seconds).max(0.0);
+        for (_, wait) in &mut player.item_queue {
+            *wait -= seconds;
+        }
+        while player.phase == PlayerPhase::Idle
+            && player
+                .item_queue
+                .front()
+                .is_some_and(|(_, wait)| *wait <= 0.0)
+            && let Some((item, _)) = player.item_queue.pop_front()
+        {
+            apply_item(player, item);
+            item_events.send(ItemEvent {
+                player: *id,
+                item,
+                landed: true,
+            });
+        }
+    }
+}
+
+fn apply_item(player: &mut PlayerState, item: ItemKind) {
+    match item {
+        ItemKind::Shuffle => {
+            let (span_x, span_y) = cursor_span(player);
+            let columns = (player.grid.width + 1).saturating_sub(span_x).max(1);
+            let rows = (player.grid.height + 1).saturating_sub(span_y).max(1);
+            let rng = player.grid.rng_mut();
+            let (x, y) = (rng.gen_range(0..columns), rng.gen_range(0..rows));
+            player.cursor.x = x;
+            player.cursor.y = y;
+            player.holding = false;
+            player.buffered_swap = None;
+        }
+        ItemKind::Freeze => player.item_freeze = FREEZE_SECONDS,
+        ItemKind::Ice => {
+            player.grid.ice_random_blocks(ICE_BLOCKS);
+            player.holding = false;
+        }
+        ItemKind::Scramble => {
+            let columns = player.grid.width.max(1);
+            let x = player.grid.rng_mut().gen_range(0..columns);
+            if player.grid.scramble_column(x) {
+                queue_clear(player);
+            }
+        }
+    }
+}
@@ -0,0 +1,5011 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Duration;
+
+use bevy::diagnostic::Diagnostics;
+use bevy::ecs::schedule::ScheduleLabel;
+use bevy::ecs::system::SystemParam;
+use bevy::input::keyboard::KeyCode;
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use bevy::utils::Instant;
+use bevy::window::PrimaryWindow;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+mod achievements;
+mod audio;
+mod bindings;
+mod challenges;
+mod cli;
+#[cfg(not(target_arch = "wasm32"))]
+mod clips;
+mod cpu;
+mod cutscene;
+#[cfg(feature = "debug")]
+mod debug;
+mod devices;
+mod difficulty;
+mod game;
+mod garbage;
+#[cfg(not(target_arch = "wasm32"))]
+mod ghost;
+mod haptics;
+#[cfg(not(target_arch = "wasm32"))]
+mod headless;
+mod howto;
+mod input_display;
+mod items;
+mod locale;
+mod marathon;
+mod menu;
+#[cfg(not(target_arch = "wasm32"))]
+mod netplay;
+mod pacing;
+mod palette;
+mod plugin;
+mod pool;
+mod profiles;
+mod profiling;
+#[cfg(not(target_arch = "wasm32"))]
+mod rating;
+mod records;
+mod rise_curve;
+mod scoring;
+#[cfg(not(target_arch = "wasm32"))]
+mod screenshots;
+mod settings;
+mod spectacle;
+#[cfg(not(target_arch = "wasm32"))]
+mod spectate;
+mod splash;
+mod split;
+#[cfg(not(target_arch = "wasm32"))]
+mod stream;
+mod survival;
+mod tips;
+mod trace;
+mod trials;
+#[cfg(not(target_arch = "wasm32"))]
+mod tui;
+mod tuning;
+mod versus;
+
+pub use plugin::{AudioPlugin, CorePlugin, TetanusAttackConfig, TetanusAttackPlugin, UiPlugin};
+
+use game::{Block, Cursor, Grid, ItemKind, SwapCmd};
+use garbage::GarbageRules;
+use locale::Locale;
+use menu::{Menu, MenuEntry, MenuEvent, MenuInput};
+use palette::Palette;
+use scoring::ScoreBreakdown;
+use tuning::{LatestTuning, Tuning};
+
+const GRID_W: usize = 6;
+const GRID_H: usize = 12;
+const CELL_SIZE: f32 = 32.0;
+const BLOCK_INSET: f32 = 6.0;
+const GARBAGE_BORDER: f32 = 3.0;
+const FRAME_THICKNESS: f32 = 4.0;
+const NEXT_ROW_ALPHA: f32 = 0.35;
+const GRAB_LIFT_SCALE: f32 = 1.2;
+const CLEAR_FLASH_HZ: f32 = 15.0;
+const CLEAR_FLASH_TINT: f32 = 0.6;
+const CLEAR_STEADY_TINT: f32 = 0.35;
+const CLEAR_FACE_SHADE: f32 = 0.4;
+const HOVER_ALPHA: f32 = 0.25;
+const CURSOR_BORDER_THICKNESS: f32 = 2.0;
+const PANEL_WIDTH: f32 = 140.0;
+const PANEL_GAP: f32 = 16.0;
+const PLAYER_GAP: f32 = 80.0;
+const VIEW_MARGIN: f32 = 24.0;
+const GRAVITY_STEP_SECONDS: f32 = 0.1;
+const BLOCK_HOVER_STEPS: u8 = 2;
+const SWAP_BUFFER_TICKS: u8 = 8;
+const DANGER_ROWS: usize = 2;
+const DANGER_PULSE_RATE: f32 = 6.0;
+const DANGER_WOBBLE_RATE: f32 = 30.0;
+const DANGER_WOBBLE_PX: f32 = 1.5;
+const SPEED_BURST_FACTOR: f32 = 3.0;
+const RESULTS_MENU_DELAY: f32 = 1.0;
+const INPUT_REPEAT_DELAY: f32 = 0.25;
+const INPUT_REPEAT_INTERVAL: f32 = 0.08;
+const STICK_DEADZONE: f32 = 0.4;
+const CHAIN_BUCKETS: usize = 8;
+const CHAIN_BAR_MAX: f32 = 56.0;
+const RISE_METER_HEIGHT: f32 = 40.0;
+const SCORE_POPUP_SECONDS: f32 = 0.7;
+const CHAIN_PULSE_SECONDS: f32 = 0.25;
+const CHAIN_PULSE_SCALE: f32 = 0.5;
+const CHAIN_FADE_SECONDS: f32 = 0.8;
+const GARBAGE_PREVIEW_CELL: f32 = 8.0;
+const GARBAGE_PREVIEW_ROWS: usize = 4;
+const GARBAGE_DROP_SECONDS: f32 = 0.3;
+const GARBAGE_DROP_ROWS: f32 = 3.0;
+const GARBAGE_SQUASH_SECONDS: f32 = 0.35;
+const GARBAGE_SQUASH: f32 = 0.25;
+const CANCEL_POPUP_SECONDS: f32 = 1.2;
+const FLOATING_TEXT_RISE: f32 = 36.0;
+const SIM_TICK_HZ: u32 = 60;
+const SIM_MAX_STEPS_PER_FRAME: u32 = 4;
+
+#[derive(States, Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+enum AppState {
+    #[default]
+    Splash,
+    Title,
+    Credits,
+    HowTo,
+    Lobby,
+    Devices,
+    Options,
+    Achievements,
+    Profiles,
+    Versus,
+    Challenges,
+    Trials,
+    Game,
+    Pause,
+}
+
+#[derive(Resource, Debug, Clone, Copy, Eq, PartialEq)]
+enum GameMode {
+    OnePlayer,
+    TwoPlayer,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default, Serialize, Deserialize)]
+enum SoloMode {
+    #[default]
+    Endless,
+    Survival,
+    Marathon,
+    Zen,
+    Challenge(usize),
+    Trial(usize),
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum MenuItem {
+    Play,
+    Solo(SoloMode),
+    Challenges,
+    Trials,
+    TwoPlayer,
+    #[cfg(not(target_arch = "wasm32"))]
+    Network,
+    Options,
+    Achievements,
+    HowTo,
+    Credits,
+    Profile,
+}
+
+fn title_entries(locale: &Locale, profiles: &profiles::Profiles) -> Vec<MenuEntry<MenuItem>> {
+    let modes = vec![
+        MenuEntry::new(
+            MenuItem::Solo(SoloMode::Endless),
+            locale.get("menu.one_player"),
+        ),
+        MenuEntry::new(
+            MenuItem::Solo(SoloMode::Survival),
+            locale.get("menu.survival"),
+        ),
+        MenuEntry::new(
+            MenuItem::Solo(SoloMode::Marathon),
+            locale.get("menu.marathon"),
+        ),
+        MenuEntry::new(MenuItem::Solo(SoloMode::Zen), locale.get("menu.zen")),
+        MenuEntry::new(MenuItem::Challenges, locale.get("menu.challenges")),
+        MenuEntry::new(MenuItem::Trials, locale.get("menu.trials")),
+        MenuEntry::new(MenuItem::TwoPlayer, locale.get("menu.two_player")),
+        #[cfg(not(target_arch = "wasm32"))]
+        MenuEntry::new(MenuItem::Network, locale.get("menu.network")),
+    ];
+    vec![
+        MenuEntry::new(MenuItem::Play, locale.get("menu.play")).with_children(modes),
+        MenuEntry::new(MenuItem::Options, locale.get("menu.options")),
+        MenuEntry::new(MenuItem::Achievements, locale.get("menu.achievements")),
+        MenuEntry::new(MenuItem::HowTo, locale.get("menu.how_to_play")),
+        MenuEntry::new(MenuItem::Credits, locale.get("menu.credits")),
+        MenuEntry::new(
+            MenuItem::Profile,
+            locale.format("menu.profile", &[("name", &profiles.active)]),
+        ),
+    ]
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum PauseItem {
+    Resume,
+    Controllers,
+    QuitToTitle,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+enum PlayerId {
+    P1,
+    P2,
+}
+
+impl PlayerId {
+    const ALL: [PlayerId; 2] = [PlayerId::P1, PlayerId::P2];
+
+    fn label(self) -> &'static str {
+        match self {
+            PlayerId::P1 => "P1",
+            PlayerId::P2 => "P2",
+        }
+    }
+}
+
+fn active_players(mode: GameMode) -> &'static [PlayerId] {
+    match mode {
+        GameMode::OnePlayer => &PlayerId::ALL[..1],
+        GameMode::TwoPlayer => &PlayerId::ALL,
+    }
+}
+
+fn opponents_of(mode: GameMode, player: PlayerId) -> impl Iterator<Item = PlayerId> {
+    active_players(mode)
+        .iter()
+        .copied()
+        .filter(move |id| *id != player)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum PanelSide {
+    Left,
+    Right,
+}
+
+#[derive(Resource)]
+struct Players {
+    p1: PlayerState,
+    p2: PlayerState,
+}
+
+impl Players {
+    fn get(&self, id: PlayerId) -> &PlayerState {
+        match id {
+            PlayerId::P1 => &self.p1,
+            PlayerId::P2 => &self.p2,
+        }
+    }
+
+    fn get_mut(&mut self, id: PlayerId) -> &mut PlayerState {
+        match id {
+            PlayerId::P1 => &mut self.p1,
+            PlayerId::P2 => &mut self.p2,
+        }
+    }
+}
+
+#[derive(Resource)]
+struct PlayerViews {
+    p1: PlayerView,
+    p2: Option<PlayerView>,
+    ghost: Option<PlayerView>,
+    arrangement: BoardArrangement,
+    observer: bool,
+}
+
+impl PlayerViews {
+    // The observer view lays the boards out as if they had no side panels.
+    fn layout_panel_width(&self) -> f32 {
+        if self.observer {
+            0.0
+        } else {
+            self.p1.panel_width
+        }
+    }
+
+    // A ghost board takes the second player's place on screen.
+    fn layout_mode(&self, mode: GameMode) -> GameMode {
+        if self.ghost.is_some() {
+            GameMode::TwoPlayer
+        } else {
+            mode
+        }
+    }
+
+    fn second(&mut self) -> Option<&mut PlayerView> {
+        self.p2.as_mut().or(self.ghost.as_mut())
+    }
+}
+
+// How two boards share the window: next to each other, or one above the other
+// when the window is too narrow for that.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum BoardArrangement {
+    #[default]
+    SideBySide,
+    Stacked,
+}
+
+// Idle -> Clearing when a swap or rise makes a match, or when blocks settle into one.
+// Idle/Clearing -> Falling when gravity moves a block; a clear always ends in Idle.
+// Falling/Converting/DroppingGarbage -> Clearing or Idle once the grid settles; a
+// chain that settles with cracked garbage goes Converting while it reveals, and
+// only ends once the revealed blocks settle without a match.
+// Idle -> DroppingGarbage when queued garbage is inserted. Topping out -> Dying.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PlayerPhase {
+    Idle,
+    Clearing,
+    Falling,
+    Converting,
+    DroppingGarbage,
+    Dying,
+}
+
+impl PlayerPhase {
+    fn settled(self) -> bool {
+        matches!(self, PlayerPhase::Idle | PlayerPhase::Clearing)
+    }
+
+    #[cfg(feature = "debug")]
+    fn label(self) -> &'static str {
+        match self {
+            PlayerPhase::Idle => "idle",
+            PlayerPhase::Clearing => "clearing",
+            PlayerPhase::Falling => "falling",
+            PlayerPhase::Converting => "converting",
+            PlayerPhase::DroppingGarbage => "garbage",
+            PlayerPhase::Dying => "dying",
+        }
+    }
+}
+
+struct PlayerState {
+    grid: Grid,
+    cursor: Cursor,
+    score: u32,
+    elapsed: f32,
+    phase: PlayerPhase,
+    pending_clears: VecDeque<PendingClear>,
+    clear_timing: tuning::ClearTiming,
+    gravity_timer: Timer,
+    rise_timer: Timer,
+    rise_pause_timer: Timer,
+    rise_paused: bool,
+    rise_level: u32,
+    rows_at_level: u32,
+    level_splits: Vec<f32>,
+    start_level: u32,
+    repeat_dir: Option<IVec2>,
+    repeat_timer: Timer,
+    repeat_initial: bool,
+    sticky_dir: Option<IVec2>,
+    swap_repeat_timer: Timer,
+    chain_index: u32,
+    chain_ended: bool,
+    chain_counts: [u32; CHAIN_BUCKETS],
+    garbage_outgoing: u32,
+    garbage_earned: u32,
+    garbage_queue: VecDeque<u32>,
+    items_outgoing: Vec<ItemKind>,
+    item_queue: VecDeque<(ItemKind, f32)>,
+    item_freeze: f32,
+    speed_burst: f32,
+    rise_scale: f32,
+    topped_seconds: f32,
+    garbage_sent: u32,
+    garbage_received: u32,
+    blocks_cleared: u32,
+    swaps_made: u32,
+    best_chain: u32,
+    waves_dropped: u32,
+    buffered_swap: Option<BufferedSwap>,
+    grab_cursor: bool,
+    holding: bool,
+    vertical_swaps: bool,
+    cursor_vertical: bool,
+    in_danger: bool,
+}
+
+impl PlayerState {
+    fn new() -> Self {
+        let tuning = Tuning::builtin();
+        Self {
+            grid: Grid::new(GRID_W, GRID_H),
+            cursor: Cursor::new(0, 0),
+            score: 0,
+            elapsed: 0.0,
+            phase: PlayerPhase::Idle,
+            pending_clears: VecDeque::new(),
+            clear_timing: tuning.clear,
+            gravity_timer: Timer::from_seconds(GRAVITY_STEP_SECONDS, TimerMode::Repeating),
+            rise_timer: Timer::from_seconds(tuning.rise.interval(0), TimerMode::Repeating),
+            rise_pause_timer: Timer::from_seconds(tuning.rise_pause, TimerMode::Repeating),
+            rise_paused: false,
+            rise_level: 0,
+            rows_at_level: 0,
+            level_splits: vec![0.0],
+            start_level: 0,
+            repeat_dir: None,
+            repeat_timer: Timer::from_seconds(INPUT_REPEAT_DELAY, TimerMode::Once),
+            repeat_initial: true,
+            sticky_dir: None,
+            swap_repeat_timer: Timer::from_seconds(INPUT_REPEAT_DELAY, TimerMode::Repeating),
+            chain_index: 0,
+            chain_ended: false,
+            chain_counts: [0; CHAIN_BUCKETS],
+            garbage_outgoing: 0,
+            garbage_earned: 0,
+            garbage_queue: VecDeque::new(),
+            items_outgoing: Vec::new(),
+            item_queue: VecDeque::new(),
+            item_freeze: 0.0,
+            speed_burst: 0.0,
+            rise_scale: 1.0,
+            topped_seconds: 0.0,
+            garbage_sent: 0,
+            garbage_received: 0,
+            blocks_cleared: 0,
+            swaps_made: 0,
+            best_chain: 0,
+            waves_dropped: 0,
+            buffered_swap: None,
+            grab_cursor: false,
+            holding: false,
+            vertical_swaps: false,
+            cursor_vertical: false,
+            in_danger: false,
+        }
+    }
+
+    fn garbage_incoming(&self) -> u32 {
+        self.garbage_queue.iter().sum()
+    }
+}
+
+#[derive(Resource)]
+struct UiTexts {
+    score: Entity,
+    timer: Entity,
+    garbage_sent: Entity,
+    garbage_incoming: Entity,
+    status: Entity,
+    danger: Entity,
+}
+
+struct RiseMeter {
+    root: Entity,
+    level: Entity,
+    fill: Entity,
+}
+
+struct ChainChart {
+    root: Entity,
+    bars: [Entity; CHAIN_BUCKETS],
+    counts: [Entity; CHAIN_BUCKETS],
+}
+
+struct PlayerView {
+    background: Vec<Entity>,
+    blocks: pool::BlockPool,
+    next_row: Vec<Entity>,
+    frame: [Entity; 4],
+    cursor: Entity,
+    hover: Entity,
+    chain_indicator: Entity,
+    garbage_preview: Entity,
+    panel: Entity,
+    ui: UiTexts,
+    rise_meter: RiseMeter,
+    chains: ChainChart,
+    origin: Vec2,
+    mirrored: bool,
+    panel_side: PanelSide,
+    panel_width: f32,
+}
+
+struct PendingClear {
+    marks: Vec<bool>,
+    order: Vec<usize>,
+    timing: tuning::ClearTiming,
+    timer: Timer,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ClearLook {
+    Flash { lit: bool },
+    Waiting,
+    Popped,
+}
+
+impl PendingClear {
+    // Blocks pop top row first, left to right within a row.
+    fn new(marks: Vec<bool>, width: usize, timing: tuning::ClearTiming) -> Self {
+        let mut order: Vec<usize> = (0..marks.len()).filter(|&idx| marks[idx]).collect();
+        order.sort_by_key(|&idx| (std::cmp::Reverse(idx / width), idx % width));
+        let timer = Timer::from_seconds(timing.duration(order.len()), TimerMode::Once);
+        Self {
+            marks,
+            order,
+            timing,
+            timer,
+        }
+    }
+
+    fn look(&self, idx: usize) -> Option<ClearLook> {
+        let order = self.order.iter().position(|&cell| cell == idx)?;
+        let elapsed = self.timer.elapsed_secs();
+        Some(if elapsed < self.timing.flash {
+            ClearLook::Flash {
+                lit: ((elapsed * CLEAR_FLASH_HZ) as u32).is_multiple_of(2),
+            }
+        } else if elapsed >= self.timing.pop_at(order) {
+            ClearLook::Popped
+        } else {
+            ClearLook::Waiting
+        })
+    }
+}
+
+#[derive(Clone, Copy)]
+struct BufferedSwap {
+    cmd: SwapCmd,
+    ticks_left: u8,
+}
+
+#[derive(Resource)]
+struct MenuRoot(Entity);
+
+#[derive(Resource)]
+struct PauseRoot(Entity);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ResultsItem {
+    Rematch,
+    QuitToTitle,
+}
+
+#[derive(Component)]
+struct ResultsMenu;
+
+#[derive(Component)]
+struct GameEntity;
+
+#[derive(Resource, Default)]
+struct GameInitialized(bool);
+
+// Presentation waits for Ready, which setup_game sets once the match's views
+// exist and going back to the title clears, so no drawing system ever runs
+// against a board that hasn't been spawned.
+#[derive(States, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+enum ViewState {
+    #[default]
+    Missing,
+    Ready,
+}
+
+fn views_ready(state: Option<Res<State<ViewState>>>, views: Option<Res<PlayerViews>>) -> bool {
+    views.is_some() && state.is_some_and(|state| *state.get() == ViewState::Ready)
+}
+
+#[derive(Resource, Default)]
+struct ObserverView {
+    enabled: bool,
+}
+
+#[derive(Default)]
+struct GarbageMotion {
+    received: u32,
+    drop: f32,
+    squash: f32,
+    garbage: Vec<bool>,
+    falling: Vec<bool>,
+    entering: Vec<bool>,
+    landed: Vec<bool>,
+}
+
+impl GarbageMotion {
+    fn drop_offset(&self, idx: usize) -> f32 {
+        let moving = self.falling.get(idx).copied().unwrap_or(false)
+            || self.entering.get(idx).copied().unwrap_or(false);
+        if moving {
+            self.drop / GARBAGE_DROP_SECONDS * GARBAGE_DROP_ROWS * CELL_SIZE
+        } else {
+            0.0
+        }
+    }
+
+    // Starts squashed, springs past its rest height once and settles back.
+    fn squash(&self, idx: usize) -> f32 {
+        if !self.landed.get(idx).copied().unwrap_or(false) {
+            return 0.0;
+        }
+        let left = self.squash / GARBAGE_SQUASH_SECONDS;
+        GARBAGE_SQUASH * left * ((1.0 - left) * std::f32::consts::TAU).cos()
+    }
+}
+
+#[derive(Resource, Default)]
+struct GarbageMotions {
+    p1: GarbageMotion,
+    p2: GarbageMotion,
+}
+
+impl GarbageMotions {
+    fn get_mut(&mut self, id: PlayerId) -> &mut GarbageMotion {
+        match id {
+            PlayerId::P1 => &mut self.p1,
+            PlayerId::P2 => &mut self.p2,
+        }
+    }
+}
+
+#[derive(Resource)]
+struct ObserverHud {
+    root: Entity,
+    p1: Entity,
+    p2: Entity,
+}
+
+#[derive(Resource, Default)]
+struct MatchOver {
+    active: bool,
+    winner: Option<PlayerId>,
+    rounds: [u32; 2],
+}
+
+impl MatchOver {
+    fn rounds(&self, id: PlayerId) -> u32 {
+        match id {
+            PlayerId::P1 => self.rounds[0],
+            PlayerId::P2 => self.rounds[1],
+        }
+    }
+
+    fn award_round(&mut self, id: PlayerId) {
+        match id {
+            PlayerId::P1 => self.rounds[0] += 1,
+            PlayerId::P2 => self.rounds[1] += 1,
+        }
+    }
+
+    fn series_decided(&self, rules: &MatchRules) -> bool {
+        PlayerId::ALL
+            .iter()
+            .any(|id| self.rounds(*id) >= rules.rounds_to_win())
+    }
+}
+
+#[derive(Resource, Default)]
+struct MatchOverTimer {
+    seconds: f32,
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+struct ClearEvent {
+    player: PlayerId,
+    chain: u32,
+    cleared: u32,
+    score: ScoreBreakdown,
+    centroid: Vec2,
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+struct LevelUpEvent {
+    player: PlayerId,
+    level: u32,
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+struct ItemEvent {
+    player: PlayerId,
+    item: ItemKind,
+    landed: bool,
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+struct GarbageCancelEvent {
+    units: u32,
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+struct GarbageCounterEvent {
+    player: PlayerId,
+    units: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Feedback {
+    GarbageLanded,
+    Danger,
+    Defeat,
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+struct FeedbackEvent {
+    player: PlayerId,
+    kind: Feedback,
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+struct MatchResultEvent {
+    winner: Option<PlayerId>,
+}
+
+#[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+struct SimulationTick;
+
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum GameSet {
+    Input,
+    Simulation,
+    GarbageResolve,
+    Presentation,
+}
+
+// Slows how quickly real time feeds the tick accumulator, so every gameplay timer
+// scales together while each tick still advances by the same fixed delta.
+#[derive(Resource)]
+struct SimTimeScale(f32);
+
+impl Default for SimTimeScale {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+#[derive(Resource)]
+struct SimClock {
+    frame: u64,
+    delta: Duration,
+    accumulator: Duration,
+    held: bool,
+    queued_steps: u32,
+}
+
+impl Default for SimClock {
+    fn default() -> Self {
+        Self {
+            frame: 0,
+            delta: Duration::from_secs(1) / SIM_TICK_HZ,
+            accumulator: Duration::ZERO,
+            held: false,
+            queued_steps: 0,
+        }
+    }
+}
+
+impl SimClock {
+    fn reset(&mut self) {
+        self.frame = 0;
+        self.accumulator = Duration::ZERO;
+    }
+
+    fn accumulate(&mut self, real_delta: Duration) -> u32 {
+        if self.held {
+            self.accumulator = Duration::ZERO;
+            return std::mem::take(&mut self.queued_steps);
+        }
+        self.accumulator =
+            (self.accumulator + real_delta).min(self.delta * SIM_MAX_STEPS_PER_FRAME);
+        let steps = (self.accumulator.as_nanos() / self.delta.as_nanos()) as u32;
+        self.accumulator -= self.delta * steps;
+        steps
+    }
+}
+
+// Match time, as opposed to the tick counter above: it only moves while a match
+// is actually being played, so every gameplay timer stops together on pause and
+// once the match is decided.
+#[derive(Resource, Default)]
+struct MatchClock {
+    elapsed: Duration,
+    delta: Duration,
+}
+
+impl MatchClock {
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    fn advance(&mut self, tick: Duration, frozen: bool) {
+        self.delta = if frozen { Duration::ZERO } else { tick };
+        self.elapsed += self.delta;
+    }
+}
+
+fn advance_match_clock(
+    sim: Res<SimClock>,
+    state: Option<Res<State<AppState>>>,
+    match_over: Res<MatchOver>,
+    mut clock: ResMut<MatchClock>,
+) {
+    let paused = state.is_some_and(|state| *state.get() != AppState::Game);
+    clock.advance(sim.delta, paused || match_over.active);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+enum PlayerCommand {
+    Move { dx: i8, dy: i8 },
+    Swap,
+    MoveTo { x: u8, y: u8 },
+    Rotate,
+    Raise,
+}
+
+#[derive(Resource, Default)]
+struct CommandQueue {
+    pending: Vec<(PlayerId, PlayerCommand)>,
+    scheduled: BTreeMap<u64, Vec<(PlayerId, PlayerCommand)>>,
+    // Set by a netplay session: only frames below this bound have inputs from every
+    // peer, and the session (not the driver) decides which frame `pending` lands on.
+    lockstep_limit: Option<u64>,
+}
+
+impl CommandQueue {
+    fn push(&mut self, player: PlayerId, command: PlayerCommand) {
+        self.pending.push((player, command));
+    }
+
+    fn seal(&mut self, frame: u64) -> Vec<PlayerCommand> {
+        let pending = std::mem::take(&mut self.pending);
+        let sealed = pending.iter().map(|(_, command)| *command).collect();
+        self.scheduled.entry(frame).or_default().extend(pending);
+        sealed
+    }
+
+    fn clear(&mut self) {
+        self.pending.clear();
+        self.scheduled.clear();
+    }
+}
+
+#[derive(Resource, Default, Clone, Copy)]
+struct MatchSeed(Option<u64>);
+
+#[derive(Resource, Default, Clone, Copy)]
+struct LocalControl(Option<PlayerId>);
+
+#[derive(Resource, Default, Clone, Copy)]
+struct CpuControl {
+    p1: bool,
+    p2: bool,
+}
+
+impl CpuControl {
+    fn players(ids: &[PlayerId]) -> Self {
+        Self {
+            p1: ids.contains(&PlayerId::P1),
+            p2: ids.contains(&PlayerId::P2),
+        }
+    }
+
+    fn controls(self, id: PlayerId) -> bool {
+        match id {
+            PlayerId::P1 => self.p1,
+            PlayerId::P2 => self.p2,
+        }
+    }
+
+    fn any(self) -> bool {
+        self.p1 || self.p2
+    }
+}
+
+#[derive(Resource, Clone, Copy)]
+struct StickSettings {
+    deadzone: f32,
+}
+
+impl Default for StickSettings {
+    fn default() -> Self {
+        Self {
+            deadzone: STICK_DEADZONE,
+        }
+    }
+}
+
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+struct MatchRules {
+    start_level: u32,
+    pacing: pacing::Pacing,
+    versus: Option<versus::VersusRules>,
+    solo: SoloMode,
+    grab_cursor: bool,
+    bomb_rate: f64,
+    adaptive_difficulty: bool,
+    solo_time_scale: f32,
+}
+
+impl Default for MatchRules {
+    fn default() -> Self {
+        Self {
+            start_level: 0,
+            pacing: pacing::Pacing::default(),
+            versus: None,
+            solo: SoloMode::Endless,
+            grab_cursor: false,
+            bomb_rate: 0.0,
+            adaptive_difficulty: false,
+            solo_time_scale: 1.0,
+        }
+    }
+}
+
+impl MatchRules {
+    fn level(&self, id: PlayerId) -> u32 {
+        if let SoloMode::Challenge(index) = self.solo
+            && let Some(challenge) = challenges::all().get(index)
+        {
+            return challenge.speed;
+        }
+        if let SoloMode::Trial(index) = self.solo
+            && let Some(trial) = trials::all().get(index)
+        {
+            return trial.speed;
+        }
+        self.versus
+            .map_or(self.start_level, |versus| versus.level(id))
+    }
+
+    fn colors(&self) -> u8 {
+        self.versus.map_or(game::MAX_COLORS, |versus| versus.colors)
+    }
+
+    fn scale_garbage(&self, units: u32) -> u32 {
+        self.versus.map_or(units, |versus| {
+            (units * versus.garbage_percent).div_ceil(100)
+        })
+    }
+
+    fn rounds_to_win(&self) -> u32 {
+        self.versus.map_or(1, |versus| versus.best_of / 2 + 1)
+    }
+}
+
+#[derive(SystemParam)]
+struct MatchControl<'w> {
+    players: ResMut<'w, Players>,
+    clock: ResMut<'w, SimClock>,
+    match_clock: ResMut<'w, MatchClock>,
+    time_scale: ResMut<'w, SimTimeScale>,
+    queue: ResMut<'w, CommandQueue>,
+    trace: ResMut<'w, trace::InputTrace>,
+    match_over: ResMut<'w, MatchOver>,
+    seed: Res<'w, MatchSeed>,
+    rules: Res<'w, MatchRules>,
+    garbage_rules: ResMut<'w, GarbageRules>,
+    tuning: ResMut<'w, Tuning>,
+    latest_tuning: Res<'w, LatestTuning>,
+    cpu: Res<'w, CpuControl>,
+}
+
+impl MatchControl<'_> {
+    // Each match snapshots the newest tuning table along with its rules, so a
+    // table saved mid-match only applies from the next one.
+    fn start(&mut self, mode: GameMode) {
+        let seed = self.seed.0.unwrap_or_else(random);
+        *self.tuning = self.latest_tuning.0.clone();
+        *self.garbage_rules = self.garbage_rules.with_tuning(&self.tuning);
+        let pacing = self.rules.pacing.with_tuning(&self.tuning);
+        let items = self.rules.versus.is_some_and(|versus| versus.items);
+        let (width, height) = match self.rules.solo {
+            SoloMode::Challenge(index) => challenges::all()
+                .get(index)
+                .map_or((GRID_W, GRID_H), challenges::Challenge::board_size),
+            _ => (GRID_W, GRID_H),
+        };
+        let players = &mut *self.players;
+        for player in [&mut players.p1, &mut players.p2] {
+            player.grid.clear();
+            resize_board(player, width, height);
+            player.grid.reseed(seed);
+            player.grid.set_hover_steps(pacing.hover_steps);
+            player.clear_timing = pacing.clear;
+            player.gravity_timer = Timer::from_seconds(pacing.gravity_step, TimerMode::Repeating);
+            player.grid.set_color_count(self.rules.colors());
+            player.grid.set_bomb_rate(self.rules.bomb_rate);
+            player
+                .grid
+                .set_item_rate(if items { items::ITEM_RATE } else { 0.0 });
+        }
+        self.trace.begin(
+            seed,
+            mode,
+            *self.rules,
+            *self.garbage_rules,
+            (width, height),
+            self.tuning.fingerprint(),
+        );
+        reset_player(
+            &mut self.players.p1,
+            self.rules.level(PlayerId::P1),
+            &self.tuning,
+        );
+        reset_player(
+            &mut self.players.p2,
+            self.rules.level(PlayerId::P2),
+            &self.tuning,
+        );
+        let vertical_swaps = self
+            .rules
+            .versus
+            .is_some_and(|versus| versus.vertical_swaps);
+        for id in [PlayerId::P1, PlayerId::P2] {
+            let player = self.players.get_mut(id);
+            player.grab_cursor = self.rules.grab_cursor && !self.cpu.controls(id);
+            player.vertical_swaps = vertical_swaps;
+        }
+        if let SoloMode::Challenge(index) = self.rules.solo
+            && let Some(challenge) = challenges::all().get(index)
+        {
+            challenges::prepare(challenge, &mut self.players.p1);
+        }
+        if let SoloMode::Trial(index) = self.rules.solo
+            && let Some(trial) = trials::all().get(index)
+        {
+            trials::prepare(trial, &mut self.players.p1);
+        }
+        self.clock.reset();
+        self.match_clock.reset();
+        self.time_scale.0 = match mode {
+            GameMode::OnePlayer => self.rules.solo_time_scale,
+            GameMode::TwoPlayer => 1.0,
+        };
+        self.queue.clear();
+        self.match_over.active = false;
+        self.match_over.winner = None;
+    }
+
+    fn start_series(&mut self, mode: GameMode) {
+        self.match_over.rounds = [0; 2];
+        self.start(mode);
+    }
+}
+
+#[derive(Component)]
+struct FloatingText {
+    timer: Timer,
+    start: Vec3,
+    color: Color,
+}
+
+fn add_simulation(app: &mut App) {
+    profiling::register(app);
+    app.insert_resource(Players {
+        p1: PlayerState::new(),
+        p2: PlayerState::new(),
+    })
+    .insert_resource(GameMode::OnePlayer)
+    .insert_resource(MatchOver::default())
+    .insert_resource(MatchOverTimer::default())
+    .insert_resource(SimClock::default())
+    .init_resource::<MatchClock>()
+    .init_resource::<SimTimeScale>()
+    .insert_resource(CommandQueue::default())
+    .insert_resource(MatchSeed::default())
+    .insert_resource(CpuControl::default())
+    .init_resource::<cpu::CpuBots>()
+    .insert_resource(MatchRules::default())
+    .init_resource::<GarbageRules>()
+    .init_resource::<Tuning>()
+    .init_resource::<LatestTuning>()
+    .init_resource::<difficulty::AdaptiveDifficulty>()
+    .init_resource::<trace::InputTrace>()
+    .add_event::<ClearEvent>()
+    .add_event::<GarbageCancelEvent>()
+    .add_event::<GarbageCounterEvent>()
+    .add_event::<LevelUpEvent>()
+    .add_event::<ItemEvent>()
+    .add_event::<FeedbackEvent>()
+    .add_event::<MatchResultEvent>()
+    .configure_sets(
+        SimulationTick,
+        (GameSet::Input, GameSet::Simulation, GameSet::GarbageResolve).chain(),
+    )
+    .add_systems(SimulationTick, advance_match_clock.before(GameSet::Input))
+    .add_systems(
+        SimulationTick,
+        (
+            cpu::cpu_commands,
+            apply_player_commands,
+            apply_buffered_swaps,
+        )
+            .chain()
+            .in_set(GameSet::Input),
+    )
+    .add_systems(
+        SimulationTick,
+        (
+            update_time,
+            update_rise_pause,
+            rise_stack,
+            apply_gravity_system,
+            update_clear_delay,
+            marathon::advance_marathon_level,
+            update_danger,
+            difficulty::adapt_difficulty,
+        )
+            .chain()
+            .in_set(GameSet::Simulation),
+    )
+    .add_systems(
+        SimulationTick,
+        (
+            survival::drop_survival_waves,
+            items::deliver_items,
+            resolve_garbage,
+            items::apply_incoming_items,
+        )
+            .chain()
+            .in_set(GameSet::GarbageResolve),
+    );
+}
+
+pub fn run() {
+    let options = cli::LaunchOptions::from_env();
+    #[cfg(not(target_arch = "wasm32"))]
+    if options.headless {
+        headless::run(&options);
+        return;
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    if options.tui {
+        tui::run(&options);
+        return;
+    }
+
+    App::new()
+        .insert_resource(options)
+        .add_plugins((DefaultPlugins, TetanusAttackPlugin::default()))
+        .run();
+}
+
+fn setup_camera(mut commands: Commands) {
+    commands.spawn(Camera2dBundle::default());
+}
+
+#[allow(clippy::too_many_arguments)]
+fn apply_launch_options(
+    options: Res<cli::LaunchOptions>,
+    mut mode: ResMut<GameMode>,
+    mut seed: ResMut<MatchSeed>,
+    mut rules: ResMut<MatchRules>,
+    mut cpu: ResMut<CpuControl>,
+    mut bots: ResMut<cpu::CpuBots>,
+    mut stick: ResMut<StickSettings>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if let Some(deadzone) = options.deadzone {
+        stick.deadzone = deadzone;
+    }
+    if let Some(value) = options.seed {
+        seed.0 = Some(value);
+    }
+    if let Some(level) = options.speed {
+        rules.start_level = level;
+    }
+    if let Some(think) = options.bot_think {
+        *bots = cpu::CpuBots::greedy(think, options.seed.unwrap_or(0));
+    }
+    if let Some(launch) = options.mode {
+        *mode = launch.game_mode();
+        *cpu = CpuControl::players(launch.cpu_players());
+        rules.solo = launch.solo_mode();
+        next_state.set(AppState::Game);
+    }
+}
+
+fn setup_menu(
+    mut commands: Commands,
+    palette: Res<Palette>,
+    locale: Res<Locale>,
+    profiles: Res<profiles::Profiles>,
+) {
+    let root = commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(0.0),
+                top: Val::Percent(0.0),
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(16.0),
+                ..Default::default()
+            },
+            background_color: BackgroundColor(palette.overlay),
+            ..Default::default()
+        })
+        .id();
+
+    commands.entity(root).with_children(|parent| {
+        parent.spawn(TextBundle {
+            text: Text::from_section(
+                locale.get("title"),
+                TextStyle {
+                    font: Default::default(),
+                    font_size: 42.0,
+                    color: palette.heading,
+                },
+            ),
+            ..Default::default()
+        });
+
+        parent.spawn((
+            TextBundle {
+                text: Text::default().with_justify(JustifyText::Center),
+                ..Default::default()
+            },
+            Menu::new(title_entries(&locale, &profiles)),
+        ));
+
+        parent.spawn(TextBundle {
+            text: Text::from_section(
+                locale.get("menu.hint"),
+                TextStyle {
+                    font: Default::default(),
+                    font_size: 18.0,
+                    color: palette.text,
+                },
+            ),
+            ..Default::default()
+        });
+    });
+
+    commands.insert_resource(MenuRoot(root));
+}
+
+fn cleanup_menu(mut commands: Commands, menu: Res<MenuRoot>) {
+    commands.entity(menu.0).despawn_recursive();
+}
+
+fn setup_pause(
+    mut commands: Commands,
+    lost: Res<devices::LostController>,
+    palette: Res<Palette>,
+    locale: Res<Locale>,
+) {
+    let root = commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(0.0),
+                top: Val::Percent(0.0),
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(12.0),
+                ..Default::default()
+            },
+            background_color: BackgroundColor(palette.pause_overlay),
+            ..Default::default()
+        })
+        .id();
+
+    commands.entity(root).with_children(|parent| {
+        parent.spawn(TextBundle {
+            text: Text::from_section(
+                locale.get("pause.title"),
+                TextStyle {
+                    font: Default::default(),
+                    font_size: 36.0,
+                    color: palette.heading,
+                },
+            ),
+            ..Default::default()
+        });
+
+        if let Some(id) = lost.0 {
+            parent.spawn(TextBundle {
+                text: Text::from_section(
+                    locale.format("pause.lost_controller", &[("player", &id.label())]),
+                    TextStyle {
+                        font: Default::default(),
+                        font_size: 22.0,
+                        color: palette.warning,
+                    },
+                )
+                .with_justify(JustifyText::Center),
+                ..Default::default()
+            });
+        }
+
+        parent.spawn((
+            TextBundle {
+                text: Text::default().with_justify(JustifyText::Center),
+                ..Default::default()
+            },
+            Menu::new(vec![
+                MenuEntry::new(PauseItem::Resume, locale.get("pause.resume")),
+                MenuEntry::new(PauseItem::Controllers, locale.get("pause.controllers")),
+                MenuEntry::new(PauseItem::QuitToTitle, locale.get("pause.quit")),
+            ]),
+        ));
+
+        parent.spawn(TextBundle {
+            text: Text::from_section(
+                locale.get("pause.hint"),
+                TextStyle {
+                    font: Default::default(),
+                    font_size: 18.0,
+                    color: palette.text,
+                },
+            )
+            .with_justify(JustifyText::Center),
+            ..Default::default()
+        });
+    });
+
+    commands.insert_resource(PauseRoot(root));
+}
+
+fn cleanup_pause(mut commands: Commands, pause: Res<PauseRoot>) {
+    commands.entity(pause.0).despawn_recursive();
+}
+
+fn cleanup_game(
+    mut commands: Commands,
+    entities: Query<Entity, With<GameEntity>>,
+    mut initialized: ResMut<GameInitialized>,
+    mut view_state: ResMut<NextState<ViewState>>,
+) {
+    for entity in &entities {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<PlayerViews>();
+    commands.remove_resource::<ObserverHud>();
+    view_state.set(ViewState::Missing);
+    initialized.0 = false;
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_title_menu(
+    mut events: EventReader<MenuEvent<MenuItem>>,
+    gamepads: Res<Gamepads>,
+    settings: Res<settings::Settings>,
+    mut mode: ResMut<GameMode>,
+    mut cpu: ResMut<CpuControl>,
+    mut rules: ResMut<MatchRules>,
+    mut garbage_rules: ResMut<GarbageRules>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for event in events.read() {
+        let MenuEvent::Confirm(item) = *event else {
+            continue;
+        };
+        match item {
+            MenuItem::Play => {}
+            MenuItem::Solo(solo) => {
+                *mode = GameMode::OnePlayer;
+                *cpu = CpuControl::default();
+                rules.versus = None;
+                rules.solo = solo;
+                rules.grab_cursor = settings.grab_cursor;
+                rules.bomb_rate = settings.bomb_rate();
+                rules.adaptive_difficulty = settings.adaptive_difficulty
+                    && matches!(solo, SoloMode::Endless | SoloMode::Survival);
+                rules.solo_time_scale = settings.solo_time_scale();
+                rules.pacing = pacing::Pacing::default();
+                *garbage_rules = GarbageRules::default();
+                next_state.set(local_start_state(&gamepads));
+            }
+            MenuItem::Challenges => {
+                rules.grab_cursor = settings.grab_cursor;
+                rules.bomb_rate = 0.0;
+                rules.adaptive_difficulty = false;
+                rules.solo_time_scale = settings.solo_time_scale();
+                rules.pacing = pacing::Pacing::default();
+                next_state.set(AppState::Challenges);
+            }
+            MenuItem::Trials => {
+                rules.grab_cursor = settings.grab_cursor;
+                rules.bomb_rate = 0.0;
+                rules.adaptive_difficulty = false;
+                rules.solo_time_scale = settings.solo_time_scale();
+                rules.pacing = pacing::Pacing::default();
+                next_state.set(AppState::Trials);
+            }
+            MenuItem::TwoPlayer => {
+                *mode = GameMode::TwoPlayer;
+                *cpu = CpuControl::default();
+                rules.solo = SoloMode::Endless;
+                rules.grab_cursor = settings.grab_cursor;
+                rules.bomb_rate = settings.bomb_rate();
+                rules.adaptive_difficulty = false;
+                next_state.set(AppState::Versus);
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            MenuItem::Network => {
+                rules.versus = None;
+                rules.solo = SoloMode::Endless;
+                rules.grab_cursor = false;
+                rules.bomb_rate = 0.0;
+                rules.adaptive_difficulty = false;
+                rules.pacing = pacing::Pacing::default();
+                *garbage_rules = GarbageRules::default();
+                next_state.set(AppState::Lobby);
+            }
+            MenuItem::Options => next_state.set(AppState::Options),
+            MenuItem::Achievements => next_state.set(AppState::Achievements),
+            MenuItem::HowTo => next_state.set(AppState::HowTo),
+            MenuItem::Credits => next_state.set(AppState::Credits),
+            MenuItem::Profile => next_state.set(AppState::Profiles),
+        }
+    }
+}
+
+fn local_start_state(gamepads: &Gamepads) -> AppState {
+    if gamepads.iter().next().is_some() {
+        AppState::Devices
+    } else {
+        AppState::Game
+    }
+}
+
+fn handle_pause_input(
+    input: MenuInput,
+    mut events: EventReader<MenuEvent<PauseItem>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if input.pressed(&[KeyCode::KeyC], &[GamepadButtonType::Select]) {
+        events.clear();
+        next_state.set(AppState::Devices);
+        return;
+    }
+    if input.pressed(&[KeyCode::Tab], &[GamepadButtonType::Start]) {
+        events.clear();
+        next_state.set(AppState::Game);
+        return;
+    }
+    for event in events.read() {
+        match *event {
+            MenuEvent::Confirm(PauseItem::Resume) | MenuEvent::Cancel => {
+                next_state.set(AppState::Game);
+            }
+            MenuEvent::Confirm(PauseItem::Controllers) => next_state.set(AppState::Devices),
+            MenuEvent::Confirm(PauseItem::QuitToTitle) => next_state.set(AppState::Title),
+            MenuEvent::Adjust(..) => {}
+        }
+    }
+}
+
+fn handle_pause_request(
+    keys: Res<ButtonInput<KeyCode>>,
+    buttons: Res<ButtonInput<GamepadButton>>,
+    gamepads: Res<Gamepads>,
+    match_over: Res<MatchOver>,
+    local: Res<LocalControl>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if match_over.active || local.0.is_some() {
+        return;
+    }
+    let keyboard = keys.just_pressed(KeyCode::Escape)
+        || keys.just_pressed(KeyCode::Tab)
+        || keys.just_pressed(KeyCode::Backspace);
+    let mut gamepad = false;
+    for gamepad_id in gamepads.iter() {
+        gamepad |= buttons.just_pressed(GamepadButton::new(gamepad_id, GamepadButtonType::Start));
+    }
+    if keyboard || gamepad {
+        next_state.set(AppState::Pause);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn setup_game(
+    mut commands: Commands,
+    mut control: MatchControl,
+    mode: Res<GameMode>,
+    mut match_over_timer: ResMut<MatchOverTimer>,
+    mut initialized: ResMut<GameInitialized>,
+    mut view_state: ResMut<NextState<ViewState>>,
+    settings: Res<settings::Settings>,
+    palette: Res<Palette>,
+    locale: Res<Locale>,
+) {
+    if initialized.0 {
+        return;
+    }
+    control.start_series(*mode);
+    match_over_timer.seconds = 0.0;
+    let players = &control.players;
+
+    let panel_width = PANEL_WIDTH * settings.ui_scale();
+    let (p1_origin, p2_origin) = compute_player_origins(
+        *mode,
+        panel_width,
+        [PlayerId::P1, PlayerId::P2].map(|id| settings.board_layout(id).panel),
+        BoardArrangement::SideBySide,
+    );
+
+    let p1_view = spawn_player_view(
+        &mut commands,
+        &players.p1,
+        p1_origin,
+        settings.board_layout(PlayerId::P1),
+        panel_width,
+        &palette,
+        &locale,
+    );
+
+    let p2_view = if *mode == GameMode::TwoPlayer {
+        Some(spawn_player_view(
+            &mut commands,
+            &players.p2,
+            p2_origin,
+            settings.board_layout(PlayerId::P2),
+            panel_width,
+            &palette,
+            &locale,
+        ))
+    } else {
+        None
+    };
+
+    commands.insert_resource(PlayerViews {
+        p1: p1_view,
+        p2: p2_view,
+        ghost: None,
+        arrangement: BoardArrangement::SideBySide,
+        observer: false,
+    });
+    let observer_hud = spawn_observer_hud(&mut commands, &palette);
+    commands.insert_resource(observer_hud);
+    view_state.set(ViewState::Ready);
+    initialized.0 = true;
+}
+
+// Clears in flight are dropped rather than remapped: their marks index the old
+// board, and whatever still matches after the resize matches again on the next
+// settle. A running chain ends at the resize, so what it earned is still sent.
+fn resize_board(player: &mut PlayerState, width: usize, height: usize) -> bool {
+    if (width, height) == (player.grid.width, player.grid.height) {
+        return true;
+    }
+    if !player.grid.resize(width, height) {
+        return false;
+    }
+    player.pending_clears.clear();
+    if player.phase == PlayerPhase::Clearing {
+        player.phase = PlayerPhase::Idle;
+    }
+    if player.chain_index > 0 {
+        player.chain_index = 0;
+        player.chain_ended = true;
+    }
+    let (span_x, span_y) = cursor_span(player);
+    player.cursor.x = player.cursor.x.min(width.saturating_sub(span_x));
+    player.cursor.y = player.cursor.y.min(height.saturating_sub(span_y));
+    player.buffered_swap = None;
+    player.holding = false;
+    true
+}
+
+fn reset_player(player: &mut PlayerState, start_level: u32, tuning: &Tuning) {
+    player.grid.clear();
+    player.grid.fill_test_pattern();
+    player.cursor = Cursor::new(0, 0);
+    player.score = 0;
+    player.elapsed = 0.0;
+    player.phase = PlayerPhase::Idle;
+    player.pending_clears.clear();
+    player.gravity_timer.reset();
+    player.rise_timer.reset();
+    player.rise_pause_timer = Timer::from_seconds(tuning.rise_pause, TimerMode::Repeating);
+    player.rise_paused = false;
+    player.start_level = start_level;
+    player.rise_level = start_level;
+    player.rows_at_level = 0;
+    player.level_splits = vec![0.0];
+    player.rise_timer =
+        Timer::from_seconds(tuning.rise.interval(start_level), TimerMode::Repeating);
+    player.chain_index = 0;
+    player.chain_ended = false;
+    player.chain_counts = [0; CHAIN_BUCKETS];
+    player.garbage_outgoing = 0;
+    player.garbage_earned = 0;
+    player.garbage_queue.clear();
+    player.items_outgoing.clear();
+    player.item_queue.clear();
+    player.item_freeze = 0.0;
+    player.speed_burst = 0.0;
+    player.rise_scale = 1.0;
+    player.topped_seconds = 0.0;
+    player.garbage_sent = 0;
+    player.garbage_received = 0;
+    player.blocks_cleared = 0;
+    player.swaps_made = 0;
+    player.best_chain = 0;
+    player.waves_dropped = 0;
+    player.buffered_swap = None;
+    player.holding = false;
+    player.cursor_vertical = false;
+    player.in_danger = false;
+    player.sticky_dir = None;
+}
+
+fn compute_player_origins(
+    mode: GameMode,
+    panel_width: f32,
+    panels: [PanelSide; 2],
+    arrangement: BoardArrangement,
+) -> (Vec2, Vec2) {
+    let grid_w = GRID_W as f32 * CELL_SIZE;
+    let total_player_w = grid_w + panel_width + PANEL_GAP;
+    let grid_center_x = |center_x: f32, panel: PanelSide| {
+        let left = center_x - total_player_w / 2.0;
+        match panel {
+            PanelSide::Right => left + grid_w / 2.0,
+            PanelSide::Left => left + panel_width + PANEL_GAP + grid_w / 2.0,
+        }
+    };
+    match (mode, arrangement) {
+        (GameMode::OnePlayer, _) => (Vec2::new(0.0, 0.0), Vec2::new(0.0, 0.0)),
+        (GameMode::TwoPlayer, BoardArrangement::Stacked) => {
+            let center_y = (board_height() + PLAYER_GAP) / 2.0;
+            (
+                Vec2::new(grid_center_x(0.0, panels[0]), center_y),
+                Vec2::new(grid_center_x(0.0, panels[1]), -center_y),
+            )
+        }
+        (GameMode::TwoPlayer, BoardArrangement::SideBySide) => {
+            let p2_center_x = -(total_player_w / 2.0 + PLAYER_GAP / 2.0);
+            let p1_center_x = total_player_w / 2.0 + PLAYER_GAP / 2.0;
+            (
+                Vec2::new(grid_center_x(p1_center_x, panels[0]), 0.0),
+                Vec2::new(grid_center_x(p2_center_x, panels[1]), 0.0),
+            )
+        }
+    }
+}
+
+fn spawn_player_view(
+    commands: &mut Commands,
+    player: &PlayerState,
+    origin: Vec2,
+    layout: settings::BoardLayout,
+    panel_width: f32,
+    palette: &Palette,
+    locale: &Locale,
+) -> PlayerView {
+    let grid = &player.grid;
+    let (frame, panel) = spawn_frame_and_panel(commands, grid, origin, layout.panel, palette);
+    let background = spawn_background_grid(commands, grid, origin, palette);
+    let blocks = pool::BlockPool::new(commands, grid.width * grid.height, grid.count_blocks());
+    let next_row = spawn_next_row(commands, grid, origin, layout.mirrored);
+    let cursor = spawn_cursor(commands, origin, player.grab_cursor, palette);
+    let hover = spawn_hover(commands, palette);
+    let chain_indicator = spawn_chain_indicator(commands, palette);
+    let garbage_preview = spawn_garbage_preview(commands, palette);
+    let ui = spawn_ui_texts(commands, panel, palette);
+    let rise_meter = spawn_rise_meter(commands, panel, palette);
+    let chains = spawn_chain_chart(commands, panel, palette, locale);
+    PlayerView {
+        background,
+        blocks,
+        next_row,
+        frame,
+        cursor,
+        hover,
+        chain_indicator,
+        garbage_preview,
+        panel,
+        ui,
+        rise_meter,
+        chains,
+        origin,
+        mirrored: layout.mirrored,
+        panel_side: layout.panel,
+        panel_width,
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    buttons: Res<ButtonInput<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    stick: Res<StickSettings>,
+    time: Res<Time<Virtual>>,
+    devices: Res<devices::PlayerDevices>,
+    mut players: ResMut<Players>,
+    mode: Res<GameMode>,
+    match_over: Res<MatchOver>,
+    local: Res<LocalControl>,
+    cpu: Res<CpuControl>,
+    rules: Res<MatchRules>,
+    settings: Res<settings::Settings>,
+    mut queue: ResMut<CommandQueue>,
+) {
+    let primary = local.0.unwrap_or(PlayerId::P1);
+    if match_over.active || cpu.controls(primary) {
+        return;
+    }
+    let delta = spectacle::unscaled(&time);
+    let local_two_player = *mode == GameMode::TwoPlayer && local.0.is_none() && !cpu.any();
+    let p1_gamepad = devices.p1;
+    let p2_gamepad = if local_two_player { devices.p2 } else { None };
+
+    let p1_keys = bindings::keys_for(PlayerId::P1);
+    let p2_keys = bindings::keys_for(PlayerId::P2);
+    let hold_swap = settings.hold_swap_seconds();
+    if repeat_swap(
+        players.get_mut(primary),
+        keys.just_pressed(p1_keys.swap) || handle_gamepad(p1_gamepad, buttons.as_ref()),
+        keys.pressed(p1_keys.swap) || swap_button_held(p1_gamepad, buttons.as_ref()),
+        hold_swap,
+        delta,
+    ) {
+        queue.push(primary, PlayerCommand::Swap);
+    }
+    if local_two_player
+        && repeat_swap(
+            &mut players.p2,
+            keys.just_pressed(p2_keys.swap) || handle_gamepad(p2_gamepad, buttons.as_ref()),
+            keys.pressed(p2_keys.swap) || swap_button_held(p2_gamepad, buttons.as_ref()),
+            hold_swap,
+            delta,
+        )
+    {
+        queue.push(PlayerId::P2, PlayerCommand::Swap);
+    }
+    if keys.just_pressed(p1_keys.rotate) || rotate_pressed(p1_gamepad, buttons.as_ref()) {
+        queue.push(primary, PlayerCommand::Rotate);
+    }
+    if local_two_player
+        && (keys.just_pressed(p2_keys.rotate) || rotate_pressed(p2_gamepad, buttons.as_ref()))
+    {
+        queue.push(PlayerId::P2, PlayerCommand::Rotate);
+    }
+    if rules.solo == SoloMode::Zen
+        && (p1_keys.raise.is_some_and(|key| keys.just_pressed(key))
+            || raise_pressed(p1_gamepad, buttons.as_ref()))
+    {
+        queue.push(primary, PlayerCommand::Raise);
+    }
+
+    let p1_stick = stick_direction(p1_gamepad, axes.as_ref(), stick.deadzone);
+    let p2_stick = stick_direction(p2_gamepad, axes.as_ref(), stick.deadzone);
+    let dash = settings.dash_step();
+    let sticky = settings.sticky_cursor;
+    let p1_dash = dash_held(keys.as_ref(), p1_keys.dash, p1_gamepad, buttons.as_ref());
+    let p2_dash = dash_held(keys.as_ref(), p2_keys.dash, p2_gamepad, buttons.as_ref());
+    if let Some(dir) = handle_repeat(
+        keys.as_ref(),
+        p1_keys,
+        buttons.as_ref(),
+        p1_gamepad,
+        p1_stick,
+        players.get_mut(primary),
+        p1_dash.then_some(dash),
+        sticky,
+        delta,
+    ) {
+        queue.push(primary, move_command(dir, settings.board_layout(primary)));
+    }
+    if local_two_player
+        && let Some(dir) = handle_repeat(
+            keys.as_ref(),
+            p2_keys,
+            buttons.as_ref(),
+            p2_gamepad,
+            p2_stick,
+            &mut players.p2,
+            p2_dash.then_some(dash),
+            sticky,
+            delta,
+        )
+    {
+        queue.push(
+            PlayerId::P2,
+            move_command(dir, settings.board_layout(PlayerId::P2)),
+        );
+    }
+}
+
+fn move_command(dir: IVec2, layout: settings::BoardLayout) -> PlayerCommand {
+    let dx = if layout.mirrored { -dir.x } else { dir.x };
+    PlayerCommand::Move {
+        dx: dx as i8,
+        dy: dir.y as i8,
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_mouse(
+    mouse: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    views: Res<PlayerViews>,
+    players: Res<Players>,
+    mode: Res<GameMode>,
+    local: Res<LocalControl>,
+    cpu: Res<CpuControl>,
+    match_over: Res<MatchOver>,
+    mut queue: ResMut<CommandQueue>,
+) {
+    if match_over.active || !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let Some(point) = pointer_world(&windows, &cameras) else {
+        return;
+    };
+    for id in mouse_players(*mode, &local, &cpu) {
+        let (player, Some(view)) = (players.get(id), player_view(&views, id)) else {
+            continue;
+        };
+        if let Some((x, y)) = pointer_cell(&player.grid, view, point) {
+            queue.push(
+                id,
+                PlayerCommand::MoveTo {
+                    x: x as u8,
+                    y: y as u8,
+                },
+            );
+        }
+    }
+}
+
+fn mouse_players(mode: GameMode, local: &LocalControl, cpu: &CpuControl) -> Vec<PlayerId> {
+    if let Some(id) = local.0 {
+        return vec![id];
+    }
+    active_players(mode)
+        .iter()
+        .copied()
+        .filter(|id| !cpu.controls(*id))
+        .collect()
+}
+
+fn pointer_world(
+    windows: &Query<&Window, With<PrimaryWindow>>,
+    cameras: &Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+) -> Option<Vec2> {
+    let window = windows.get_single().ok()?;
+    let cursor = window.cursor_position()?;
+    let (camera, transform) = cameras
+        .iter()
+        .filter(|(camera, _)| {
+            camera.is_active
+                && camera
+                    .logical_viewport_rect()
+                    .is_none_or(|rect| rect.contains(cursor))
+        })
+        .max_by_key(|(camera, _)| camera.viewport.is_some())?;
+    camera.viewport_to_world_2d(transform, cursor)
+}
+
+fn pointer_cell(grid: &Grid, view: &PlayerView, point: Vec2) -> Option<(usize, usize)> {
+    let size = Vec2::new(grid.width as f32, grid.height as f32) * CELL_SIZE;
+    let local = (point - view.origin + size / 2.0) / CELL_SIZE;
+    if local.x < 0.0 || local.y < 0.0 {
+        return None;
+    }
+    let (x, y) = (local.x as usize, local.y as usize);
+    if x >= grid.width || y >= grid.height {
+        return None;
+    }
+    Some((if view.mirrored { grid.width - 1 - x } else { x }, y))
+}
+
+fn handle_gamepad(gamepad: Option<Gamepad>, buttons: &ButtonInput<GamepadButton>) -> bool {
+    let Some(gamepad) = gamepad else {
+        return false;
+    };
+    buttons.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::South))
+        || buttons.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::East))
+        || buttons.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::West))
+        || buttons.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::North))
+}
+
+fn swap_button_held(gamepad: Option<Gamepad>, buttons: &ButtonInput<GamepadButton>) -> bool {
+    let Some(gamepad) = gamepad else {
+        return false;
+    };
+    buttons.any_pressed([
+        GamepadButton::new(gamepad, GamepadButtonType::South),
+        GamepadButton::new(gamepad, GamepadButtonType::East),
+        GamepadButton::new(gamepad, GamepadButtonType::West),
+        GamepadButton::new(gamepad, GamepadButtonType::North),
+    ])
+}
+
+// With hold-to-swap on, keeping the swap button down swaps again every interval
+// after the first press.
+fn repeat_swap(
+    player: &mut PlayerState,
+    pressed: bool,
+    held: bool,
+    interval: Option<f32>,
+    delta: std::time::Duration,
+) -> bool {
+    let Some(interval) = interval else {
+        return pressed;
+    };
+    if pressed {
+        player.swap_repeat_timer = Timer::from_seconds(interval, TimerMode::Repeating);
+        return true;
+    }
+    held && player.swap_repeat_timer.tick(delta).just_finished()
+}
+
+fn stick_direction(
+    gamepad: Option<Gamepad>,
+    axes: &Axis<GamepadAxis>,
+    deadzone: f32,
+) -> Option<IVec2> {
+    let gamepad = gamepad?;
+    let x = axes
+        .get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX))
+        .unwrap_or(0.0);
+    let y = axes
+        .get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickY))
+        .unwrap_or(0.0);
+    if x.abs().max(y.abs()) < deadzone {
+        return None;
+    }
+    Some(if x.abs() >= y.abs() {
+        IVec2::new(x.signum() as i32, 0)
+    } else {
+        IVec2::new(0, y.signum() as i32)
+    })
+}
+
+fn raise_pressed(gamepad: Option<Gamepad>, buttons: &ButtonInput<GamepadButton>) -> bool {
+    gamepad.is_some_and(|pad| {
+        buttons.just_pressed(GamepadButton::new(pad, GamepadButtonType::RightTrigger2))
+    })
+}
+
+fn rotate_pressed(gamepad: Option<Gamepad>, buttons: &ButtonInput<GamepadButton>) -> bool {
+    gamepad
+        .is_some_and(|pad| buttons.just_pressed(GamepadButton::new(pad, GamepadButtonType::Select)))
+}
+
+fn dash_held(
+    keys: &ButtonInput<KeyCode>,
+    key: KeyCode,
+    gamepad: Option<Gamepad>,
+    buttons: &ButtonInput<GamepadButton>,
+) -> bool {
+    keys.pressed(key)
+        || gamepad.is_some_and(|pad| {
+            buttons.pressed(GamepadButton::new(pad, GamepadButtonType::LeftTrigger2))
+        })
+}
+
+fn drive_simulation(world: &mut World) {
+    let scale = world.resource::<SimTimeScale>().0;
+    let mut real_delta = spectacle::unscaled(world.resource::<Time<Virtual>>());
+    if scale != 1.0 {
+        real_delta = real_delta.mul_f32(scale);
+    }
+    let steps = world.resource_mut::<SimClock>().accumulate(real_delta);
+    for _ in 0..steps {
+        if !step_simulation(world) {
+            break;
+        }
+    }
+}
+
+fn step_simulation(world: &mut World) -> bool {
+    let frame = world.resource::<SimClock>().frame;
+    let mut queue = world.resource_mut::<CommandQueue>();
+    match queue.lockstep_limit {
+        Some(limit) if frame >= limit => return false,
+        Some(_) => {}
+        None => {
+            queue.seal(frame);
+        }
+    }
+    world.run_schedule(SimulationTick);
+    world.resource_mut::<SimClock>().frame += 1;
+    true
+}
+
+fn apply_player_commands(
+    clock: Res<SimClock>,
+    mut queue: ResMut<CommandQueue>,
+    mut trace: ResMut<trace::InputTrace>,
+    mut players: ResMut<Players>,
+    match_over: Res<MatchOver>,
+) {
+    let Some(mut commands) = queue.scheduled.remove(&clock.frame) else {
+        return;
+    };
+    if match_over.active {
+        return;
+    }
+    // Peers receive each other's commands in different orders; only the
+    // per-player order matters, so normalise on player id.
+    commands.sort_by_key(|(player, _)| *player);
+    for (player, command) in commands {
+        trace.record(clock.frame, player, command);
+        apply_command(&mut players, player, command);
+    }
+}
+
+fn apply_command(players: &mut Players, id: PlayerId, command: PlayerCommand) {
+    match command {
+        PlayerCommand::Move { dx, dy } => {
+            let player = players.get_mut(id);
+            let dir = IVec2::new(dx as i32, dy as i32);
+            if player.grab_cursor {
+                carry_block(player, dir);
+            } else {
+                move_cursor(player, dir);
+            }
+        }
+        PlayerCommand::Swap if players.get(id).grab_cursor => toggle_grab(players.get_mut(id)),
+        PlayerCommand::Swap => try_swap(players.get_mut(id)),
+        PlayerCommand::MoveTo { x, y } => {
+            let player = players.get_mut(id);
+            player.holding = false;
+            player.cursor = snapped_cursor(player, x as usize, y as usize);
+        }
+        PlayerCommand::Rotate => rotate_cursor(players.get_mut(id)),
+        PlayerCommand::Raise => raise_manually(players.get_mut(id)),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_repeat(
+    keys: &ButtonInput<KeyCode>,
+    player_keys: &bindings::PlayerKeys,
+    buttons: &ButtonInput<GamepadButton>,
+    gamepad: Option<Gamepad>,
+    stick: Option<IVec2>,
+    player: &mut PlayerState,
+    dash: Option<i32>,
+    sticky: bool,
+    delta: std::time::Duration,
+) -> Option<IVec2> {
+    let state = |dir| dir_state(keys, player_keys, buttons, gamepad, stick, dir);
+    let (left_jp, left_p) = state(Direction::Left);
+    let (right_jp, right_p) = state(Direction::Right);
+    let (up_jp, up_p) = state(Direction::Up);
+    let (down_jp, down_p) = state(Direction::Down);
+
+    let dir = select_direction(
+        player.repeat_dir,
+        &[
+            (left_jp, IVec2::new(-1, 0)),
+            (right_jp, IVec2::new(1, 0)),
+            (up_jp, IVec2::new(0, 1)),
+            (down_jp, IVec2::new(0, -1)),
+        ],
+        &[
+            (left_p, IVec2::new(-1, 0)),
+            (right_p, IVec2::new(1, 0)),
+            (up_p, IVec2::new(0, 1)),
+            (down_p, IVec2::new(0, -1)),
+        ],
+    );
+    let dir = if dash.is_some() {
+        held_direction(left_p, right_p, up_p, down_p)
+    } else if sticky {
+        sticky_direction(player, [left_jp, right_jp, up_jp, down_jp])
+    } else {
+        dir
+    };
+    update_repeat_move(player, dir, dash, delta)
+}
+
+#[derive(Clone, Copy)]
+enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl Direction {
+    fn offset(self) -> IVec2 {
+        match self {
+            Direction::Left => IVec2::new(-1, 0),
+            Direction::Right => IVec2::new(1, 0),
+            Direction::Up => IVec2::new(0, 1),
+            Direction::Down => IVec2::new(0, -1),
+        }
+    }
+}
+
+fn dir_state(
+    keys: &ButtonInput<KeyCode>,
+    player_keys: &bindings::PlayerKeys,
+    buttons: &ButtonInput<GamepadButton>,
+    gamepad: Option<Gamepad>,
+    stick: Option<IVec2>,
+    dir: Direction,
+) -> (bool, bool) {
+    let (key, button) = match dir {
+        Direction::Left => (player_keys.left, GamepadButtonType::DPadLeft),
+        Direction::Right => (player_keys.right, GamepadButtonType::DPadRight),
+        Direction::Up => (player_keys.up, GamepadButtonType::DPadUp),
+        Direction::Down => (player_keys.down, GamepadButtonType::DPadDown),
+    };
+    let gp_pressed = gamepad.is_some_and(|pad| buttons.pressed(GamepadButton::new(pad, button)));
+    let gp_just = gamepad.is_some_and(|pad| buttons.just_pressed(GamepadButton::new(pad, button)));
+    (
+        keys.just_pressed(key) || gp_just,
+        keys.pressed(key) || gp_pressed || stick == Some(dir.offset()),
+    )
+}
+
+fn select_direction(
+    current: Option<IVec2>,
+    just_pressed: &[(bool, IVec2)],
+    pressed: &[(bool, IVec2)],
+) -> Option<IVec2> {
+    for (is_just, dir) in just_pressed {
+        if *is_just {
+            return Some(*dir);
+        }
+    }
+    if let Some(dir) = current
+        && pressed
+            .iter()
+            .any(|(is_pressed, d)| *is_pressed && *d == dir)
+    {
+        return Some(dir);
+    }
+    for (is_pressed, dir) in pressed {
+        if *is_pressed {
+            return Some(*dir);
+        }
+    }
+    None
+}
+
+// Sticky movement keeps the last direction pressed going until it is pressed
+// again or another direction replaces it.
+fn sticky_direction(player: &mut PlayerState, just_pressed: [bool; 4]) -> Option<IVec2> {
+    let dirs = [
+        IVec2::new(-1, 0),
+        IVec2::new(1, 0),
+        IVec2::new(0, 1),
+        IVec2::new(0, -1),
+    ];
+    if let Some(dir) = dirs
+        .into_iter()
+        .zip(just_pressed)
+        .find_map(|(dir, pressed)| pressed.then_some(dir))
+    {
+        player.sticky_dir = (player.sticky_dir != Some(dir)).then_some(dir);
+    }
+    player.sticky_dir
+}
+
+fn held_direction(left: bool, right: bool, up: bool, down: bool) -> Option<IVec2> {
+    let dir = IVec2::new(
+        i32::from(right) - i32::from(left),
+        i32::from(up) - i32::from(down),
+    );
+    (dir != IVec2::ZERO).then_some(dir)
+}
+
+fn update_repeat_move(
+    player: &mut PlayerState,
+    dir: Option<IVec2>,
+    dash: Option<i32>,
+    delta: std::time::Duration,
+) -> Option<IVec2> {
+    let step = dash.unwrap_or(1);
+    if let Some(dir) = dir {
+        let dir_changed = player.repeat_dir != Some(dir);
+        if dir_changed {
+            player.repeat_dir = Some(dir);
+            player.repeat_initial = true;
+            player.repeat_timer = Timer::from_seconds(INPUT_REPEAT_DELAY, TimerMode::Once);
+            return Some(dir * step);
+        }
+        if player.repeat_timer.tick(delta).just_finished() {
+            if player.repeat_initial {
+                player.repeat_initial = false;
+                player.repeat_timer =
+                    Timer::from_seconds(INPUT_REPEAT_INTERVAL, TimerMode::Repeating);
+            }
+            return Some(dir * step);
+        }
+    } else {
+        player.repeat_dir = None;
+        player.repeat_initial = true;
+        player.repeat_timer.reset();
+    }
+    None
+}
+
+fn cursor_span(player: &PlayerState) -> (usize, usize) {
+    match (player.grab_cursor, player.cursor_vertical) {
+        (true, _) => (1, 1),
+        (false, true) => (1, 2),
+        (false, false) => (2, 1),
+    }
+}
+
+fn move_cursor(player: &mut PlayerState, dir: IVec2) {
+    player.cursor.move_by(
+        dir.x as isize,
+        dir.y as isize,
+        cursor_span(player),
+        (player.grid.width, player.grid.height),
+    );
+}
+
+fn snapped_cursor(player: &PlayerState, x: usize, y: usize) -> Cursor {
+    let mut cursor = Cursor::new(x, y);
+    cursor.move_by(
+        0,
+        0,
+        cursor_span(player),
+        (player.grid.width, player.grid.height),
+    );
+    cursor
+}
+
+fn rotate_cursor(player: &mut PlayerState) {
+    if !player.vertical_swaps || player.grab_cursor {
+        return;
+    }
+    player.cursor_vertical = !player.cursor_vertical;
+    move_cursor(player, IVec2::ZERO);
+}
+
+fn holding_block(player: &PlayerState) -> bool {
+    player
+        .grid
+        .get(player.cursor.x, player.cursor.y)
+        .is_some_and(Block::swappable)
+}
+
+fn toggle_grab(player: &mut PlayerState) {
+    player.holding = !player.holding && holding_block(player);
+}
+
+fn carry_block(player: &mut PlayerState, dir: IVec2) {
+    if dir.y != 0 || !holding_block(player) {
+        player.holding = false;
+    }
+    if !player.holding {
+        move_cursor(player, dir);
+        return;
+    }
+    for _ in 0..dir.x.unsigned_abs() {
+        let x = player.cursor.x as i32 + dir.x.signum();
+        if x < 0 || x as usize >= player.grid.width {
+            break;
+        }
+        let pair = SwapCmd::right_of(player.cursor.x.min(x as usize), player.cursor.y);
+        if !swap_at(player, pair) {
+            break;
+        }
+        player.cursor.x = x as usize;
+        player.grid.release_swap(player.cursor.x, player.cursor.y);
+    }
+}
+
+fn try_swap(player: &mut PlayerState) {
+    let cmd = if player.cursor_vertical {
+        SwapCmd::above(player.cursor.x, player.cursor.y)
+    } else {
+        SwapCmd::right_of(player.cursor.x, player.cursor.y)
+    };
+    player.buffered_swap = if swap_at(player, cmd) {
+        None
+    } else {
+        Some(BufferedSwap {
+            cmd,
+            ticks_left: SWAP_BUFFER_TICKS,
+        })
+    };
+}
+
+// A clear elsewhere on the board doesn't block a swap; only the two cells do,
+// while they pop, hover, fall or are still mid-swap.
+fn swap_blocked(player: &PlayerState, cmd: SwapCmd) -> bool {
+    if player.phase == PlayerPhase::Dying {
+        return true;
+    }
+    let grid = &player.grid;
+    [(cmd.ax, cmd.ay), (cmd.bx, cmd.by)]
+        .into_iter()
+        .any(|(x, y)| {
+            x < grid.width && y < grid.height && (grid.in_motion(x, y) || grid.is_reserved(x, y))
+        })
+}
+
+fn swap_at(player: &mut PlayerState, cmd: SwapCmd) -> bool {
+    if swap_blocked(player, cmd) {
+        return false;
+    }
+    if player.grid.swap_in_bounds(cmd) {
+        player.swaps_made += 1;
+        if player.phase.settled() {
+            queue_clear(player);
+        }
+    }
+    true
+}
+
+fn apply_buffered_swaps(
+    mut players: ResMut<Players>,
+    mode: Res<GameMode>,
+    match_over: Res<MatchOver>,
+) {
+    if match_over.active {
+        return;
+    }
+    for id in active_players(*mode) {
+        let player = players.get_mut(*id);
+        let Some(buffered) = player.buffered_swap else {
+            continue;
+        };
+        player.buffered_swap = if swap_at(player, buffered.cmd) || buffered.ticks_left <= 1 {
+            None
+        } else {
+            Some(BufferedSwap {
+                ticks_left: buffered.ticks_left - 1,
+                ..buffered
+            })
+        };
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_results_menu(
+    mut commands: Commands,
+    match_over: Res<MatchOver>,
+    match_over_timer: Res<MatchOverTimer>,
+    local: Res<LocalControl>,
+    rules: Res<MatchRules>,
+    roots: Query<Entity, With<ResultsMenu>>,
+    palette: Res<Palette>,
+    locale: Res<Locale>,
+) {
+    let due = match_over.active && match_over_timer.seconds >= RESULTS_MENU_DELAY;
+    if !due {
+        for root in &roots {
+            commands.entity(root).despawn_recursive();
+        }
+        return;
+    }
+    if !roots.is_empty() {
+        return;
+    }
+    let mut entries = Vec::new();
+    if local.0.is_none() {
+        let key = if match_over.series_decided(&rules) {
+            "results.rematch"
+        } else {
+            "results.next_round"
+        };
+        entries.push(MenuEntry::new(ResultsItem::Rematch, locale.get(key)));
+    }
+    entries.push(MenuEntry::new(
+        ResultsItem::QuitToTitle,
+        locale.get("pause.quit"),
+    ));
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Percent(10.0),
+                    width: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ResultsMenu,
+            GameEntity,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        padding: UiRect::all(Val::Px(12.0)),
+                        ..Default::default()
+                    },
+                    background_color: BackgroundColor(palette.pause_overlay),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle {
+                            text: Text::default().with_justify(JustifyText::Center),
+                            ..Default::default()
+                        },
+                        Menu::new(entries),
+                    ));
+                });
+        });
+}
+
+fn handle_results_input(
+    mut events: EventReader<MenuEvent<ResultsItem>>,
+    mut control: MatchControl,
+    mode: Res<GameMode>,
+    mut match_over_timer: ResMut<MatchOverTimer>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for event in events.read() {
+        match *event {
+            MenuEvent::Confirm(ResultsItem::Rematch) if control.match_over.active => {
+                if control.match_over.series_decided(&control.rules) {
+                    control.start_series(*mode);
+                } else {
+                    control.start(*mode);
+                }
+                match_over_timer.seconds = 0.0;
+            }
+            MenuEvent::Confirm(ResultsItem::QuitToTitle) | MenuEvent::Cancel => {
+                next_state.set(AppState::Title);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn rise_stack(
+    clock: Res<MatchClock>,
+    mut players: ResMut<Players>,
+    mut match_over: ResMut<MatchOver>,
+    mut match_over_timer: ResMut<MatchOverTimer>,
+    mode: Res<GameMode>,
+    rules: Res<MatchRules>,
+    tuning: Res<Tuning>,
+    mut feedback: EventWriter<FeedbackEvent>,
+) {
+    if match_over.active || rules.solo == SoloMode::Zen {
+        return;
+    }
+    let delta = clock.delta;
+    let p1_over = rise_player(delta, &mut players.p1, &tuning);
+    let p2_over = if *mode == GameMode::TwoPlayer {
+        rise_player(delta, &mut players.p2, &tuning)
+    } else {
+        false
+    };
+
+    if !p1_over && !p2_over {
+        return;
+    }
+    for (id, over) in [(PlayerId::P1, p1_over), (PlayerId::P2, p2_over)] {
+        if over {
+            players.get_mut(id).phase = PlayerPhase::Dying;
+            feedback.send(FeedbackEvent {
+                player: id,
+                kind: Feedback::Defeat,
+            });
+        }
+    }
+    // Both stacks topping out on the same tick is a draw, with no round awarded.
+    match_over.active = true;
+    match_over.winner = match (*mode, p1_over, p2_over) {
+        (GameMode::TwoPlayer, true, false) => Some(PlayerId::P2),
+        (GameMode::TwoPlayer, false, true) => Some(PlayerId::P1),
+        _ => None,
+    };
+    match_over_timer.seconds = 0.0;
+    if let Some(winner) = match_over.winner {
+        match_over.award_round(winner);
+    }
+}
+
+// A full stack only tops out once its next rise has come due and then stayed
+// blocked by the ceiling for the tuned `top_out_grace` of match time. Clears,
+// falls and any stop time hold both clocks, so a rise that comes due during one
+// waits rather than being dropped, and clearing the top row resets the grace.
+// Handicaps and speed bursts hurry the rise but not the grace.
+fn rise_player(delta: std::time::Duration, player: &mut PlayerState, tuning: &Tuning) -> bool {
+    let rise_delta = if player.rise_scale != 1.0 {
+        delta.mul_f32(player.rise_scale)
+    } else {
+        delta
+    };
+    let rise_delta = if player.speed_burst > 0.0 {
+        player.speed_burst = (player.speed_burst - rise_delta.as_secs_f32()).max(0.0);
+        rise_delta.mul_f32(SPEED_BURST_FACTOR)
+    } else {
+        rise_delta
+    };
+    let stopped = player.rise_paused
+        || player.item_freeze > 0.0
+        || player.phase != PlayerPhase::Idle
+        || player.grid.has_reservations()
+        || player.grid.has_falling_garbage();
+    if player.grid.top_row_occupied() {
+        if !stopped
+            && (player.topped_seconds > 0.0 || player.rise_timer.tick(rise_delta).just_finished())
+        {
+            player.topped_seconds += delta.as_secs_f32();
+        }
+        return player.topped_seconds >= tuning.top_out_grace;
+    }
+    player.topped_seconds = 0.0;
+    if !stopped && player.rise_timer.tick(rise_delta).just_finished() {
+        raise_stack_once(player);
+    }
+    false
+}
+
+fn raise_manually(player: &mut PlayerState) {
+    if !player.phase.settled()
+        || player.grid.has_reservations()
+        || player.grid.has_falling_garbage()
+        || player.grid.top_row_occupied()
+    {
+        return;
+    }
+    raise_stack_once(player);
+}
+
+fn raise_stack_once(player: &mut PlayerState) -> bool {
+    if player.grid.top_row_occupied() {
+        return true;
+    }
+    player.grid.push_bottom_row();
+    player.rows_at_level += 1;
+    if player.cursor.y + 1 < player.grid.height {
+        player.cursor.y += 1;
+    }
+    queue_clear(player);
+    false
+}
+
+// Every new match waits out its own flash and pops, so matches made at different
+// moments resolve in the order they happened.
+fn queue_clear(player: &mut PlayerState) -> bool {
+    let Some(marks) = player.grid.take_new_matches() else {
+        return false;
+    };
+    let width = player.grid.width;
+    let timing = player.clear_timing;
+    player
+        .pending_clears
+        .push_back(PendingClear::new(marks, width, timing));
+    player.phase = PlayerPhase::Clearing;
+    true
+}
+
+fn update_time(
+    clock: Res<MatchClock>,
+    mut players: ResMut<Players>,
+    mode: Res<GameMode>,
+    rules: Res<MatchRules>,
+    tuning: Res<Tuning>,
+) {
+    let delta = clock.delta.as_secs_f32();
+    players.p1.elapsed += delta;
+    if rules.solo != SoloMode::Marathon {
+        update_rise_speed(&mut players.p1, &tuning);
+    }
+    if *mode == GameMode::TwoPlayer {
+        players.p2.elapsed += delta;
+        update_rise_speed(&mut players.p2, &tuning);
+    }
+}
+
+fn update_rise_speed(player: &mut PlayerState, tuning: &Tuning) {
+    if player.rows_at_level < tuning.rise.rows_per_level(player.rise_level) {
+        return;
+    }
+    set_rise_level(player, player.rise_level + 1, tuning);
+}
+
+fn set_rise_level(player: &mut PlayerState, level: u32, tuning: &Tuning) {
+    player.rise_level = level;
+    player.rows_at_level = 0;
+    player.rise_timer = Timer::from_seconds(tuning.rise.interval(level), TimerMode::Repeating);
+}
+
+// Modes end matches from several places (topping out, trial and challenge
+// goals, the marathon goal), so the result is announced once from here when the
+// match flips over.
+fn announce_match_result(
+    match_over: Res<MatchOver>,
+    mut was_over: Local<bool>,
+    mut results: EventWriter<MatchResultEvent>,
+) {
+    if match_over.active && !*was_over {
+        results.send(MatchResultEvent {
+            winner: match_over.winner,
+        });
+    }
+    *was_over = match_over.active;
+}
+
+fn update_game_over_timer(
+    time: Res<Time>,
+    mut timer: ResMut<MatchOverTimer>,
+    match_over: Res<MatchOver>,
+) {
+    if match_over.active && timer.seconds < 1.0 {
+        timer.seconds += time.delta_seconds();
+    }
+}
+
+fn apply_gravity_system(
+    clock: Res<MatchClock>,
+    mut players: ResMut<Players>,
+    match_over: Res<MatchOver>,
+    mode: Res<GameMode>,
+    mut feedback: EventWriter<FeedbackEvent>,
+    mut diagnostics: Diagnostics,
+) {
+    if match_over.active {
+        return;
+    }
+    let _span = info_span!("gravity").entered();
+    let started = Instant::now();
+    for id in active_players(*mode) {
+        if process_player_gravity(clock.delta, players.get_mut(*id)) {
+            feedback.send(FeedbackEvent {
+                player: *id,
+                kind: Feedback::GarbageLanded,
+            });
+        }
+    }
+    diagnostics.add_measurement(&profiling::GRAVITY, || profiling::millis_since(started));
+}
+
+fn process_player_gravity(delta: std::time::Duration, player: &mut PlayerState) -> bool {
+    let mut garbage_landed = false;
+    player.grid.tick_reservations();
+    if player.gravity_timer.tick(delta).just_finished() {
+        let moved = player.grid.apply_gravity_step();
+        if !moved {
+            garbage_landed = player.phase == PlayerPhase::DroppingGarbage;
+            if queue_clear(player) || !player.pending_clears.is_empty() {
+                player.phase = PlayerPhase::Clearing;
+            } else {
+                player.phase = PlayerPhase::Idle;
+                if player.chain_index > 0 {
+                    if player.grid.convert_cracked_garbage() {
+                        player.phase = PlayerPhase::Converting;
+                    } else {
+                        let bucket = (player.chain_index as usize).clamp(1, CHAIN_BUCKETS) - 1;
+                        player.chain_counts[bucket] += 1;
+                        player.chain_index = 0;
+                        player.chain_ended = true;
+                    }
+                }
+            }
+        } else if player.phase.settled() {
+            player.phase = PlayerPhase::Falling;
+        }
+    }
+    garbage_landed
+}
+
+fn update_danger(
+    mut players: ResMut<Players>,
+    match_over: Res<MatchOver>,
+    mode: Res<GameMode>,
+    mut feedback: EventWriter<FeedbackEvent>,
+) {
+    if match_over.active {
+        return;
+    }
+    for id in active_players(*mode) {
+        let player = players.get_mut(*id);
+        let in_danger = stack_in_danger(&player.grid);
+        if in_danger && !player.in_danger {
+            feedback.send(FeedbackEvent {
+                player: *id,
+                kind: Feedback::Danger,
+            });
+        }
+        player.in_danger = in_danger;
+    }
+}
+
+fn stack_in_danger(grid: &Grid) -> bool {
+    grid.highest_occupied_row()
+        .is_some_and(|row| row + 1 + DANGER_ROWS >= grid.height)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_clear_delay(
+    clock: Res<MatchClock>,
+    mut players: ResMut<Players>,
+    match_over: Res<MatchOver>,
+    mode: Res<GameMode>,
+    garbage_rules: Res<GarbageRules>,
+    tuning: Res<Tuning>,
+    mut clear_events: EventWriter<ClearEvent>,
+    mut diagnostics: Diagnostics,
+) {
+    if match_over.active {
+        return;
+    }
+    let _span = info_span!("clears").entered();
+    let started = Instant::now();
+    let delta = clock.delta;
+    for id in active_players(*mode) {
+        let events = process_clear_delay(
+            delta,
+            *id,
+            players.get_mut(*id),
+            &garbage_rules,
+            &tuning.scoring,
+        );
+        clear_events.send_batch(events);
+    }
+    diagnostics.add_measurement(&profiling::CLEARS, || profiling::millis_since(started));
+}
+
+fn process_clear_delay(
+    delta: std::time::Duration,
+    player_id: PlayerId,
+    player: &mut PlayerState,
+    garbage_rules: &GarbageRules,
+    scoring: &scoring::ScoreTables,
+) -> Vec<ClearEvent> {
+    for pending in &mut player.pending_clears {
+        pending.timer.tick(delta);
+    }
+    let mut events = Vec::new();
+    while player
+        .pending_clears
+        .front()
+        .is_some_and(|pending| pending.timer.finished())
+        && let Some(pending) = player.pending_clears.pop_front()
+    {
+        let stats = player.grid.clear_marked(pending.marks);
+        if stats.cleared > 0 {
+            player.rise_paused = true;
+            player.rise_pause_timer.reset();
+            player.grid.crack_adjacent_garbage(&stats.marks);
+            player.chain_index += 1;
+            player.best_chain = player.best_chain.max(player.chain_index);
+            let score = scoring.score_clear(stats.cleared, player.chain_index);
+            player.score += score.total();
+            player.blocks_cleared += stats.cleared;
+            add_garbage_for_clear(player, garbage_rules, stats.cleared, stats.groups);
+            player.items_outgoing.extend(&stats.items);
+            events.push(ClearEvent {
+                player: player_id,
+                chain: player.chain_index,
+                cleared: stats.cleared,
+                score,
+                centroid: largest_group_centroid(&player.grid, &stats.marks),
+            });
+        }
+    }
+    if player.pending_clears.is_empty() && player.phase == PlayerPhase::Clearing {
+        player.phase = PlayerPhase::Idle;
+    }
+    events
+}
+
+// Groups that clear together can sit far apart, so the popup goes to the
+// biggest one (the lowest, on a tie) rather than the empty space between them.
+fn largest_group_centroid(grid: &Grid, marks: &[bool]) -> Vec2 {
+    let groups = grid.match_groups(marks);
+    let Some(group) = groups.iter().reduce(|best, group| {
+        if group.len() > best.len() {
+            group
+        } else {
+            best
+        }
+    }) else {
+        return Vec2::ZERO;
+    };
+    let sum: Vec2 = group
+        .iter()
+        .map(|&(x, y)| Vec2::new(x as f32, y as f32))
+        .sum();
+    sum / group.len() as f32
+}
+
+fn add_garbage_for_clear(
+    player: &mut PlayerState,
+    garbage_rules: &GarbageRules,
+    cleared: u32,
+    groups: u32,
+) {
+    let total = garbage_rules.units_for_clear(cleared, groups, player.chain_index);
+    if total == 0 {
+        return;
+    }
+    player.garbage_earned = player.garbage_earned.saturating_add(total);
+    player.garbage_outgoing = garbage_rules.capped(player.garbage_earned);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn resolve_garbage(
+    mut players: ResMut<Players>,
+    match_over: Res<MatchOver>,
+    rules: Res<MatchRules>,
+    garbage_rules: Res<GarbageRules>,
+    mode: Res<GameMode>,
+    mut cancel_events: EventWriter<GarbageCancelEvent>,
+    mut counter_events: EventWriter<GarbageCounterEvent>,
+    mut diagnostics: Diagnostics,
+) {
+    if match_over.active {
+        return;
+    }
+    let _span = info_span!("garbage").entered();
+    let started = Instant::now();
+
+    let attackers = if *mode == GameMode::TwoPlayer {
+        active_players(*mode)
+    } else {
+        &[]
+    };
+    for id in attackers {
+        let attacker = players.get_mut(*id);
+        if !attacker.chain_ended {
+            continue;
+        }
+        attacker.chain_ended = false;
+        attacker.garbage_earned = 0;
+        let outgoing = rules.scale_garbage(std::mem::take(&mut attacker.garbage_outgoing));
+        let Some(target) = opponents_of(*mode, *id).next() else {
+            continue;
+        };
+        if outgoing == 0 {
+            continue;
+        }
+        attacker.garbage_sent = attacker.garbage_sent.saturating_add(outgoing);
+        let cancelled = if garbage_rules.cancel {
+            take_queued_garbage(&mut attacker.garbage_queue, outgoing)
+        } else {
+            0
+        };
+        let surplus = outgoing - cancelled;
+        if cancelled > 0 {
+            cancel_events.send(GarbageCancelEvent { units: cancelled });
+            if surplus > 0 {
+                counter_events.send(GarbageCounterEvent {
+                    player: *id,
+                    units: surplus,
+                });
+            }
+        }
+        if surplus > 0 {
+            players.get_mut(target).garbage_queue.push_back(surplus);
+        }
+    }
+
+    for id in active_players(*mode) {
+        apply_incoming_garbage(players.get_mut(*id));
+    }
+    diagnostics.add_measurement(&profiling::GARBAGE, || profiling::millis_since(started));
+}
+
+fn apply_incoming_garbage(player: &mut PlayerState) {
+    if player.garbage_queue.is_empty() {
+        return;
+    }
+    if player.phase != PlayerPhase::Idle || player.rise_paused {
+        return;
+    }
+    let width = player.grid.width;
+    let room = (player.grid.free_rows_at_top() * width) as u32;
+    let units = take_queued_garbage(&mut player.garbage_queue, room);
+    if units == 0 {
+        return;
+    }
+    let rows = build_garbage_rows(width, units, player.grid.garbage_rng_mut());
+    if !player.grid.insert_garbage_rows_from_top(&rows) {
+        player.garbage_queue.push_front(units);
+        return;
+    }
+    player.garbage_received = player.garbage_received.saturating_add(units);
+    player.phase = PlayerPhase::DroppingGarbage;
+}
+
+fn take_queued_garbage(queue: &mut VecDeque<u32>, limit: u32) -> u32 {
+    let mut taken = 0;
+    while let Some(front) = queue.front_mut() {
+        let take = (*front).min(limit - taken);
+        *front -= take;
+        taken += take;
+        if *front == 0 {
+            queue.pop_front();
+        }
+        if taken == limit {
+            break;
+        }
+    }
+    taken
+}
+
+fn build_garbage_rows(width: usize, units: u32, rng: &mut impl Rng) -> Vec<Vec<bool>> {
+    if units == 0 || width == 0 {
+        return Vec::new();
+    }
+    let units = units as usize;
+    let full_rows = units / width;
+    let rem = units % width;
+    let mut rows = Vec::with_capacity(full_rows + if rem > 0 { 1 } else { 0 });
+    for _ in 0..full_rows {
+        rows.push(vec![true; width]);
+    }
+    if rem > 0 {
+        rows.push(build_partial_garbage_row(width, rem, rng));
+    }
+    rows
+}
+
+fn build_partial_garbage_row(width: usize, blocks: usize, rng: &mut impl Rng) -> Vec<bool> {
+    let mut mask = vec![false; width];
+    if blocks >= width {
+        mask.fill(true);
+        return mask;
+    }
+
+    let max_start = width - blocks;
+    let start = rng.gen_range(0..=max_start);
+    mask[start..start + blocks].fill(true);
+
+    mask
+}
+
+fn update_rise_pause(clock: Res<MatchClock>, mut players: ResMut<Players>, mode: Res<GameMode>) {
+    let delta = clock.delta;
+    tick_rise_pause(delta, &mut players.p1);
+    if *mode == GameMode::TwoPlayer {
+        tick_rise_pause(delta, &mut players.p2);
+    }
+}
+
+fn tick_rise_pause(delta: std::time::Duration, player: &mut PlayerState) {
+    if player.rise_paused && player.rise_pause_timer.tick(delta).just_finished() {
+        player.rise_paused = false;
+    }
+}
+
+fn spawn_next_row(
+    commands: &mut Commands,
+    grid: &Grid,
+    origin: Vec2,
+    mirrored: bool,
+) -> Vec<Entity> {
+    (0..grid.width)
+        .map(|x| {
+            commands
+                .spawn(SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::srgba(0.0, 0.0, 0.0, 0.0),
+                        custom_size: Some(Vec2::splat(CELL_SIZE - BLOCK_INSET)),
+                        ..Default::default()
+                    },
+                    transform: Transform::from_translation(next_row_center(
+                        grid, x, origin, mirrored,
+                    )),
+                    ..Default::default()
+                })
+                .insert(GameEntity)
+                .id()
+        })
+        .collect()
+}
+
+fn spawn_background_grid(
+    commands: &mut Commands,
+    grid: &Grid,
+    origin: Vec2,
+    palette: &Palette,
+) -> Vec<Entity> {
+    let mut cells = Vec::with_capacity(grid.width * grid.height);
+    for y in 0..grid.height {
+        for x in 0..grid.width {
+            let pos = cell_center(grid, x, y, origin, false);
+            let cell = commands
+                .spawn(SpriteBundle {
+                    sprite: Sprite {
+                        color: palette.grid_cell,
+                        custom_size: Some(Vec2::splat(CELL_SIZE - 1.0)),
+                        ..Default::default()
+                    },
+                    transform: Transform::from_translation(pos - Vec3::new(0.0, 0.0, 1.0)),
+                    ..Default::default()
+                })
+                .insert(GameEntity)
+                .id();
+            cells.push(cell);
+        }
+    }
+    cells
+}
+
+fn spawn_frame_and_panel(
+    commands: &mut Commands,
+    grid: &Grid,
+    origin: Vec2,
+    _panel_side: PanelSide,
+    palette: &Palette,
+) -> ([Entity; 4], Entity) {
+    let grid_h = GRID_H as f32 * CELL_SIZE;
+    let frame = frame_rects(grid, origin).map(|(pos, size)| {
+        commands
+            .spawn(SpriteBundle {
+                sprite: Sprite {
+                    color: palette.frame,
+                    custom_size: Some(size),
+                    ..Default::default()
+                },
+                transform: Transform::from_translation(pos),
+                ..Default::default()
+            })
+            .insert(GameEntity)
+            .id()
+    });
+
+    let panel = commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(0.0),
+                top: Val::Px(0.0),
+                width: Val::Px(PANEL_WIDTH),
+                height: Val::Px(grid_h + FRAME_THICKNESS * 2.0),
+                flex_direction: FlexDirection::Column,
+                ..Default::default()
+            },
+            background_color: BackgroundColor(palette.panel),
+            ..Default::default()
+        })
+        .insert(GameEntity)
+        .id();
+
+    commands.entity(panel).with_children(|parent| {
+        parent
+            .spawn(NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Px(28.0),
+                    ..Default::default()
+                },
+                background_color: BackgroundColor(palette.panel_header),
+                ..Default::default()
+            })
+            .insert(GameEntity);
+    });
+
+    (frame, panel)
+}
+
+fn frame_rects(grid: &Grid, origin: Vec2) -> [(Vec3, Vec2); 4] {
+    let grid_w = grid.width as f32 * CELL_SIZE;
+    let grid_h = grid.height as f32 * CELL_SIZE;
+    let half_w = grid_w / 2.0;
+    let half_h = grid_h / 2.0;
+
+    let origin3 = Vec3::new(origin.x, origin.y, 0.0);
+    let top = origin3 + Vec3::new(0.0, half_h + FRAME_THICKNESS / 2.0, -0.5);
+    let bottom = origin3 + Vec3::new(0.0, -half_h - FRAME_THICKNESS / 2.0, -0.5);
+    let left = origin3 + Vec3::new(-half_w - FRAME_THICKNESS / 2.0, 0.0, -0.5);
+    let right = origin3 + Vec3::new(half_w + FRAME_THICKNESS / 2.0, 0.0, -0.5);
+
+    let horizontal_size = Vec2::new(grid_w + FRAME_THICKNESS * 2.0, FRAME_THICKNESS);
+    let vertical_size = Vec2::new(FRAME_THICKNESS, grid_h);
+
+    [
+        (top, horizontal_size),
+        (bottom, horizontal_size),
+        (left, vertical_size),
+        (right, vertical_size),
+    ]
+}
+
+// A grid resized mid-session remaps its block sprites, gets a fresh background and
+// a refitted frame, so board gimmicks only have to call resize_board.
+fn resize_player_views(
+    mut commands: Commands,
+    players: Res<Players>,
+    mut views: ResMut<PlayerViews>,
+    palette: Res<Palette>,
+    mut frame_query: Query<(&mut Sprite, &mut Transform)>,
+) {
+    let PlayerViews { p1, p2, .. } = &mut *views;
+    for (player, view) in [(&players.p1, Some(p1)), (&players.p2, p2.as_mut())] {
+        let Some(view) = view else {
+            continue;
+        };
+        let grid = &player.grid;
+        let cells = grid.width * grid.height;
+        if view.blocks.cell_count() == cells && view.next_row.len() == grid.width {
+            continue;
+        }
+        for sprite in view.blocks.resize(cells) {
+            for entity in [sprite.fill, sprite.edge] {
+                if let Ok((mut sprite, _)) = frame_query.get_mut(entity) {
+                    sprite.color = Color::srgba(0.0, 0.0, 0.0, 0.0);
+                }
+            }
+        }
+        for entity in view.background.drain(..).chain(view.next_row.drain(..)) {
+            commands.entity(entity).despawn_recursive();
+        }
+        view.background = spawn_background_grid(&mut commands, grid, view.origin, &palette);
+        view.next_row = spawn_next_row(&mut commands, grid, view.origin, view.mirrored);
+        for (entity, (pos, size)) in view.frame.iter().zip(frame_rects(grid, view.origin)) {
+            if let Ok((mut sprite, mut transform)) = frame_query.get_mut(*entity) {
+                sprite.custom_size = Some(size);
+                transform.translation = pos;
+            }
+        }
+    }
+}
+
+fn spawn_ui_texts(commands: &mut Commands, panel: Entity, palette: &Palette) -> UiTexts {
+    let panel_margin = 16.0;
+    let style = TextStyle {
+        font: Default::default(),
+        font_size: 20.0,
+        color: palette.heading,
+    };
+
+    let score = commands
+        .spawn(TextBundle {
+            text: Text::from_section("", style.clone()),
+            style: Style {
+                margin: UiRect::all(Val::Px(panel_margin)),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(GameEntity)
+        .set_parent(panel)
+        .id();
+
+    let timer = commands
+        .spawn(TextBundle {
+            text: Text::from_section("", style),
+            style: Style {
+                margin: UiRect::left(Val::Px(panel_margin)),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(GameEntity)
+        .set_parent(panel)
+        .id();
+
+    let garbage_style = TextStyle {
+        font: Default::default(),
+        font_size: 16.0,
+        color: palette.text,
+    };
+
+    let garbage_sent = commands
+        .spawn(TextBundle {
+            text: Text::from_section("", garbage_style.clone()),
+            style: Style {
+                margin: UiRect {
+                    left: Val::Px(panel_margin),
+                    top: Val::Px(panel_margin),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            visibility: Visibility::Hidden,
+            ..Default::default()
+        })
+        .insert(GameEntity)
+        .set_parent(panel)
+        .id();
+
+    let garbage_incoming = commands
+        .spawn(TextBundle {
+            text: Text::from_sections([
+                TextSection::new("", garbage_style.clone()),
+                TextSection::new(
+                    "",
+                    TextStyle {
+                        color: palette.highlight,
+                        ..garbage_style
+                    },
+                ),
+            ]),
+            style: Style {
+                margin: UiRect {
+                    left: Val::Px(panel_margin),
+                    bottom: Val::Px(panel_margin),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            visibility: Visibility::Hidden,
+            ..Default::default()
+        })
+        .insert(GameEntity)
+        .set_parent(panel)
+        .id();
+
+    let status = commands
+        .spawn(TextBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font: Default::default(),
+                    font_size: 22.0,
+                    color: palette.danger,
+                },
+            ),
+            style: Style {
+                margin: UiRect::left(Val::Px(panel_margin)),
+                ..Default::default()
+            },
+            visibility: Visibility::Hidden,
+            ..Default::default()
+        })
+        .insert(GameEntity)
+        .set_parent(panel)
+        .id();
+
+    let danger = commands
+        .spawn(TextBundle {
+            text: Text::from_section(
+                "!",
+                TextStyle {
+                    font: Default::default(),
+                    font_size: 48.0,
+                    color: palette.danger,
+                },
+            ),
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(0.0),
+                right: Val::Px(panel_margin),
+                ..Default::default()
+            },
+            visibility: Visibility::Hidden,
+            ..Default::default()
+        })
+        .insert(GameEntity)
+        .set_parent(panel)
+        .id();
+
+    UiTexts {
+        score,
+        timer,
+        garbage_sent,
+        garbage_incoming,
+        status,
+        danger,
+    }
+}
+
+fn spawn_rise_meter(commands: &mut Commands, panel: Entity, palette: &Palette) -> RiseMeter {
+    let root = commands
+        .spawn(NodeBundle {
+            style: Style {
+                align_items: AlignItems::FlexEnd,
+                column_gap: Val::Px(8.0),
+                margin: UiRect::new(Val::Px(16.0), Val::Px(8.0), Val::Px(8.0), Val::Px(0.0)),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(GameEntity)
+        .set_parent(panel)
+        .id();
+
+    let mut level = Entity::PLACEHOLDER;
+    let mut fill = Entity::PLACEHOLDER;
+    commands.entity(root).with_children(|parent| {
+        parent
+            .spawn(NodeBundle {
+                style: Style {
+                    width: Val::Px(8.0),
+                    height: Val::Px(RISE_METER_HEIGHT),
+                    flex_direction: FlexDirection::ColumnReverse,
+                    ..Default::default()
+                },
+                background_color: BackgroundColor(palette.panel_header),
+                ..Default::default()
+            })
+            .with_children(|bar| {
+                fill = bar
+                    .spawn(NodeBundle {
+                        style: Style {
+                            width: Val::Percent(100.0),
+                            height: Val::Percent(0.0),
+                            ..Default::default()
+                        },
+                        background_color: BackgroundColor(palette.accent),
+                        ..Default::default()
+                    })
+                    .id();
+            });
+        level = parent
+            .spawn(TextBundle {
+                text: Text::from_section(
+                    "",
+                    TextStyle {
+                        font: Default::default(),
+                        font_size: 16.0,
+                        color: palette.text,
+                    },
+                ),
+                ..Default::default()
+            })
+            .id();
+    });
+
+    RiseMeter { root, level, fill }
+}
+
+// The bar fills toward the next row push; zen has no automatic rise, so it hides there.
+#[allow(clippy::too_many_arguments)]
+fn update_rise_meters(
+    players: Res<Players>,
+    views: Res<PlayerViews>,
+    mode: Res<GameMode>,
+    rules: Res<MatchRules>,
+    locale: Res<Locale>,
+    mut text_query: Query<&mut Text>,
+    mut style_query: Query<&mut Style>,
+    mut vis_query: Query<&mut Visibility>,
+) {
+    let zen = *mode == GameMode::OnePlayer && rules.solo == SoloMode::Zen;
+    for id in active_players(*mode) {
+        let Some(view) = player_view(&views, *id) else {
+            continue;
+        };
+        let meter = &view.rise_meter;
+        if let Ok(mut visibility) = vis_query.get_mut(meter.root) {
+            *visibility = if zen {
+                Visibility::Hidden
+            } else {
+                Visibility::Inherited
+            };
+        }
+        let player = players.get(*id);
+        if let Ok(mut text) = text_query.get_mut(meter.level) {
+            text.sections[0].value = locale.format("hud.speed", &[("level", &player.rise_level)]);
+        }
+        if let Ok(mut style) = style_query.get_mut(meter.fill) {
+            style.height = Val::Percent(player.rise_timer.fraction() * 100.0);
+        }
+    }
+}
+
+fn spawn_chain_chart(
+    commands: &mut Commands,
+    panel: Entity,
+    palette: &Palette,
+    locale: &Locale,
+) -> ChainChart {
+    let style = TextStyle {
+        font: Default::default(),
+        font_size: 12.0,
+        color: palette.text,
+    };
+    let root = commands
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                margin: UiRect::new(Val::Px(16.0), Val::Px(8.0), Val::Px(8.0), Val::Px(0.0)),
+                row_gap: Val::Px(2.0),
+                ..Default::default()
+            },
+            visibility: Visibility::Hidden,
+            ..Default::default()
+        })
+        .insert(GameEntity)
+        .set_parent(panel)
+        .id();
+
+    let mut bars = [Entity::PLACEHOLDER; CHAIN_BUCKETS];
+    let mut counts = [Entity::PLACEHOLDER; CHAIN_BUCKETS];
+    commands.entity(root).with_children(|parent| {
+        parent.spawn(TextBundle {
+            text: Text::from_section(
+                locale.get("results.chains"),
+                TextStyle {
+                    color: palette.heading,
+                    ..style.clone()
+                },
+            ),
+            ..Default::default()
+        });
+        for bucket in 0..CHAIN_BUCKETS {
+            let label = if bucket + 1 == CHAIN_BUCKETS {
+                format!("x{}+", bucket + 1)
+            } else {
+                format!("x{}", bucket + 1)
+            };
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        align_items: AlignItems::Center,
+                        column_gap: Val::Px(4.0),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .with_children(|row| {
+                    row.spawn(TextBundle {
+                        text: Text::from_section(label, style.clone()),
+                        style: Style {
+                            width: Val::Px(24.0),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    });
+                    bars[bucket] = row
+                        .spawn(NodeBundle {
+                            style: Style {
+                                width: Val::Px(0.0),
+                                height: Val::Px(8.0),
+                                ..Default::default()
+                            },
+                            background_color: BackgroundColor(palette.accent),
+                            ..Default::default()
+                        })
+                        .id();
+                    counts[bucket] = row
+                        .spawn(TextBundle {
+                            text: Text::from_section("", style.clone()),
+                            ..Default::default()
+                        })
+                        .id();
+                });
+        }
+    });
+
+    ChainChart { root, bars, counts }
+}
+
+fn update_chain_charts(
+    players: Res<Players>,
+    match_over: Res<MatchOver>,
+    views: Res<PlayerViews>,
+    mode: Res<GameMode>,
+    mut text_query: Query<&mut Text>,
+    mut style_query: Query<&mut Style>,
+    mut vis_query: Query<&mut Visibility>,
+) {
+    for id in active_players(*mode) {
+        let Some(view) = player_view(&views, *id) else {
+            continue;
+        };
+        let chart = &view.chains;
+        if let Ok(mut visibility) = vis_query.get_mut(chart.root) {
+            *visibility = if match_over.active {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            };
+        }
+        if !match_over.active {
+            continue;
+        }
+        let chain_counts = players.get(*id).chain_counts;
+        let most = chain_counts.iter().copied().max().unwrap_or(0).max(1);
+        for (bucket, count) in chain_counts.into_iter().enumerate() {
+            if let Ok(mut style) = style_query.get_mut(chart.bars[bucket]) {
+                style.width = Val::Px(CHAIN_BAR_MAX * count as f32 / most as f32);
+            }
+            if let Ok(mut text) = text_query.get_mut(chart.counts[bucket]) {
+                text.sections[0].value = count.to_string();
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_ui_text(
+    players: Res<Players>,
+    match_over: Res<MatchOver>,
+    views: Res<PlayerViews>,
+    mode: Res<GameMode>,
+    rules: Res<MatchRules>,
+    locale: Res<Locale>,
+    mut text_query: Query<&mut Text>,
+    mut vis_query: Query<&mut Visibility>,
+) {
+    let two_player = *mode == GameMode::TwoPlayer;
+    update_player_ui(
+        PlayerId::P1,
+        &players.p1,
+        two_player.then_some(&players.p2),
+        &views.p1.ui,
+        &match_over,
+        &rules,
+        &locale,
+        &mut text_query,
+        &mut vis_query,
+    );
+    if two_player && let Some(p2_view) = &views.p2 {
+        update_player_ui(
+            PlayerId::P2,
+            &players.p2,
+            Some(&players.p1),
+            &p2_view.ui,
+            &match_over,
+            &rules,
+            &locale,
+            &mut text_query,
+            &mut vis_query,
+        );
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_player_ui(
+    player_id: PlayerId,
+    player: &PlayerState,
+    opponent: Option<&PlayerState>,
+    ui: &UiTexts,
+    match_over: &MatchOver,
+    rules: &MatchRules,
+    locale: &Locale,
+    text_query: &mut Query<&mut Text>,
+    vis_query: &mut Query<&mut Visibility>,
+) {
+    if let Ok(mut text) = text_query.get_mut(ui.score) {
+        text.sections[0].value = locale.format("hud.score", &[("score", &player.score)]);
+    }
+    if let Ok(mut text) = text_query.get_mut(ui.timer) {
+        text.sections[0].value =
+            locale.format("hud.time", &[("time", &format!("{:.1}", player.elapsed))]);
+    }
+
+    let survival = opponent.is_none() && rules.solo == SoloMode::Survival;
+    let zen = opponent.is_none() && rules.solo == SoloMode::Zen;
+    let marathon = opponent.is_none() && rules.solo == SoloMode::Marathon;
+    for (entity, shown) in [
+        (
+            ui.garbage_sent,
+            opponent.is_some() || survival || zen || marathon,
+        ),
+        (ui.garbage_incoming, opponent.is_some() || survival),
+    ] {
+        if let Ok(mut visibility) = vis_query.get_mut(entity) {
+            *visibility = if shown {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            };
+        }
+    }
+    if zen && let Ok(mut text) = text_query.get_mut(ui.garbage_sent) {
+        text.sections[0].value = locale.format("hud.zen", &[("chain", &player.best_chain)]);
+    }
+    if marathon && let Ok(mut text) = text_query.get_mut(ui.garbage_sent) {
+        text.sections[0].value = locale.format(
+            "hud.marathon",
+            &[
+                ("level", &player.rise_level),
+                ("goal", &marathon::GOAL_LEVEL),
+                (
+                    "next",
+                    &marathon::blocks_to_next_level(player.blocks_cleared),
+                ),
+            ],
+        );
+    }
+    if opponent.is_some() || survival {
+        if let Ok(mut text) = text_query.get_mut(ui.garbage_sent) {
+            text.sections[0].value = if survival {
+                locale.format(
+                    "hud.survival",
+                    &[
+                        ("wave", &player.waves_dropped),
+                        ("blocks", &player.blocks_cleared),
+                    ],
+                )
+            } else {
+                locale.format(
+                    "hud.garbage",
+                    &[
+                        ("sent", &player.garbage_sent),
+                        ("received", &player.garbage_received),
+                    ],
+                )
+            };
+        }
+        if let Ok(mut text) = text_query.get_mut(ui.garbage_incoming) {
+            text.sections[0].value =
+                locale.format("hud.incoming", &[("count", &player.garbage_incoming())]);
+            text.sections[1].value = match opponent {
+                Some(opponent) if opponent.garbage_outgoing > 0 => {
+                    format!(" +{}", opponent.garbage_outgoing)
+                }
+                _ => String::new(),
+            };
+        }
+    }
+
+    if let Ok(mut visibility) = vis_query.get_mut(ui.status) {
+        if match_over.active && !matches!(rules.solo, SoloMode::Challenge(_) | SoloMode::Trial(_)) {
+            *visibility = Visibility::Visible;
+        } else {
+            *visibility = Visibility::Hidden;
+        }
+    }
+
+    if match_over.active
+        && let Ok(mut text) = text_query.get_mut(ui.status)
+    {
+        let won = match_over.winner == Some(player_id);
+        text.sections[0].value = if marathon {
+            let fastest = marathon::fastest_level(&player.level_splits)
+                .map_or_else(|| "-".to_string(), |seconds| format!("{seconds:.1}"));
+            locale.format(
+                if won {
+                    "hud.marathon_clear"
+                } else {
+                    "hud.marathon_over"
+                },
+                &[
+                    ("level", &player.rise_level),
+                    ("time", &format!("{:.1}", player.elapsed)),
+                    ("fastest", &fastest),
+                ],
+            )
+        } else if survival {
+            locale.format(
+                "hud.survived",
+                &[
+                    ("time", &format!("{:.1}", player.elapsed)),
+                    ("blocks", &player.blocks_cleared),
+                ],
+            )
+        } else if opponent.is_none() || rules.rounds_to_win() == 1 {
+            locale
+                .get(if won { "hud.you_win" } else { "hud.game_over" })
+                .to_string()
+        } else {
+            let key = match (won, match_over.series_decided(rules)) {
+                (true, true) => "hud.series_won",
+                (false, true) => "hud.series_lost",
+                (true, false) => "hud.round_won",
+                (false, false) => "hud.round_lost",
+            };
+            let opponent_id = match player_id {
+                PlayerId::P1 => PlayerId::P2,
+                PlayerId::P2 => PlayerId::P1,
+            };
+            locale.format(
+                key,
+                &[
+                    ("wins", &match_over.rounds(player_id)),
+                    ("losses", &match_over.rounds(opponent_id)),
+                ],
+            )
+        };
+    }
+}
+
+fn board_height() -> f32 {
+    (GRID_H + 2) as f32 * CELL_SIZE + FRAME_THICKNESS * 4.0
+}
+
+fn content_size(mode: GameMode, panel_width: f32, arrangement: BoardArrangement) -> Vec2 {
+    let grid_w = GRID_W as f32 * CELL_SIZE;
+    let player_w = grid_w + panel_width + PANEL_GAP;
+    match (mode, arrangement) {
+        (GameMode::OnePlayer, _) => Vec2::new(
+            (grid_w / 2.0 + PANEL_GAP + panel_width) * 2.0,
+            board_height(),
+        ),
+        (GameMode::TwoPlayer, BoardArrangement::SideBySide) => {
+            Vec2::new(player_w * 2.0 + PLAYER_GAP, board_height())
+        }
+        (GameMode::TwoPlayer, BoardArrangement::Stacked) => {
+            Vec2::new(player_w, board_height() * 2.0 + PLAYER_GAP)
+        }
+    }
+}
+
+fn fit_scale(content: Vec2, window: Vec2) -> f32 {
+    let needed = content + Vec2::splat(VIEW_MARGIN * 2.0);
+    (needed.x / window.x).max(needed.y / window.y)
+}
+
+// Picks whichever arrangement draws the boards larger, so portrait and
+// half-screen windows stack the boards.
+fn update_board_arrangement(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mode: Res<GameMode>,
+    settings: Res<settings::Settings>,
+    mut views: ResMut<PlayerViews>,
+    mut transform_query: Query<&mut Transform>,
+) {
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let window = Vec2::new(window.width(), window.height());
+    let mode = views.layout_mode(*mode);
+    if mode == GameMode::OnePlayer || window.x <= 0.0 || window.y <= 0.0 {
+        return;
+    }
+    let panel_width = views.layout_panel_width();
+    let arrangement = [BoardArrangement::SideBySide, BoardArrangement::Stacked]
+        .into_iter()
+        .min_by(|a, b| {
+            let a = fit_scale(content_size(mode, panel_width, *a), window);
+            let b = fit_scale(content_size(mode, panel_width, *b), window);
+            a.total_cmp(&b)
+        })
+        .unwrap_or_default();
+    if arrangement == views.arrangement {
+        return;
+    }
+    views.arrangement = arrangement;
+    place_views(&mut views, mode, &settings, &mut transform_query);
+}
+
+fn place_views(
+    views: &mut PlayerViews,
+    mode: GameMode,
+    settings: &settings::Settings,
+    transform_query: &mut Query<&mut Transform>,
+) {
+    let (p1_origin, p2_origin) = compute_player_origins(
+        mode,
+        views.layout_panel_width(),
+        [PlayerId::P1, PlayerId::P2].map(|id| settings.board_layout(id).panel),
+        views.arrangement,
+    );
+    move_view(&mut views.p1, p1_origin, transform_query);
+    if let Some(view) = views.second() {
+        move_view(view, p2_origin, transform_query);
+    }
+}
+
+// Board sprites that are only placed at spawn move along with the origin.
+fn move_view(view: &mut PlayerView, origin: Vec2, transform_query: &mut Query<&mut Transform>) {
+    let shift = (origin - view.origin).extend(0.0);
+    view.origin = origin;
+    let placed = view
+        .background
+        .iter()
+        .chain(&view.next_row)
+        .chain(&view.frame);
+    for entity in placed {
+        if let Ok(mut transform) = transform_query.get_mut(*entity) {
+            transform.translation += shift;
+        }
+    }
+}
+
+fn fit_camera_to_window(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mode: Res<GameMode>,
+    views: Res<PlayerViews>,
+    mut cameras: Query<&mut OrthographicProjection, (With<Camera2d>, Without<split::BoardCamera>)>,
+) {
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    if window.width() <= 0.0 || window.height() <= 0.0 {
+        return;
+    }
+    let scale = fit_scale(
+        content_size(
+            views.layout_mode(*mode),
+            views.layout_panel_width(),
+            views.arrangement,
+        ),
+        Vec2::new(window.width(), window.height()),
+    );
+    for mut projection in &mut cameras {
+        if projection.scale != scale {
+            projection.scale = scale;
+        }
+    }
+}
+
+// Split screen places the panels itself, against each board's own camera.
+fn update_panel_layout(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&OrthographicProjection, &Transform), Without<split::BoardCamera>>,
+    board_cameras: Query<(), With<split::BoardCamera>>,
+    views: Res<PlayerViews>,
+    mode: Res<GameMode>,
+    ui_scale: Res<UiScale>,
+    mut style_query: Query<&mut Style>,
+) {
+    let window = match windows.get_single() {
+        Ok(window) => window,
+        Err(_) => return,
+    };
+    if !board_cameras.is_empty() {
+        return;
+    }
+    let (scale, camera_center) = cameras
+        .get_single()
+        .map_or((1.0, Vec2::ZERO), |(projection, transform)| {
+            (projection.scale, transform.translation.truncate())
+        });
+
+    let grid_h = GRID_H as f32 * CELL_SIZE;
+    let panel_h = grid_h + FRAME_THICKNESS * 2.0;
+    let window_size = Vec2::new(window.width(), window.height());
+
+    position_panel(
+        &views.p1,
+        window_size / 2.0,
+        camera_center,
+        scale,
+        ui_scale.0,
+        panel_h,
+        &mut style_query,
+    );
+    let second = match *mode {
+        GameMode::TwoPlayer => views.p2.as_ref(),
+        GameMode::OnePlayer => views.ghost.as_ref(),
+    };
+    if let Some(second) = second {
+        position_panel(
+            second,
+            window_size / 2.0,
+            camera_center,
+            scale,
+            ui_scale.0,
+            panel_h,
+            &mut style_query,
+        );
+    }
+}
+
+fn position_panel(
+    view: &PlayerView,
+    screen_center: Vec2,
+    camera_center: Vec2,
+    scale: f32,
+    ui_scale: f32,
+    panel_h: f32,
+    style_query: &mut Query<&mut Style>,
+) {
+    let grid_w = GRID_W as f32 * CELL_SIZE;
+    let world_left = match view.panel_side {
+        PanelSide::Right => view.origin.x + grid_w / 2.0 + PANEL_GAP,
+        PanelSide::Left => view.origin.x - grid_w / 2.0 - PANEL_GAP - view.panel_width,
+    };
+
+    if let Ok(mut style) = style_query.get_mut(view.panel) {
+        let top = screen_center.y - (view.origin.y + panel_h / 2.0 - camera_center.y) / scale;
+        style.left = Val::Px((screen_center.x + (world_left - camera_center.x) / scale) / ui_scale);
+        style.top = Val::Px(top.max(0.0) / ui_scale);
+        style.width = Val::Px(view.panel_width / scale / ui_scale);
+        style.height = Val::Px(panel_h / scale / ui_scale);
+    }
+}
+
+fn spawn_cursor(commands: &mut Commands, origin: Vec2, single: bool, palette: &Palette) -> Entity {
+    let width = if single { CELL_SIZE } else { CELL_SIZE * 2.0 };
+    let height = CELL_SIZE;
+    let thickness = CURSOR_BORDER_THICKNESS;
+    let color = palette.cursor;
+
+    let cursor = commands
+        .spawn(SpatialBundle {
+            transform: Transform::from_translation(Vec3::new(origin.x, origin.y, 1.0)),
+            ..Default::default()
+        })
+        .insert(GameEntity)
+        .id();
+
+    commands.entity(cursor).with_children(|parent| {
+        let horizontal = Vec2::new(width, thickness);
+        let vertical = Vec2::new(thickness, height);
+
+        let top_y = height / 2.0 - thickness / 2.0;
+        let bottom_y = -height / 2.0 + thickness / 2.0;
+        let left_x = -width / 2.0 + thickness / 2.0;
+        let right_x = width / 2.0 - thickness / 2.0;
+
+        for (pos, size) in [
+            (Vec3::new(0.0, top_y, 0.0), horizontal),
+            (Vec3::new(0.0, bottom_y, 0.0), horizontal),
+            (Vec3::new(left_x, 0.0, 0.0), vertical),
+            (Vec3::new(right_x, 0.0, 0.0), vertical),
+        ] {
+            parent.spawn(SpriteBundle {
+                sprite: Sprite {
+                    color,
+                    custom_size: Some(size),
+                    ..Default::default()
+                },
+                transform: Transform::from_translation(pos),
+                ..Default::default()
+            });
+        }
+    });
+
+    cursor
+}
+
+fn spawn_hover(commands: &mut Commands, palette: &Palette) -> Entity {
+    commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color: palette.cursor.with_alpha(HOVER_ALPHA),
+                ..Default::default()
+            },
+            visibility: Visibility::Hidden,
+            ..Default::default()
+        })
+        .insert(GameEntity)
+        .id()
+}
+
+#[derive(Component, Default)]
+struct ChainIndicator {
+    shown: u32,
+    pulse: f32,
+    fade: f32,
+}
+
+fn spawn_chain_indicator(commands: &mut Commands, palette: &Palette) -> Entity {
+    commands
+        .spawn(Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font: Default::default(),
+                    font_size: 26.0,
+                    color: palette.popup_chain,
+                },
+            ),
+            visibility: Visibility::Hidden,
+            ..Default::default()
+        })
+        .insert((ChainIndicator::default(), GameEntity))
+        .id()
+}
+
+// Sits above the board's top-left corner while a chain of x2 or more is running,
+// pulses each time it grows and fades out once the chain ends.
+fn update_chain_indicators(
+    time: Res<Time>,
+    players: Res<Players>,
+    views: Res<PlayerViews>,
+    mode: Res<GameMode>,
+    settings: Res<settings::Settings>,
+    mut indicator_query: Query<(
+        &mut ChainIndicator,
+        &mut Text,
+        &mut Transform,
+        &mut Visibility,
+    )>,
+) {
+    let delta = time.delta_seconds();
+    for id in active_players(*mode) {
+        let Some(view) = player_view(&views, *id) else {
+            continue;
+        };
+        let Ok((mut indicator, mut text, mut transform, mut visibility)) =
+            indicator_query.get_mut(view.chain_indicator)
+        else {
+            continue;
+        };
+        let player = players.get(*id);
+        let chain = player.chain_index;
+        if chain >= 2 {
+            if chain != indicator.shown {
+                indicator.shown = chain;
+                indicator.pulse = CHAIN_PULSE_SECONDS;
+                text.sections[0].value = format!("x{chain}");
+            }
+            indicator.fade = CHAIN_FADE_SECONDS;
+        } else if indicator.fade > 0.0 {
+            indicator.fade = (indicator.fade - delta).max(0.0);
+        } else {
+            indicator.shown = 0;
+        }
+        indicator.pulse = (indicator.pulse - delta).max(0.0);
+        *visibility = if indicator.fade > 0.0 {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+        let alpha = indicator.fade / CHAIN_FADE_SECONDS;
+        let color = text.sections[0].style.color;
+        text.sections[0].style.color = color.with_alpha(alpha);
+        let scale = if settings.reduced_motion {
+            1.0
+        } else {
+            1.0 + CHAIN_PULSE_SCALE * indicator.pulse / CHAIN_PULSE_SECONDS
+        };
+        let grid = &player.grid;
+        transform.translation = Vec3::new(
+            view.origin.x - grid.width as f32 * CELL_SIZE / 2.0 + CELL_SIZE / 2.0,
+            view.origin.y + grid.height as f32 * CELL_SIZE / 2.0 + FRAME_THICKNESS + 16.0,
+            6.0,
+        );
+        transform.scale = Vec3::splat(scale);
+    }
+}
+
+#[derive(Component, Default)]
+struct GarbagePreview {
+    shape: Vec<Vec<bool>>,
+}
+
+fn spawn_garbage_preview(commands: &mut Commands, palette: &Palette) -> Entity {
+    commands
+        .spawn(SpatialBundle {
+            visibility: Visibility::Hidden,
+            ..Default::default()
+        })
+        .insert((GarbagePreview::default(), GameEntity))
+        .with_children(|parent| {
+            parent.spawn(Text2dBundle {
+                text: Text::from_section(
+                    "",
+                    TextStyle {
+                        font: Default::default(),
+                        font_size: 14.0,
+                        color: palette.warning,
+                    },
+                ),
+                text_anchor: Anchor::BottomRight,
+                ..Default::default()
+            });
+        })
+        .id()
+}
+
+// Sits above the board's top-right corner while garbage is queued. The holes come
+// from the board's garbage RNG, so the preview matches what lands as long as the
+// whole queue fits on the board in one drop.
+fn update_garbage_previews(
+    mut commands: Commands,
+    players: Res<Players>,
+    views: Res<PlayerViews>,
+    mode: Res<GameMode>,
+    palette: Res<Palette>,
+    mut preview_query: Query<(
+        &mut GarbagePreview,
+        &mut Transform,
+        &mut Visibility,
+        &Children,
+    )>,
+    mut text_query: Query<&mut Text>,
+) {
+    for id in active_players(*mode) {
+        let Some(view) = player_view(&views, *id) else {
+            continue;
+        };
+        let Ok((mut preview, mut transform, mut visibility, children)) =
+            preview_query.get_mut(view.garbage_preview)
+        else {
+            continue;
+        };
+        let player = players.get(*id);
+        let grid = &player.grid;
+        let shape = build_garbage_rows(
+            grid.width,
+            player.garbage_incoming(),
+            &mut grid.garbage_rng().clone(),
+        );
+        *visibility = if shape.is_empty() {
+            Visibility::Hidden
+        } else {
+            Visibility::Visible
+        };
+        transform.translation = Vec3::new(
+            view.origin.x + grid.width as f32 * CELL_SIZE / 2.0,
+            view.origin.y + grid.height as f32 * CELL_SIZE / 2.0 + FRAME_THICKNESS + 6.0,
+            6.0,
+        );
+        if shape == preview.shape {
+            continue;
+        }
+        // The bottom row of the drop is drawn lowest; anything past the preview
+        // height is summed up by the label to its left.
+        let left = -(grid.width as f32 * GARBAGE_PREVIEW_CELL);
+        let mut label = children.iter();
+        if let Some(entity) = label.next()
+            && let Ok(mut text) = text_query.get_mut(*entity)
+        {
+            text.sections[0].value = format!("{}x{}", shape.len(), grid.width);
+            commands
+                .entity(*entity)
+                .insert(Transform::from_xyz(left - 4.0, 0.0, 0.0));
+        }
+        for entity in label {
+            commands.entity(*entity).despawn_recursive();
+        }
+        commands
+            .entity(view.garbage_preview)
+            .with_children(|parent| {
+                for (row, cells) in shape.iter().take(GARBAGE_PREVIEW_ROWS).enumerate() {
+                    for (x, filled) in cells.iter().enumerate() {
+                        let x = if view.mirrored {
+                            cells.len() - 1 - x
+                        } else {
+                            x
+                        };
+                        let color = if *filled {
+                            palette.garbage
+                        } else {
+                            palette.grid_cell
+                        };
+                        parent.spawn(SpriteBundle {
+                            sprite: Sprite {
+                                color,
+                                custom_size: Some(Vec2::splat(GARBAGE_PREVIEW_CELL - 1.0)),
+                                ..Default::default()
+                            },
+                            transform: Transform::from_xyz(
+                                left + (x as f32 + 0.5) * GARBAGE_PREVIEW_CELL,
+                                (row as f32 + 0.5) * GARBAGE_PREVIEW_CELL,
+                                0.0,
+                            ),
+                            ..Default::default()
+                        });
+                    }
+                }
+            });
+        preview.shape = shape;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_mouse_hover(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    views: Res<PlayerViews>,
+    players: Res<Players>,
+    mode: Res<GameMode>,
+    local: Res<LocalControl>,
+    cpu: Res<CpuControl>,
+    mut hover_query: Query<(&mut Sprite, &mut Transform, &mut Visibility)>,
+) {
+    let point = pointer_world(&windows, &cameras);
+    let controlled = mouse_players(*mode, &local, &cpu);
+    for id in active_players(*mode) {
+        let (player, Some(view)) = (players.get(*id), player_view(&views, *id)) else {
+            continue;
+        };
+        let Ok((mut sprite, mut transform, mut visibility)) = hover_query.get_mut(view.hover)
+        else {
+            continue;
+        };
+        let cell = point
+            .filter(|_| controlled.contains(id))
+            .and_then(|point| pointer_cell(&player.grid, view, point));
+        let Some((x, y)) = cell else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        let cursor = snapped_cursor(player, x, y);
+        let (span_x, span_y) = cursor_span(player);
+        let first = cell_center(&player.grid, cursor.x, cursor.y, view.origin, view.mirrored);
+        let last = cell_center(
+            &player.grid,
+            cursor.x + span_x - 1,
+            cursor.y + span_y - 1,
+            view.origin,
+            view.mirrored,
+        );
+        sprite.custom_size = Some(Vec2::new(span_x as f32, span_y as f32) * CELL_SIZE);
+        transform.translation = ((first + last) / 2.0).truncate().extend(0.5);
+        *visibility = Visibility::Visible;
+    }
+}
+
+// A fresh garbage drop slides in from above the frame, and a slab squashes and
+// settles on the frame it comes to rest.
+fn track_garbage_motion(
+    time: Res<Time>,
+    players: Res<Players>,
+    mode: Res<GameMode>,
+    mut motions: ResMut<GarbageMotions>,
+) {
+    let delta = time.delta_seconds();
+    for id in active_players(*mode) {
+        let grid = &players.get(*id).grid;
+        let received = players.get(*id).garbage_received;
+        let motion = motions.get_mut(*id);
+        let garbage: Vec<bool> = grid
+            .iter_cells()
+            .map(|(_, _, cell)| cell.is_some_and(Block::is_garbage))
+            .collect();
+        let falling = grid.falling_garbage();
+        if garbage.len() != motion.garbage.len() || received < motion.received {
+            *motion = GarbageMotion {
+                received,
+                garbage,
+                falling,
+                ..Default::default()
+            };
+            continue;
+        }
+        if received > motion.received {
+            motion.drop = GARBAGE_DROP_SECONDS;
+            motion.entering = garbage
+                .iter()
+                .zip(&motion.garbage)
+                .map(|(now, before)| *now && !*before)
+                .collect();
+        }
+        motion.received = received;
+        let mut landed: Vec<bool> = (0..garbage.len())
+            .map(|idx| {
+                let above = idx + grid.width;
+                garbage[idx]
+                    && !falling[idx]
+                    && grid.just_fell(idx % grid.width, idx / grid.width)
+                    && motion.falling.get(above).copied().unwrap_or(false)
+            })
+            .collect();
+        if motion.drop > 0.0 {
+            motion.drop = (motion.drop - delta).max(0.0);
+            if motion.drop == 0.0 {
+                for (idx, entering) in motion.entering.iter().enumerate() {
+                    landed[idx] |= *entering && garbage[idx] && !falling[idx];
+                }
+            }
+        }
+        motion.squash = (motion.squash - delta).max(0.0);
+        if landed.contains(&true) {
+            motion.landed = landed;
+            motion.squash = GARBAGE_SQUASH_SECONDS;
+        }
+        motion.garbage = garbage;
+        motion.falling = falling;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_visuals(
+    mut commands: Commands,
+    players: Res<Players>,
+    mut views: ResMut<PlayerViews>,
+    mode: Res<GameMode>,
+    settings: Res<settings::Settings>,
+    palette: Res<Palette>,
+    motions: Res<GarbageMotions>,
+    mut sprite_query: Query<&mut Sprite>,
+    mut transform_query: Query<&mut Transform>,
+    mut diagnostics: Diagnostics,
+) {
+    let _span = info_span!("visuals").entered();
+    let started = Instant::now();
+    update_player_visuals(
+        &mut commands,
+        &players.p1,
+        &mut views.p1,
+        &motions.p1,
+        settings.reduced_motion,
+        &palette,
+        &mut sprite_query,
+        &mut transform_query,
+    );
+    if *mode == GameMode::TwoPlayer
+        && let Some(p2_view) = &mut views.p2
+    {
+        update_player_visuals(
+            &mut commands,
+            &players.p2,
+            p2_view,
+            &motions.p2,
+            settings.reduced_motion,
+            &palette,
+            &mut sprite_query,
+            &mut transform_query,
+        );
+    }
+    diagnostics.add_measurement(&profiling::VISUALS, || profiling::millis_since(started));
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_player_visuals(
+    commands: &mut Commands,
+    player: &PlayerState,
+    view: &mut PlayerView,
+    garbage_motion: &GarbageMotion,
+    reduced_motion: bool,
+    palette: &Palette,
+    sprite_query: &mut Query<&mut Sprite>,
+    transform_query: &mut Query<&mut Transform>,
+) {
+    let fall_offset = (1.0 - player.gravity_timer.fraction()) * CELL_SIZE;
+    for y in 0..player.grid.height {
+        for x in 0..player.grid.width {
+            let idx = y * player.grid.width + x;
+            let reveal = player.grid.reveal_steps(x, y);
+            let flip = if reveal == 1 && !reduced_motion {
+                (player.gravity_timer.fraction() * std::f32::consts::PI).cos()
+            } else {
+                1.0
+            };
+            let look = player
+                .pending_clears
+                .iter()
+                .find_map(|pending| pending.look(idx));
+            let Some(block) = player
+                .grid
+                .get(x, y)
+                .filter(|_| look != Some(ClearLook::Popped))
+            else {
+                if let Some(sprite) = view.blocks.release(idx) {
+                    sprite.hide(sprite_query);
+                }
+                continue;
+            };
+            let Some(sprite) = view.blocks.acquire(commands, idx) else {
+                continue;
+            };
+            let color = match (block, look) {
+                (Block::Normal { .. }, _) if reveal > 1 || (reveal == 1 && flip > 0.0) => {
+                    palette.garbage_cracked
+                }
+                (_, Some(ClearLook::Flash { .. })) if reduced_motion => {
+                    palette.cell(block).mix(&Color::WHITE, CLEAR_STEADY_TINT)
+                }
+                (_, Some(ClearLook::Flash { lit: true })) => {
+                    palette.cell(block).mix(&Color::WHITE, CLEAR_FLASH_TINT)
+                }
+                (_, Some(ClearLook::Waiting)) => palette
+                    .cell(block)
+                    .mix(&palette.background, CLEAR_FACE_SHADE),
+                _ => palette.cell(block),
+            };
+            let block_rect =
+                Rect::from_center_size(Vec2::ZERO, Vec2::splat(CELL_SIZE - BLOCK_INSET));
+            let (fill, edge) = match block {
+                block if block.is_garbage() => slab_rects(&player.grid, x, y),
+                block if matches!(block, Block::Bomb | Block::Item { .. }) || block.is_iced() => {
+                    (block_rect, block_rect.inflate(BLOCK_INSET / 2.0))
+                }
+                _ => (block_rect, block_rect),
+            };
+            let (fill, edge) = if view.mirrored {
+                (mirror_rect(fill), mirror_rect(edge))
+            } else {
+                (fill, edge)
+            };
+            let lift = if player.holding && player.cursor.x == x && player.cursor.y == y {
+                GRAB_LIFT_SCALE
+            } else {
+                1.0
+            };
+            if let Ok(mut sprite) = sprite_query.get_mut(sprite.fill) {
+                sprite.color = color;
+                sprite.custom_size = Some(fill.size() * Vec2::new(flip.abs(), 1.0) * lift);
+            }
+            if let Ok(mut transform) = transform_query.get_mut(sprite.fill) {
+                let mut pos = cell_center(&player.grid, x, y, view.origin, view.mirrored)
+                    + fill.center().extend(0.0);
+                let mut squash = 0.0;
+                if !reduced_motion {
+                    if player.grid.just_fell(x, y) {
+                        pos.y += fall_offset;
+                    }
+                    pos.y += garbage_motion.drop_offset(idx);
+                    squash = garbage_motion.squash(idx);
+                }
+                pos.y -= squash * CELL_SIZE / 2.0;
+                transform.translation = pos;
+                transform.scale = Vec3::new(1.0 + squash / 2.0, 1.0 - squash, 1.0);
+            }
+            if let Ok(mut sprite) = sprite_query.get_mut(sprite.edge) {
+                sprite.color = match block {
+                    block if block.is_garbage() => palette.garbage_edge,
+                    Block::Bomb => palette.bomb_fuse,
+                    Block::Item { .. } => palette.highlight,
+                    block if block.is_iced() => palette.ice,
+                    _ => Color::srgba(0.0, 0.0, 0.0, 0.0),
+                };
+                sprite.custom_size = Some(edge.size());
+            }
+            if let Ok(mut transform) = transform_query.get_mut(sprite.edge) {
+                transform.translation = (edge.center() - fill.center()).extend(-0.1);
+            }
+        }
+    }
+
+    for (entity, block) in view.next_row.iter().zip(player.grid.next_row()) {
+        if let Ok(mut sprite) = sprite_query.get_mut(*entity) {
+            sprite.color = palette.cell(*block).with_alpha(NEXT_ROW_ALPHA);
+        }
+    }
+
+    let cell = cell_center(
+        &player.grid,
+        player.cursor.x,
+        player.cursor.y,
+        view.origin,
+        view.mirrored,
+    );
+    let transform_to = if player.grab_cursor {
+        Transform::from_translation(cell + Vec3::Z)
+    } else if player.cursor_vertical {
+        Transform::from_translation(cell + Vec3::new(0.0, CELL_SIZE / 2.0, 1.0))
+            .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2))
+    } else {
+        Transform::from_translation(cursor_center(
+            &player.grid,
+            player.cursor.x,
+            player.cursor.y,
+            view.origin,
+            view.mirrored,
+        ))
+    };
+    if let Ok(mut transform) = transform_query.get_mut(view.cursor) {
+        *transform = transform_to;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_danger_visuals(
+    time: Res<Time>,
+    players: Res<Players>,
+    views: Res<PlayerViews>,
+    mode: Res<GameMode>,
+    settings: Res<settings::Settings>,
+    palette: Res<Palette>,
+    mut sprite_query: Query<&mut Sprite>,
+    mut transform_query: Query<&mut Transform>,
+    mut vis_query: Query<&mut Visibility>,
+) {
+    let t = time.elapsed_seconds();
+    for id in active_players(*mode) {
+        let (player, Some(view)) = (players.get(*id), player_view(&views, *id)) else {
+            continue;
+        };
+        let danger = stack_in_danger(&player.grid);
+        let tint = match (danger, settings.reduced_motion) {
+            (false, _) => 0.0,
+            (true, true) => 0.7,
+            (true, false) => 0.5 + 0.3 * (t * DANGER_PULSE_RATE).sin(),
+        };
+        for entity in view.frame {
+            if let Ok(mut sprite) = sprite_query.get_mut(entity) {
+                sprite.color = palette.frame.mix(&palette.danger, tint);
+            }
+        }
+        if let Ok(mut visibility) = vis_query.get_mut(view.ui.danger) {
+            *visibility = if danger {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            };
+        }
+        if !danger || settings.reduced_motion {
+            continue;
+        }
+        let grid = &player.grid;
+        for y in grid.height.saturating_sub(DANGER_ROWS + 1)..grid.height {
+            for x in 0..grid.width {
+                let Some(sprite) = view.blocks.get(y * grid.width + x) else {
+                    continue;
+                };
+                if let Ok(mut transform) = transform_query.get_mut(sprite.fill) {
+                    transform.translation.x +=
+                        (t * DANGER_WOBBLE_RATE + x as f32).sin() * DANGER_WOBBLE_PX;
+                }
+            }
+        }
+    }
+}
+
+// Garbage cells reach into neighbouring garbage so a slab reads as one piece, with
+// its border only along the outside. Returns the fill and border rects around the
+// cell centre.
+fn slab_rects(grid: &Grid, x: usize, y: usize) -> (Rect, Rect) {
+    let joined = |dx: isize, dy: isize| {
+        let (Some(nx), Some(ny)) = (x.checked_add_signed(dx), y.checked_add_signed(dy)) else {
+            return false;
+        };
+        grid.in_bounds(nx, ny) && grid.get(nx, ny).is_some_and(Block::is_garbage)
+    };
+    let outer = CELL_SIZE / 2.0;
+    let inner = outer - BLOCK_INSET / 2.0;
+    let reach = |dx, dy, inset| {
+        if joined(dx, dy) { outer } else { inner - inset }
+    };
+    let rect = |inset| {
+        Rect::new(
+            -reach(-1, 0, inset),
+            -reach(0, -1, inset),
+            reach(1, 0, inset),
+            reach(0, 1, inset),
+        )
+    };
+    (rect(GARBAGE_BORDER), rect(0.0))
+}
+
+fn next_row_center(grid: &Grid, x: usize, origin: Vec2, mirrored: bool) -> Vec3 {
+    cell_center(grid, x, 0, origin, mirrored)
+        - Vec3::new(0.0, CELL_SIZE + FRAME_THICKNESS * 2.0, 0.0)
+}
+
+fn cell_center(grid: &Grid, x: usize, y: usize, origin: Vec2, mirrored: bool) -> Vec3 {
+    let x = if mirrored { grid.width - 1 - x } else { x };
+    let origin_x = -((grid.width as f32) * CELL_SIZE) / 2.0 + CELL_SIZE / 2.0 + origin.x;
+    let origin_y = -((grid.height as f32) * CELL_SIZE) / 2.0 + CELL_SIZE / 2.0 + origin.y;
+    Vec3::new(
+        origin_x + x as f32 * CELL_SIZE,
+        origin_y + y as f32 * CELL_SIZE,
+        0.0,
+    )
+}
+
+fn cursor_center(grid: &Grid, x: usize, y: usize, origin: Vec2, mirrored: bool) -> Vec3 {
+    let x = if mirrored {
+        grid.width.saturating_sub(x + 2)
+    } else {
+        x
+    };
+    let origin_x = -((grid.width as f32) * CELL_SIZE) / 2.0 + CELL_SIZE + origin.x;
+    let origin_y = -((grid.height as f32) * CELL_SIZE) / 2.0 + CELL_SIZE / 2.0 + origin.y;
+    Vec3::new(
+        origin_x + x as f32 * CELL_SIZE,
+        origin_y + y as f32 * CELL_SIZE,
+        1.0,
+    )
+}
+
+fn mirror_rect(rect: Rect) -> Rect {
+    Rect::new(-rect.max.x, rect.min.y, -rect.min.x, rect.max.y)
+}
+
+fn player_view(views: &PlayerViews, player: PlayerId) -> Option<&PlayerView> {
+    match player {
+        PlayerId::P1 => Some(&views.p1),
+        PlayerId::P2 => views.p2.as_ref(),
+    }
+}
+
+fn spawn_score_popups(
+    mut commands: Commands,
+    mut clear_events: EventReader<ClearEvent>,
+    players: Res<Players>,
+    views: Res<PlayerViews>,
+    palette: Res<Palette>,
+) {
+    for event in clear_events.read() {
+        let Some(view) = player_view(&views, event.player) else {
+            continue;
+        };
+        let grid = match event.player {
+            PlayerId::P1 => &players.p1.grid,
+            PlayerId::P2 => &players.p2.grid,
+        };
+        let color = if event.score.chain > 0 {
+            palette.popup_chain
+        } else if event.score.combo > 0 {
+            palette.popup_combo
+        } else {
+            palette.popup_plain
+        };
+        let mut centroid = event.centroid;
+        if view.mirrored {
+            centroid.x = grid.width as f32 - 1.0 - centroid.x;
+        }
+        let start =
+            cell_center(grid, 0, 0, view.origin, false) + (centroid * CELL_SIZE).extend(5.0);
+        spawn_floating_text(
+            &mut commands,
+            format!("+{}", event.score.total()),
+            start,
+            color,
+            20.0,
+            SCORE_POPUP_SECONDS,
+        );
+    }
+}
+
+fn spawn_cancel_popups(
+    mut commands: Commands,
+    mut cancel_events: EventReader<GarbageCancelEvent>,
+    views: Res<PlayerViews>,
+    palette: Res<Palette>,
+    locale: Res<Locale>,
+) {
+    let grid_h = GRID_H as f32 * CELL_SIZE;
+    for event in cancel_events.read() {
+        for view in std::iter::once(&views.p1).chain(views.p2.as_ref()) {
+            let start = Vec3::new(
+                view.origin.x,
+                view.origin.y + grid_h / 2.0 - CELL_SIZE * 2.0,
+                5.0,
+            );
+            spawn_floating_text(
+                &mut commands,
+                locale.format("popup.cancel", &[("units", &event.units)]),
+                start,
+                palette.popup_cancel,
+                26.0,
+                CANCEL_POPUP_SECONDS,
+            );
+        }
+    }
+}
+
+fn spawn_counter_popups(
+    mut commands: Commands,
+    mut counter_events: EventReader<GarbageCounterEvent>,
+    views: Res<PlayerViews>,
+    palette: Res<Palette>,
+    locale: Res<Locale>,
+) {
+    let grid_h = GRID_H as f32 * CELL_SIZE;
+    for event in counter_events.read() {
+        let Some(view) = player_view(&views, event.player) else {
+            continue;
+        };
+        let start = Vec3::new(
+            view.origin.x,
+            view.origin.y + grid_h / 2.0 - CELL_SIZE * 3.0,
+            5.0,
+        );
+        spawn_floating_text(
+            &mut commands,
+            locale.format("popup.counter", &[("units", &event.units)]),
+            start,
+            palette.popup_counter,
+            30.0,
+            CANCEL_POPUP_SECONDS,
+        );
+    }
+}
+
+fn spawn_level_up_popups(
+    mut commands: Commands,
+    mut level_events: EventReader<LevelUpEvent>,
+    views: Res<PlayerViews>,
+    palette: Res<Palette>,
+    locale: Res<Locale>,
+) {
+    for event in level_events.read() {
+        let Some(view) = player_view(&views, event.player) else {
+            continue;
+        };
+        spawn_floating_text(
+            &mut commands,
+            locale.format("popup.level_up", &[("level", &event.level)]),
+            view.origin.extend(5.0),
+            palette.popup_chain,
+            34.0,
+            CANCEL_POPUP_SECONDS,
+        );
+    }
+}
+
+fn spawn_item_popups(
+    mut commands: Commands,
+    mut item_events: EventReader<ItemEvent>,
+    views: Res<PlayerViews>,
+    palette: Res<Palette>,
+    locale: Res<Locale>,
+) {
+    let grid_h = GRID_H as f32 * CELL_SIZE;
+    for event in item_events.read() {
+        let Some(view) = player_view(&views, event.player) else {
+            continue;
+        };
+        let item = locale.get(items::label_key(event.item));
+        let (value, start, color) = if event.landed {
+            (
+                locale.format("popup.item", &[("item", &item)]),
+                view.origin.extend(5.0),
+                palette.popup_counter,
+            )
+        } else {
+            (
+                locale.format("popup.item_incoming", &[("item", &item)]),
+                Vec3::new(
+                    view.origin.x,
+                    view.origin.y + grid_h / 2.0 - CELL_SIZE * 2.0,
+                    5.0,
+                ),
+                palette.warning,
+            )
+        };
+        spawn_floating_text(
+            &mut commands,
+            value,
+            start,
+            color,
+            28.0,
+            CANCEL_POPUP_SECONDS,
+        );
+    }
+}
+
+fn spawn_floating_text(
+    commands: &mut Commands,
+    value: String,
+    start: Vec3,
+    color: Color,
+    font_size: f32,
+    seconds: f32,
+) {
+    commands
+        .spawn(Text2dBundle {
+            text: Text::from_section(
+                value,
+                TextStyle {
+                    font: Default::default(),
+                    font_size,
+                    color,
+                },
+            ),
+            transform: Transform::from_translation(start),
+            ..Default::default()
+        })
+        .insert(FloatingText {
+            timer: Timer::from_seconds(seconds, TimerMode::Once),
+            start,
+            color,
+        })
+        .insert(GameEntity);
+}
+
+fn update_floating_texts(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<settings::Settings>,
+    mut texts: Query<(Entity, &mut FloatingText, &mut Transform, &mut Text)>,
+) {
+    let rise = if settings.reduced_motion {
+        0.0
+    } else {
+        FLOATING_TEXT_RISE
+    };
+    for (entity, mut floating, mut transform, mut text) in &mut texts {
+        floating.timer.tick(time.delta());
+        if floating.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let t = floating.timer.fraction();
+        transform.translation = floating.start + Vec3::new(0.0, rise * t, 0.0);
+        text.sections[0].style.color = floating.color.with_alpha(1.0 - t);
+    }
+}
+
+fn spawn_observer_hud(commands: &mut Commands, palette: &Palette) -> ObserverHud {
+    let root = commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(0.0),
+                top: Val::Px(12.0),
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::SpaceEvenly,
+                ..Default::default()
+            },
+            visibility: Visibility::Hidden,
+            ..Default::default()
+        })
+        .insert(GameEntity)
+        .id();
+
+    let style = TextStyle {
+        font: Default::default(),
+        font_size: 36.0,
+        color: palette.heading,
+    };
+    let spawn_column = |commands: &mut Commands| {
+        commands
+            .spawn(TextBundle {
+                text: Text::from_section("", style.clone()).with_justify(JustifyText::Center),
+                ..Default::default()
+            })
+            .insert(GameEntity)
+            .set_parent(root)
+            .id()
+    };
+    // Columns follow the on-screen board order: P2 plays on the left.
+    let p2 = spawn_column(commands);
+    let p1 = spawn_column(commands);
+
+    ObserverHud { root, p1, p2 }
+}
+
+fn toggle_observer_view(
+    keys: Res<ButtonInput<KeyCode>>,
+    mode: Res<GameMode>,
+    mut observer: ResMut<ObserverView>,
+) {
+    if *mode == GameMode::TwoPlayer && keys.just_pressed(KeyCode::F2) {
+        observer.enabled = !observer.enabled;
+    }
+}
+
+// With the panels gone the boards close up around the centre and the camera
+// fits just the boards, so both draw larger.
+fn apply_observer_layout(
+    observer: Res<ObserverView>,
+    mode: Res<GameMode>,
+    settings: Res<settings::Settings>,
+    mut views: ResMut<PlayerViews>,
+    mut transform_query: Query<&mut Transform>,
+) {
+    let enabled = observer.enabled && *mode == GameMode::TwoPlayer;
+    if views.observer == enabled {
+        return;
+    }
+    views.observer = enabled;
+    let layout = views.layout_mode(*mode);
+    place_views(&mut views, layout, &settings, &mut transform_query);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_observer_view(
+    players: Res<Players>,
+    views: Res<PlayerViews>,
+    mode: Res<GameMode>,
+    observer: Res<ObserverView>,
+    hud: Res<ObserverHud>,
+    locale: Res<Locale>,
+    mut text_query: Query<&mut Text>,
+    mut vis_query: Query<&mut Visibility>,
+) {
+    let enabled = observer.enabled && *mode == GameMode::TwoPlayer;
+    let hidden_when = |hide: bool| {
+        if hide {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        }
+    };
+    for view in std::iter::once(&views.p1).chain(views.p2.as_ref()) {
+        if let Ok(mut visibility) = vis_query.get_mut(view.panel) {
+            *visibility = hidden_when(enabled);
+        }
+    }
+    if let Ok(mut visibility) = vis_query.get_mut(hud.root) {
+        *visibility = hidden_when(!enabled);
+    }
+    if !enabled {
+        return;
+    }
+    for (id, entity) in [(PlayerId::P1, hud.p1), (PlayerId::P2, hud.p2)] {
+        let player = players.get(id);
+        let chain = if player.chain_index > 1 {
+            locale.format("observer.chain", &[("chain", &player.chain_index)])
+        } else {
+            "-".to_string()
+        };
+        if let Ok(mut text) = text_query.get_mut(entity) {
+            let garbage = locale.format(
+                "observer.garbage",
+                &[
+                    ("incoming", &player.garbage_incoming()),
+                    ("outgoing", &player.garbage_outgoing),
+                ],
+            );
+            text.sections[0].value =
+                format!("{}  {}\n{}\n{}", id.label(), player.score, chain, garbage);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    const TICKS: u32 = 30;
+
+    fn running_match() -> App {
+        let mut app = App::new();
+        add_simulation(&mut app);
+        let world = app.world_mut();
+        world.insert_resource(MatchSeed(Some(7)));
+        world.run_system_once(|mut control: MatchControl| control.start(GameMode::OnePlayer));
+        app
+    }
+
+    fn step(app: &mut App, ticks: u32) {
+        for _ in 0..ticks {
+            step_simulation(app.world_mut());
+        }
+    }
+
+    fn tick() -> Duration {
+        SimClock::default().delta
+    }
+
+    #[test]
+    fn match_clock_follows_simulation_ticks() {
+        let mut app = running_match();
+        step(&mut app, TICKS);
+        let world = app.world();
+        assert_eq!(world.resource::<MatchClock>().elapsed, tick() * TICKS);
+        let elapsed = world.resource::<Players>().p1.elapsed;
+        assert!((elapsed - (tick() * TICKS).as_secs_f32()).abs() < 1e-4);
+    }
+
+    #[test]
+    fn match_over_freezes_gameplay_timers() {
+        let mut app = running_match();
+        step(&mut app, TICKS);
+        app.world_mut().resource_mut::<MatchOver>().active = true;
+        let clock = app.world().resource::<MatchClock>().elapsed;
+        let player = &app.world().resource::<Players>().p1;
+        let (elapsed, rise) = (player.elapsed, player.rise_timer.elapsed());
+
+        step(&mut app, TICKS);
+        let world = app.world();
+        assert_eq!(world.resource::<MatchClock>().elapsed, clock);
+        assert_eq!(world.resource::<MatchClock>().delta, Duration::ZERO);
+        let player = &world.resource::<Players>().p1;
+        assert_eq!(player.elapsed, elapsed);
+        assert_eq!(player.rise_timer.elapsed(), rise);
+    }
+
+    #[test]
+    fn pause_freezes_match_clock_until_resumed() {
+        let mut app = running_match();
+        app.world_mut().insert_resource(State::new(AppState::Pause));
+        step(&mut app, TICKS);
+        assert_eq!(app.world().resource::<MatchClock>().elapsed, Duration::ZERO);
+        assert_eq!(app.world().resource::<Players>().p1.elapsed, 0.0);
+
+        app.world_mut().insert_resource(State::new(AppState::Game));
+        step(&mut app, TICKS);
+        assert_eq!(app.world().resource::<MatchClock>().elapsed, tick() * TICKS);
+    }
+
+    #[test]
+    fn rise_due_during_a_stop_waits_for_it() {
+        let mut app = running_match();
+        let mut players = app.world_mut().resource_mut::<Players>();
+        let player = &mut players.p1;
+        let rows = player.rows_at_level;
+        let nearly = player.rise_timer.remaining() - tick();
+        rise_player(nearly, player, &Tuning::default());
+        player.item_freeze = 1.0;
+        rise_player(tick() * 2, player, &Tuning::default());
+        assert_eq!(player.rows_at_level, rows);
+
+        player.item_freeze = 0.0;
+        rise_player(tick() * 2, player, &Tuning::default());
+        assert_eq!(player.rows_at_level, rows + 1);
+    }
+
+    #[test]
+    fn top_out_grace_ignores_rise_speed() {
+        let mut app = running_match();
+        let mut players = app.world_mut().resource_mut::<Players>();
+        let player = &mut players.p1;
+        let top = player.grid.height - 1;
+        player.grid.set(0, top, Block::from_symbol('R'));
+        player.rise_scale = 4.0;
+        player.speed_burst = 1.0;
+        player.topped_seconds = f32::EPSILON;
+        rise_player(tick(), player, &Tuning::default());
+        assert!((player.topped_seconds - tick().as_secs_f32()).abs() < 1e-4);
+    }
+
+    #[test]
+    fn resizing_mid_clear_drops_the_clear_and_ends_the_chain() {
+        let mut app = running_match();
+        let mut players = app.world_mut().resource_mut::<Players>();
+        let player = &mut players.p1;
+        player.grid.load_rows(&["RRRGB"]);
+        player.chain_index = 1;
+        assert!(queue_clear(player));
+
+        assert!(resize_board(player, 5, 8));
+        assert!(player.pending_clears.is_empty());
+        assert_eq!(player.phase, PlayerPhase::Idle);
+        assert_eq!((player.chain_index, player.chain_ended), (0, true));
+        assert!(!player.grid.has_reservations());
+        assert!(queue_clear(player));
+    }
+
+    #[test]
+    fn starting_a_match_resets_match_clock() {
+        let mut app = running_match();
+        step(&mut app, TICKS);
+        app.world_mut()
+            .run_system_once(|mut control: MatchControl| control.start(GameMode::OnePlayer));
+        assert_eq!(app.world().resource::<MatchClock>().elapsed, Duration::ZERO);
+    }
+}
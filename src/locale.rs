@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    English,
+    German,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::German];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Language::English => "ENGLISH",
+            Language::German => "DEUTSCH",
+        }
+    }
+
+    fn source(self) -> &'static str {
+        match self {
+            Language::English => include_str!("../assets/locale/en.json"),
+            Language::German => include_str!("../assets/locale/de.json"),
+        }
+    }
+
+    fn strings(self) -> HashMap<String, String> {
+        serde_json::from_str(self.source()).unwrap_or_else(|err| {
+            warn!("ignoring broken {} string table: {err}", self.label());
+            HashMap::new()
+        })
+    }
+}
+
+#[derive(Resource, Clone, Debug)]
+pub struct Locale {
+    strings: HashMap<String, String>,
+    fallback: HashMap<String, String>,
+}
+
+impl Locale {
+    pub fn new(language: Language) -> Self {
+        Self {
+            strings: language.strings(),
+            fallback: Language::English.strings(),
+        }
+    }
+
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.strings
+            .get(key)
+            .or_else(|| self.fallback.get(key))
+            .map(String::as_str)
+            .unwrap_or(key)
+    }
+
+    pub fn format(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        args.iter()
+            .fold(self.get(key).to_string(), |text, (name, value)| {
+                text.replace(&format!("{{{name}}}"), &value.to_string())
+            })
+    }
+}
@@ -0,0 +1,50 @@
+use bevy::prelude::*;
+
+use crate::{
+    GameMode, LevelUpEvent, MatchOver, MatchOverTimer, MatchRules, PlayerId, Players, SoloMode,
+    set_rise_level, tuning::Tuning,
+};
+
+pub const BLOCKS_PER_LEVEL: u32 = 10;
+pub const GOAL_LEVEL: u32 = 99;
+
+pub fn blocks_to_next_level(blocks_cleared: u32) -> u32 {
+    BLOCKS_PER_LEVEL - blocks_cleared % BLOCKS_PER_LEVEL
+}
+
+pub fn fastest_level(splits: &[f32]) -> Option<f32> {
+    splits
+        .windows(2)
+        .map(|pair| pair[1] - pair[0])
+        .min_by(f32::total_cmp)
+}
+
+pub fn advance_marathon_level(
+    mut players: ResMut<Players>,
+    mut match_over: ResMut<MatchOver>,
+    mut match_over_timer: ResMut<MatchOverTimer>,
+    rules: Res<MatchRules>,
+    mode: Res<GameMode>,
+    tuning: Res<Tuning>,
+    mut level_events: EventWriter<LevelUpEvent>,
+) {
+    if match_over.active || *mode != GameMode::OnePlayer || rules.solo != SoloMode::Marathon {
+        return;
+    }
+    let player = &mut players.p1;
+    let level = (player.start_level + player.blocks_cleared / BLOCKS_PER_LEVEL).min(GOAL_LEVEL);
+    if level <= player.rise_level {
+        return;
+    }
+    set_rise_level(player, level, &tuning);
+    player.level_splits.push(player.elapsed);
+    level_events.send(LevelUpEvent {
+        player: PlayerId::P1,
+        level,
+    });
+    if level >= GOAL_LEVEL {
+        match_over.active = true;
+        match_over.winner = Some(PlayerId::P1);
+        match_over_timer.seconds = 0.0;
+    }
+}
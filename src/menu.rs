@@ -0,0 +1,236 @@
+use std::marker::PhantomData;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+use crate::bindings::{MenuAction, default_layouts, menu_keys};
+use crate::palette::Palette;
+use crate::settings::alt_held;
+
+const MENU_FONT_SIZE: f32 = 28.0;
+
+pub struct MenuPlugin<T>(PhantomData<T>);
+
+impl<T> Default for MenuPlugin<T> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T: MenuId> Plugin for MenuPlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.add_event::<MenuEvent<T>>().add_systems(
+            Update,
+            (navigate_menus::<T>, render_menus::<T>)
+                .chain()
+                .in_set(MenuSet),
+        );
+    }
+}
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MenuSet;
+
+pub trait MenuId: Copy + Eq + Send + Sync + 'static {}
+
+impl<T: Copy + Eq + Send + Sync + 'static> MenuId for T {}
+
+#[derive(Clone, Debug)]
+pub struct MenuEntry<T> {
+    pub id: T,
+    pub label: String,
+    pub enabled: bool,
+    children: Vec<MenuEntry<T>>,
+}
+
+impl<T> MenuEntry<T> {
+    pub fn new(id: T, label: impl Into<String>) -> Self {
+        Self {
+            id,
+            label: label.into(),
+            enabled: true,
+            children: Vec::new(),
+        }
+    }
+
+    pub fn with_children(mut self, children: Vec<MenuEntry<T>>) -> Self {
+        self.children = children;
+        self
+    }
+}
+
+#[derive(Component)]
+pub struct Menu<T> {
+    entries: Vec<MenuEntry<T>>,
+    cursor: usize,
+    parents: Vec<(Vec<MenuEntry<T>>, usize)>,
+}
+
+impl<T: MenuId> Menu<T> {
+    pub fn new(entries: Vec<MenuEntry<T>>) -> Self {
+        Self {
+            entries,
+            cursor: 0,
+            parents: Vec::new(),
+        }
+    }
+
+    pub fn entries(&self) -> &[MenuEntry<T>] {
+        &self.entries
+    }
+
+    pub fn entries_mut(&mut self) -> &mut [MenuEntry<T>] {
+        &mut self.entries
+    }
+
+    pub fn selected(&self) -> Option<&MenuEntry<T>> {
+        self.entries.get(self.cursor)
+    }
+
+    fn step(&mut self, step: isize) {
+        let count = self.entries.len() as isize;
+        if count > 0 {
+            self.cursor = (self.cursor as isize + step).rem_euclid(count) as usize;
+        }
+    }
+
+    fn open_selected(&mut self) -> bool {
+        let Some(entry) = self.entries.get_mut(self.cursor) else {
+            return false;
+        };
+        if entry.children.is_empty() {
+            return false;
+        }
+        let children = std::mem::take(&mut entry.children);
+        let entries = std::mem::replace(&mut self.entries, children);
+        self.parents.push((entries, self.cursor));
+        self.cursor = 0;
+        true
+    }
+
+    fn close(&mut self) -> bool {
+        let Some((mut entries, cursor)) = self.parents.pop() else {
+            return false;
+        };
+        entries[cursor].children = std::mem::take(&mut self.entries);
+        self.entries = entries;
+        self.cursor = cursor;
+        true
+    }
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub enum MenuEvent<T> {
+    Confirm(T),
+    Adjust(T, isize),
+    Cancel,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MenuNav {
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+    pub confirm: bool,
+    pub cancel: bool,
+}
+
+#[derive(SystemParam)]
+pub struct MenuInput<'w> {
+    keys: Res<'w, ButtonInput<KeyCode>>,
+    buttons: Res<'w, ButtonInput<GamepadButton>>,
+    gamepads: Res<'w, Gamepads>,
+}
+
+impl MenuInput<'_> {
+    pub fn pressed(&self, keys: &[KeyCode], buttons: &[GamepadButtonType]) -> bool {
+        keys.iter().any(|key| self.keys.just_pressed(*key))
+            || self.gamepads.iter().any(|gamepad| {
+                buttons.iter().any(|button| {
+                    self.buttons
+                        .just_pressed(GamepadButton::new(gamepad, *button))
+                })
+            })
+    }
+
+    pub fn any_pressed(&self) -> bool {
+        self.keys.get_just_pressed().next().is_some()
+            || self.buttons.get_just_pressed().next().is_some()
+    }
+
+    pub fn nav(&self) -> MenuNav {
+        use GamepadButtonType as Pad;
+        let layouts = default_layouts();
+        let action = |action, buttons: &[Pad]| self.pressed(&menu_keys(&layouts, action), buttons);
+        MenuNav {
+            up: action(MenuAction::Up, &[Pad::DPadUp]),
+            down: action(MenuAction::Down, &[Pad::DPadDown]),
+            left: action(MenuAction::Left, &[Pad::DPadLeft]),
+            right: action(MenuAction::Right, &[Pad::DPadRight]),
+            // Alt+Enter is fullscreen, never a menu confirm.
+            confirm: action(MenuAction::Confirm, &[Pad::South, Pad::Start])
+                && !(self.keys.just_pressed(KeyCode::Enter) && alt_held(&self.keys)),
+            cancel: action(MenuAction::Cancel, &[Pad::East]),
+        }
+    }
+}
+
+fn navigate_menus<T: MenuId>(
+    input: MenuInput,
+    mut menus: Query<&mut Menu<T>>,
+    mut events: EventWriter<MenuEvent<T>>,
+) {
+    let nav = input.nav();
+    for mut menu in &mut menus {
+        if nav.up {
+            menu.step(-1);
+        }
+        if nav.down {
+            menu.step(1);
+        }
+        let Some(entry) = menu.selected() else {
+            continue;
+        };
+        let (id, enabled) = (entry.id, entry.enabled);
+        if nav.left {
+            events.send(MenuEvent::Adjust(id, -1));
+        }
+        if nav.right {
+            events.send(MenuEvent::Adjust(id, 1));
+        }
+        if nav.confirm && !menu.open_selected() && enabled {
+            events.send(MenuEvent::Confirm(id));
+        } else if nav.cancel && !menu.close() {
+            events.send(MenuEvent::Cancel);
+        }
+    }
+}
+
+fn render_menus<T: MenuId>(palette: Res<Palette>, mut menus: Query<(Ref<Menu<T>>, &mut Text)>) {
+    for (menu, mut text) in &mut menus {
+        if !menu.is_changed() && !palette.is_changed() {
+            continue;
+        }
+        text.sections = menu
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| {
+                let color = if entry.enabled {
+                    palette.menu_item(index == menu.cursor)
+                } else {
+                    palette.disabled
+                };
+                TextSection::new(
+                    format!("{}\n", entry.label),
+                    TextStyle {
+                        font: Default::default(),
+                        font_size: MENU_FONT_SIZE,
+                        color,
+                    },
+                )
+            })
+            .collect();
+    }
+}
@@ -0,0 +1,833 @@
+use std::io::{ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, UdpSocket};
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use rand::random;
+use serde::{Deserialize, Serialize};
+
+use crate::locale::Locale;
+use crate::menu::MenuInput;
+use crate::palette::Palette;
+use crate::profiles::Profiles;
+use crate::rating::{MatchOutcome, PlayerRating};
+use crate::tuning::LatestTuning;
+use crate::{
+    AppState, CommandQueue, GameMode, GameSet, LocalControl, MatchOver, MatchSeed, PlayerCommand,
+    PlayerId, SimClock, handle_input,
+};
+
+const PROTOCOL_VERSION: u32 = 3;
+const BEACON_PREFIX: &str = "TETANUS_ATTACK v2";
+const DISCOVERY_PORT: u16 = 47777;
+const GAME_PORT: u16 = 47778;
+const BEACON_SECONDS: f32 = 1.0;
+const HOST_TIMEOUT: Duration = Duration::from_secs(3);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+const INPUT_DELAY_FRAMES: u64 = 3;
+const MATCH_FOUND_SECONDS: f32 = 2.5;
+const HOST_ENTRIES: usize = 2;
+
+pub struct NetplayPlugin;
+
+impl Plugin for NetplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Lobby), setup_lobby)
+            .add_systems(OnExit(AppState::Lobby), cleanup_lobby)
+            .add_systems(OnEnter(AppState::Title), end_session)
+            .add_systems(
+                Update,
+                (
+                    discover_hosts,
+                    handle_lobby_input,
+                    advance_lobby_connection,
+                    refresh_lobby_text,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Lobby)),
+            )
+            .add_systems(
+                Update,
+                pump_session
+                    .after(handle_input)
+                    .in_set(GameSet::Input)
+                    .run_if(resource_exists::<NetSession>),
+            )
+            .add_systems(
+                Update,
+                record_ranked_result
+                    .in_set(GameSet::GarbageResolve)
+                    .run_if(resource_exists::<NetSession>),
+            )
+            .add_systems(
+                Last,
+                forfeit_on_exit
+                    .run_if(on_event::<AppExit>())
+                    .run_if(resource_exists::<NetSession>),
+            );
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum NetMessage {
+    Hello {
+        version: u32,
+        rating: i32,
+    },
+    // `tuning` is the host's tuning fingerprint; both sides snapshot their own
+    // table when the match starts, so they have to agree on it up front.
+    Start {
+        seed: u64,
+        ranked: bool,
+        tuning: u64,
+    },
+    Input {
+        frame: u64,
+        commands: Vec<PlayerCommand>,
+    },
+    Bye,
+}
+
+// Only a Bye or the peer's end of the stream means they left; anything that
+// fails on our side is our own drop and never hands them the loss.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Closed {
+    ByPeer,
+    LocalError,
+}
+
+struct Connection {
+    stream: TcpStream,
+    inbound: Vec<u8>,
+    outbound: Vec<u8>,
+    closed: Option<Closed>,
+}
+
+impl Connection {
+    fn new(stream: TcpStream) -> std::io::Result<Self> {
+        stream.set_nonblocking(true)?;
+        stream.set_nodelay(true)?;
+        Ok(Self {
+            stream,
+            inbound: Vec::new(),
+            outbound: Vec::new(),
+            closed: None,
+        })
+    }
+
+    fn send(&mut self, message: &NetMessage) {
+        let Ok(payload) = serde_json::to_vec(message) else {
+            return;
+        };
+        self.outbound.extend_from_slice(&payload);
+        self.outbound.push(b'\n');
+        self.flush();
+    }
+
+    fn flush(&mut self) {
+        while !self.outbound.is_empty() && self.closed.is_none() {
+            match self.stream.write(&self.outbound) {
+                Ok(0) => self.closed = Some(Closed::LocalError),
+                Ok(written) => {
+                    self.outbound.drain(..written);
+                }
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                Err(_) => self.closed = Some(Closed::LocalError),
+            }
+        }
+    }
+
+    fn receive(&mut self) -> Vec<NetMessage> {
+        let mut buf = [0u8; 4096];
+        while self.closed.is_none() {
+            match self.stream.read(&mut buf) {
+                Ok(0) => self.closed = Some(Closed::ByPeer),
+                Ok(read) => self.inbound.extend_from_slice(&buf[..read]),
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                Err(_) => self.closed = Some(Closed::LocalError),
+            }
+        }
+        self.flush();
+
+        let mut messages = Vec::new();
+        while let Some(end) = self.inbound.iter().position(|byte| *byte == b'\n') {
+            let line: Vec<u8> = self.inbound.drain(..=end).collect();
+            match serde_json::from_slice(&line[..end]) {
+                Ok(message) => messages.push(message),
+                Err(err) => warn!("dropping malformed netplay message: {err}"),
+            }
+        }
+        messages
+    }
+}
+
+#[derive(Resource)]
+struct NetSession {
+    connection: Connection,
+    local: PlayerId,
+    remote: PlayerId,
+    sealed_through: Option<u64>,
+    remote_through: Option<u64>,
+    ranked: bool,
+    opponent_rating: i32,
+    recorded: bool,
+}
+
+impl NetSession {
+    fn new(found: MatchFound) -> Self {
+        let remote = match found.local {
+            PlayerId::P1 => PlayerId::P2,
+            PlayerId::P2 => PlayerId::P1,
+        };
+        Self {
+            connection: found.connection,
+            local: found.local,
+            remote,
+            sealed_through: None,
+            remote_through: None,
+            ranked: found.ranked,
+            opponent_rating: found.opponent_rating,
+            recorded: false,
+        }
+    }
+}
+
+struct DiscoveredHost {
+    name: String,
+    addr: SocketAddr,
+    ranked: bool,
+    last_seen: Instant,
+}
+
+struct MatchFound {
+    connection: Connection,
+    local: PlayerId,
+    seed: u64,
+    ranked: bool,
+    opponent_rating: i32,
+    timer: Timer,
+}
+
+enum LobbyRole {
+    Browsing,
+    Hosting {
+        listener: TcpListener,
+        beacon: UdpSocket,
+        timer: Timer,
+        ranked: bool,
+    },
+    Greeting {
+        connection: Connection,
+        hosted: Option<(u64, bool)>,
+        opponent_rating: Option<i32>,
+    },
+    MatchFound(MatchFound),
+}
+
+#[derive(Resource)]
+struct Lobby {
+    root: Entity,
+    list: Entity,
+    status: Entity,
+    discovery: Option<UdpSocket>,
+    hosts: Vec<DiscoveredHost>,
+    selection: usize,
+    role: LobbyRole,
+    message: Option<String>,
+}
+
+fn bind_discovery() -> Option<UdpSocket> {
+    let socket = match UdpSocket::bind(("0.0.0.0", DISCOVERY_PORT)) {
+        Ok(socket) => socket,
+        Err(err) => {
+            warn!("LAN discovery unavailable on port {DISCOVERY_PORT}: {err}");
+            return None;
+        }
+    };
+    socket.set_nonblocking(true).ok()?;
+    Some(socket)
+}
+
+fn host_name() -> String {
+    std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .or_else(|_| std::env::var("USER"))
+        .unwrap_or_else(|_| "player".to_string())
+}
+
+fn start_hosting(ranked: bool) -> std::io::Result<LobbyRole> {
+    let listener = TcpListener::bind(("0.0.0.0", GAME_PORT))?;
+    listener.set_nonblocking(true)?;
+    let beacon = UdpSocket::bind(("0.0.0.0", 0))?;
+    beacon.set_broadcast(true)?;
+    let mut timer = Timer::from_seconds(BEACON_SECONDS, TimerMode::Repeating);
+    timer.tick(timer.duration());
+    Ok(LobbyRole::Hosting {
+        listener,
+        beacon,
+        timer,
+        ranked,
+    })
+}
+
+fn greet(
+    stream: TcpStream,
+    hosted: Option<(u64, bool)>,
+    rating: &PlayerRating,
+) -> std::io::Result<LobbyRole> {
+    let mut connection = Connection::new(stream)?;
+    connection.send(&NetMessage::Hello {
+        version: PROTOCOL_VERSION,
+        rating: rating.rating,
+    });
+    Ok(LobbyRole::Greeting {
+        connection,
+        hosted,
+        opponent_rating: None,
+    })
+}
+
+fn setup_lobby(mut commands: Commands, palette: Res<Palette>, locale: Res<Locale>) {
+    let root = commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(0.0),
+                top: Val::Percent(0.0),
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(16.0),
+                ..Default::default()
+            },
+            background_color: BackgroundColor(palette.overlay),
+            ..Default::default()
+        })
+        .id();
+
+    let mut list = Entity::PLACEHOLDER;
+    let mut status = Entity::PLACEHOLDER;
+    commands.entity(root).with_children(|parent| {
+        parent.spawn(TextBundle {
+            text: Text::from_section(
+                locale.get("lobby.title"),
+                TextStyle {
+                    font: Default::default(),
+                    font_size: 42.0,
+                    color: palette.heading,
+                },
+            ),
+            ..Default::default()
+        });
+
+        list = parent
+            .spawn(TextBundle {
+                text: Text::default().with_justify(JustifyText::Center),
+                ..Default::default()
+            })
+            .id();
+
+        status = parent
+            .spawn(TextBundle {
+                text: Text::from_section(
+                    "",
+                    TextStyle {
+                        font: Default::default(),
+                        font_size: 18.0,
+                        color: palette.text,
+                    },
+                )
+                .with_justify(JustifyText::Center),
+                ..Default::default()
+            })
+            .id();
+    });
+
+    commands.insert_resource(Lobby {
+        root,
+        list,
+        status,
+        discovery: bind_discovery(),
+        hosts: Vec::new(),
+        selection: 0,
+        role: LobbyRole::Browsing,
+        message: None,
+    });
+}
+
+fn cleanup_lobby(mut commands: Commands, lobby: Res<Lobby>) {
+    commands.entity(lobby.root).despawn_recursive();
+    commands.remove_resource::<Lobby>();
+}
+
+fn discover_hosts(mut lobby: ResMut<Lobby>) {
+    let now = Instant::now();
+    let mut buf = [0u8; 256];
+    let mut beacons: Vec<(String, SocketAddr, bool)> = Vec::new();
+    if let Some(socket) = &lobby.discovery {
+        loop {
+            match socket.recv_from(&mut buf) {
+                Ok((len, from)) => {
+                    if let Some(host) = parse_beacon(&buf[..len], from) {
+                        beacons.push(host);
+                    }
+                }
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                Err(_) => break,
+            }
+        }
+    }
+    for (name, addr, ranked) in beacons {
+        match lobby.hosts.iter_mut().find(|host| host.addr == addr) {
+            Some(host) => {
+                host.name = name;
+                host.ranked = ranked;
+                host.last_seen = now;
+            }
+            None => lobby.hosts.push(DiscoveredHost {
+                name,
+                addr,
+                ranked,
+                last_seen: now,
+            }),
+        }
+    }
+    lobby
+        .hosts
+        .retain(|host| now.duration_since(host.last_seen) < HOST_TIMEOUT);
+    lobby.selection = lobby.selection.min(lobby.hosts.len() + HOST_ENTRIES - 1);
+}
+
+fn parse_beacon(payload: &[u8], from: SocketAddr) -> Option<(String, SocketAddr, bool)> {
+    let text = std::str::from_utf8(payload).ok()?;
+    let mut parts = text.strip_prefix(BEACON_PREFIX)?.trim().splitn(3, ' ');
+    let port = parts.next()?.parse().ok()?;
+    let ranked = parts.next()? == "ranked";
+    let name = parts.next().unwrap_or("host");
+    Some((name.to_string(), SocketAddr::new(from.ip(), port), ranked))
+}
+
+fn handle_lobby_input(
+    input: MenuInput,
+    mut lobby: ResMut<Lobby>,
+    rating: Res<PlayerRating>,
+    locale: Res<Locale>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let nav = input.nav();
+    if nav.cancel {
+        if matches!(lobby.role, LobbyRole::Browsing) {
+            next_state.set(AppState::Title);
+        } else {
+            lobby.role = LobbyRole::Browsing;
+            lobby.message = None;
+        }
+        return;
+    }
+    if !matches!(lobby.role, LobbyRole::Browsing) {
+        return;
+    }
+
+    let count = lobby.hosts.len() + HOST_ENTRIES;
+    if nav.up {
+        lobby.selection = (lobby.selection + count - 1) % count;
+    }
+    if nav.down {
+        lobby.selection = (lobby.selection + 1) % count;
+    }
+    if !nav.confirm {
+        return;
+    }
+    let result = match lobby.selection {
+        0 => start_hosting(false),
+        1 => start_hosting(true),
+        index => {
+            TcpStream::connect_timeout(&lobby.hosts[index - HOST_ENTRIES].addr, CONNECT_TIMEOUT)
+                .and_then(|stream| greet(stream, None, &rating))
+        }
+    };
+    match result {
+        Ok(role) => {
+            lobby.role = role;
+            lobby.message = None;
+        }
+        Err(err) => {
+            lobby.message = Some(locale.format("lobby.network_error", &[("error", &err)]));
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn advance_lobby_connection(
+    mut commands: Commands,
+    mut lobby: ResMut<Lobby>,
+    time: Res<Time>,
+    rating: Res<PlayerRating>,
+    locale: Res<Locale>,
+    latest_tuning: Res<LatestTuning>,
+    mut mode: ResMut<GameMode>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let tuning = latest_tuning.0.fingerprint();
+    let mut next_role = None;
+    let mut found = None;
+    let mut failure = None;
+    match &mut lobby.role {
+        LobbyRole::Browsing => {}
+        LobbyRole::Hosting {
+            listener,
+            beacon,
+            timer,
+            ranked,
+        } => {
+            if timer.tick(time.delta()).just_finished() {
+                let kind = if *ranked { "ranked" } else { "casual" };
+                let payload = format!("{BEACON_PREFIX} {GAME_PORT} {kind} {}", host_name());
+                let _ = beacon.send_to(payload.as_bytes(), ("255.255.255.255", DISCOVERY_PORT));
+            }
+            match listener.accept() {
+                Ok((stream, peer)) => {
+                    info!("netplay peer connected from {peer}");
+                    match greet(stream, Some((random(), *ranked)), &rating) {
+                        Ok(role) => next_role = Some(role),
+                        Err(err) => {
+                            failure =
+                                Some(locale.format("lobby.connection_failed", &[("error", &err)]));
+                        }
+                    }
+                }
+                Err(err) if err.kind() == ErrorKind::WouldBlock => {}
+                Err(err) => {
+                    failure = Some(locale.format("lobby.network_error", &[("error", &err)]));
+                }
+            }
+        }
+        LobbyRole::Greeting {
+            connection,
+            hosted,
+            opponent_rating,
+        } => {
+            let mut start = None;
+            for message in connection.receive() {
+                match message {
+                    NetMessage::Hello { version, rating } if version == PROTOCOL_VERSION => {
+                        *opponent_rating = Some(rating);
+                    }
+                    NetMessage::Hello { version, .. } => {
+                        failure = Some(
+                            locale.format("lobby.protocol_mismatch", &[("version", &version)]),
+                        );
+                    }
+                    NetMessage::Start { tuning: theirs, .. }
+                        if hosted.is_none() && theirs != tuning =>
+                    {
+                        failure = Some(locale.get("lobby.tuning_mismatch").to_string());
+                    }
+                    NetMessage::Start { seed, ranked, .. } if hosted.is_none() => {
+                        start = Some((seed, ranked));
+                    }
+                    _ => {}
+                }
+            }
+            if let (Some((seed, ranked)), Some(_)) = (*hosted, *opponent_rating) {
+                connection.send(&NetMessage::Start {
+                    seed,
+                    ranked,
+                    tuning,
+                });
+                start = Some((seed, ranked));
+            }
+            if connection.closed.is_some() {
+                failure = Some(locale.get("lobby.closed").to_string());
+            }
+            if let (Some((seed, ranked)), Some(opponent_rating), None) =
+                (start, *opponent_rating, &failure)
+            {
+                let local = if hosted.is_some() {
+                    PlayerId::P1
+                } else {
+                    PlayerId::P2
+                };
+                found = Some((local, seed, ranked, opponent_rating));
+            }
+        }
+        LobbyRole::MatchFound(found) => {
+            if !found.timer.tick(time.delta()).finished() {
+                return;
+            }
+            let LobbyRole::MatchFound(found) =
+                std::mem::replace(&mut lobby.role, LobbyRole::Browsing)
+            else {
+                return;
+            };
+            commands.insert_resource(MatchSeed(Some(found.seed)));
+            commands.insert_resource(LocalControl(Some(found.local)));
+            commands.insert_resource(NetSession::new(found));
+            *mode = GameMode::TwoPlayer;
+            next_state.set(AppState::Game);
+            return;
+        }
+    }
+
+    if let Some(message) = failure {
+        lobby.role = LobbyRole::Browsing;
+        lobby.message = Some(message);
+    } else if let Some(role) = next_role {
+        lobby.role = role;
+    } else if let Some((local, seed, ranked, opponent_rating)) = found
+        && let LobbyRole::Greeting { connection, .. } =
+            std::mem::replace(&mut lobby.role, LobbyRole::Browsing)
+    {
+        lobby.role = LobbyRole::MatchFound(MatchFound {
+            connection,
+            local,
+            seed,
+            ranked,
+            opponent_rating,
+            timer: Timer::from_seconds(MATCH_FOUND_SECONDS, TimerMode::Once),
+        });
+    }
+}
+
+fn refresh_lobby_text(
+    lobby: Res<Lobby>,
+    rating: Res<PlayerRating>,
+    palette: Res<Palette>,
+    locale: Res<Locale>,
+    mut text_query: Query<&mut Text>,
+) {
+    if let Ok(mut text) = text_query.get_mut(lobby.list) {
+        let browsing = matches!(lobby.role, LobbyRole::Browsing);
+        let entries = ["lobby.host", "lobby.host_ranked"]
+            .into_iter()
+            .map(|key| locale.get(key).to_string())
+            .chain(lobby.hosts.iter().map(|host| {
+                let key = if host.ranked {
+                    "lobby.join_ranked"
+                } else {
+                    "lobby.join"
+                };
+                locale.format(key, &[("name", &host.name), ("addr", &host.addr.ip())])
+            }));
+        text.sections = entries
+            .enumerate()
+            .map(|(index, label)| {
+                let selected = browsing && index == lobby.selection;
+                TextSection::new(
+                    format!("{label}\n"),
+                    TextStyle {
+                        font: Default::default(),
+                        font_size: 28.0,
+                        color: palette.menu_item(selected),
+                    },
+                )
+            })
+            .collect();
+        if let LobbyRole::MatchFound(found) = &lobby.role {
+            let kind = locale.get(if found.ranked {
+                "lobby.ranked_match"
+            } else {
+                "lobby.friendly_match"
+            });
+            text.sections = vec![TextSection::new(
+                locale.format(
+                    "lobby.match_found",
+                    &[
+                        ("kind", &kind),
+                        ("rating", &rating.rating),
+                        ("opponent", &found.opponent_rating),
+                    ],
+                ),
+                TextStyle {
+                    font: Default::default(),
+                    font_size: 28.0,
+                    color: palette.accent,
+                },
+            )];
+        }
+    }
+    if let Ok(mut text) = text_query.get_mut(lobby.status) {
+        let status = match (&lobby.role, &lobby.message) {
+            (LobbyRole::Hosting { .. }, _) => {
+                locale.format("lobby.hosting", &[("port", &GAME_PORT)])
+            }
+            (LobbyRole::Greeting { .. }, _) => locale.get("lobby.connecting").to_string(),
+            (LobbyRole::MatchFound(_), _) => locale.get("lobby.get_ready").to_string(),
+            (LobbyRole::Browsing, Some(message)) => {
+                locale.format("lobby.message", &[("message", message)])
+            }
+            (LobbyRole::Browsing, None) if lobby.discovery.is_none() => locale.format(
+                "lobby.no_discovery",
+                &[("rating", &rating.summary(&locale))],
+            ),
+            (LobbyRole::Browsing, None) => {
+                locale.format("lobby.searching", &[("rating", &rating.summary(&locale))])
+            }
+        };
+        text.sections[0].value = status;
+    }
+}
+
+fn pump_session(
+    mut session: ResMut<NetSession>,
+    clock: Res<SimClock>,
+    mut queue: ResMut<CommandQueue>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let session = &mut *session;
+    for message in session.connection.receive() {
+        match message {
+            NetMessage::Input { frame, commands } => {
+                queue.scheduled.entry(frame).or_default().extend(
+                    commands
+                        .into_iter()
+                        .map(|command| (session.remote, command)),
+                );
+                session.remote_through = Some(frame);
+            }
+            NetMessage::Bye => session.connection.closed = Some(Closed::ByPeer),
+            _ => {}
+        }
+    }
+    if let Some(closed) = session.connection.closed {
+        match closed {
+            Closed::ByPeer => warn!("netplay peer disconnected"),
+            Closed::LocalError => warn!("netplay connection failed on our side"),
+        }
+        next_state.set(AppState::Title);
+        return;
+    }
+
+    let target = clock.frame + INPUT_DELAY_FRAMES;
+    let first = session.sealed_through.map_or(0, |frame| frame + 1);
+    for frame in first..=target {
+        let commands = if frame == target {
+            queue.seal(frame)
+        } else {
+            Vec::new()
+        };
+        session
+            .connection
+            .send(&NetMessage::Input { frame, commands });
+    }
+    session.sealed_through = Some(session.sealed_through.map_or(target, |f| f.max(target)));
+
+    let ready = |through: Option<u64>| through.map_or(0, |frame| frame + 1);
+    queue.lockstep_limit = Some(ready(session.sealed_through).min(ready(session.remote_through)));
+}
+
+fn end_session(
+    mut commands: Commands,
+    session: Option<ResMut<NetSession>>,
+    mut local: ResMut<LocalControl>,
+    mut seed: ResMut<MatchSeed>,
+    mut queue: ResMut<CommandQueue>,
+    mut rating: ResMut<PlayerRating>,
+    profiles: Res<Profiles>,
+) {
+    if let Some(mut session) = session {
+        let peer_left = session.connection.closed == Some(Closed::ByPeer);
+        forfeit(&mut session, peer_left, &mut rating, &profiles);
+        session.connection.send(&NetMessage::Bye);
+        commands.remove_resource::<NetSession>();
+    }
+    local.0 = None;
+    seed.0 = None;
+    queue.lockstep_limit = None;
+}
+
+fn record_ranked_result(
+    mut session: ResMut<NetSession>,
+    match_over: Res<MatchOver>,
+    mut rating: ResMut<PlayerRating>,
+    profiles: Res<Profiles>,
+) {
+    if !session.ranked || session.recorded || !match_over.active {
+        return;
+    }
+    let outcome = match match_over.winner {
+        Some(winner) if winner == session.local => MatchOutcome::Win,
+        Some(_) => MatchOutcome::Loss,
+        None => MatchOutcome::Draw,
+    };
+    record_outcome(&mut session, outcome, &mut rating, &profiles);
+}
+
+// Closing the game mid-match leaves the same way quitting to the title does.
+fn forfeit_on_exit(
+    mut session: ResMut<NetSession>,
+    mut rating: ResMut<PlayerRating>,
+    profiles: Res<Profiles>,
+) {
+    forfeit(&mut session, false, &mut rating, &profiles);
+}
+
+// A ranked match that ends before it has a result still counts: the side that
+// left takes the loss and the side that stayed the win. Losing our own
+// connection counts as leaving.
+fn forfeit_outcome(ranked: bool, recorded: bool, peer_left: bool) -> Option<MatchOutcome> {
+    (ranked && !recorded).then_some(if peer_left {
+        MatchOutcome::Win
+    } else {
+        MatchOutcome::Loss
+    })
+}
+
+fn forfeit(
+    session: &mut NetSession,
+    peer_left: bool,
+    rating: &mut PlayerRating,
+    profiles: &Profiles,
+) {
+    if let Some(outcome) = forfeit_outcome(session.ranked, session.recorded, peer_left) {
+        record_outcome(session, outcome, rating, profiles);
+    }
+}
+
+fn record_outcome(
+    session: &mut NetSession,
+    outcome: MatchOutcome,
+    rating: &mut PlayerRating,
+    profiles: &Profiles,
+) {
+    session.recorded = true;
+    let delta = rating.record(session.opponent_rating, outcome);
+    info!(
+        "ranked match {outcome:?}: rating {delta:+} to {}",
+        rating.rating
+    );
+    rating.save(profiles);
+    rating.sync();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unfinished_ranked_match_counts_for_whoever_stayed() {
+        assert_eq!(forfeit_outcome(true, false, true), Some(MatchOutcome::Win));
+        assert_eq!(
+            forfeit_outcome(true, false, false),
+            Some(MatchOutcome::Loss)
+        );
+        assert_eq!(forfeit_outcome(true, true, true), None);
+        assert_eq!(forfeit_outcome(false, false, true), None);
+    }
+
+    #[test]
+    fn peer_hanging_up_counts_as_them_leaving() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let peer = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        let mut connection = Connection::new(stream).unwrap();
+        drop(peer);
+        while connection.closed.is_none() {
+            connection.receive();
+        }
+        assert_eq!(connection.closed, Some(Closed::ByPeer));
+    }
+}
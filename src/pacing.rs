@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+
+use crate::tuning::{ClearTiming, Tuning};
+use crate::{BLOCK_HOVER_STEPS, GRAVITY_STEP_SECONDS};
+
+pub const FLASH_SECONDS: &[f32] = &[0.25, 0.5, 0.75, 1.0];
+pub const POP_INTERVALS: &[f32] = &[0.05, 0.1, 0.15, 0.2];
+pub const HOVER_STEPS: &[u8] = &[0, 1, 2, 3, 4];
+pub const GRAVITY_STEPS: &[f32] = &[0.05, 0.1, 0.15, 0.2];
+const FAST_CLEAR: ClearTiming = ClearTiming {
+    flash: 0.5,
+    pop_interval: 0.1,
+};
+const FAST_HOVER_STEPS: u8 = 1;
+const FAST_GRAVITY_STEP: f32 = 0.05;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PacingPreset {
+    Classic,
+    Fast,
+    Custom,
+}
+
+impl PacingPreset {
+    pub const ALL: &'static [PacingPreset] = &[
+        PacingPreset::Classic,
+        PacingPreset::Fast,
+        PacingPreset::Custom,
+    ];
+
+    pub fn label_key(self) -> &'static str {
+        match self {
+            PacingPreset::Classic => "pacing.classic",
+            PacingPreset::Fast => "pacing.fast",
+            PacingPreset::Custom => "pacing.custom",
+        }
+    }
+}
+
+// How a match feels between a swap and the next chance to act: how long clears
+// flash and pop, how long blocks hover before falling and how fast they fall.
+// CLASSIC follows `assets/tuning.ron`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Pacing {
+    pub preset: PacingPreset,
+    pub clear: ClearTiming,
+    pub hover_steps: u8,
+    pub gravity_step: f32,
+}
+
+impl Default for Pacing {
+    fn default() -> Self {
+        Self::from_preset(PacingPreset::Classic)
+    }
+}
+
+impl Pacing {
+    pub fn from_preset(preset: PacingPreset) -> Self {
+        match preset {
+            PacingPreset::Classic => Self {
+                preset,
+                clear: Tuning::builtin().clear,
+                hover_steps: BLOCK_HOVER_STEPS,
+                gravity_step: GRAVITY_STEP_SECONDS,
+            },
+            PacingPreset::Fast => Self {
+                preset,
+                clear: FAST_CLEAR,
+                hover_steps: FAST_HOVER_STEPS,
+                gravity_step: FAST_GRAVITY_STEP,
+            },
+            PacingPreset::Custom => Self {
+                preset,
+                ..Self::from_preset(PacingPreset::Classic)
+            },
+        }
+    }
+
+    // CLASSIC clears follow the match's tuning rather than the built-in table.
+    pub fn with_tuning(self, tuning: &Tuning) -> Self {
+        match self.preset {
+            PacingPreset::Classic => Self {
+                clear: tuning.clear,
+                ..self
+            },
+            _ => self,
+        }
+    }
+}
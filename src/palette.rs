@@ -0,0 +1,293 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::game::{Block, BlockColor, BlockStatus};
+
+const ICE_TINT: f32 = 0.45;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PaletteKind {
+    #[default]
+    Standard,
+    HighContrast,
+    Light,
+    Deuteranopia,
+    Protanopia,
+    Tritanopia,
+    Neon,
+    Gold,
+}
+
+impl PaletteKind {
+    pub const ALL: [PaletteKind; 8] = [
+        PaletteKind::Standard,
+        PaletteKind::HighContrast,
+        PaletteKind::Light,
+        PaletteKind::Deuteranopia,
+        PaletteKind::Protanopia,
+        PaletteKind::Tritanopia,
+        PaletteKind::Neon,
+        PaletteKind::Gold,
+    ];
+
+    pub fn label_key(self) -> &'static str {
+        match self {
+            PaletteKind::Standard => "palette.standard",
+            PaletteKind::HighContrast => "palette.high_contrast",
+            PaletteKind::Light => "palette.light",
+            PaletteKind::Deuteranopia => "palette.deuteranopia",
+            PaletteKind::Protanopia => "palette.protanopia",
+            PaletteKind::Tritanopia => "palette.tritanopia",
+            PaletteKind::Neon => "palette.neon",
+            PaletteKind::Gold => "palette.gold",
+        }
+    }
+}
+
+#[derive(Resource, Clone, Debug)]
+pub struct Palette {
+    pub background: Color,
+    pub overlay: Color,
+    pub pause_overlay: Color,
+    pub heading: Color,
+    pub text: Color,
+    pub selected: Color,
+    pub disabled: Color,
+    pub warning: Color,
+    pub accent: Color,
+    pub danger: Color,
+    pub highlight: Color,
+    pub grid_cell: Color,
+    pub frame: Color,
+    pub panel: Color,
+    pub panel_header: Color,
+    pub cursor: Color,
+    pub blocks: [Color; 5],
+    pub garbage: Color,
+    pub garbage_cracked: Color,
+    pub garbage_edge: Color,
+    pub bomb: Color,
+    pub bomb_fuse: Color,
+    pub stone: Color,
+    pub stone_cracked: Color,
+    pub ice: Color,
+    pub popup_chain: Color,
+    pub popup_combo: Color,
+    pub popup_plain: Color,
+    pub popup_cancel: Color,
+    pub popup_counter: Color,
+}
+
+impl Palette {
+    pub fn new(kind: PaletteKind) -> Self {
+        match kind {
+            PaletteKind::Standard => Self::standard(),
+            PaletteKind::HighContrast => Self::high_contrast(),
+            PaletteKind::Light => Self::light(),
+            PaletteKind::Deuteranopia => Self {
+                blocks: [
+                    Color::srgb(0.84, 0.37, 0.0),
+                    Color::srgb(0.0, 0.62, 0.45),
+                    Color::srgb(0.0, 0.45, 0.7),
+                    Color::srgb(0.94, 0.89, 0.26),
+                    Color::srgb(0.8, 0.47, 0.65),
+                ],
+                ..Self::standard()
+            },
+            PaletteKind::Protanopia => Self {
+                blocks: [
+                    Color::srgb(0.84, 0.37, 0.0),
+                    Color::srgb(0.0, 0.62, 0.45),
+                    Color::srgb(0.34, 0.71, 0.91),
+                    Color::srgb(0.94, 0.89, 0.26),
+                    Color::srgb(0.8, 0.47, 0.65),
+                ],
+                ..Self::standard()
+            },
+            PaletteKind::Tritanopia => Self {
+                blocks: [
+                    Color::srgb(0.9, 0.2, 0.25),
+                    Color::srgb(0.0, 0.6, 0.6),
+                    Color::srgb(0.2, 0.25, 0.55),
+                    Color::srgb(1.0, 0.65, 0.75),
+                    Color::srgb(0.55, 0.1, 0.3),
+                ],
+                ..Self::standard()
+            },
+            PaletteKind::Neon => Self {
+                background: Color::srgb(0.04, 0.02, 0.08),
+                frame: Color::srgb(0.55, 0.1, 0.75),
+                panel_header: Color::srgb(0.3, 0.05, 0.45),
+                blocks: [
+                    Color::srgb(1.0, 0.1, 0.55),
+                    Color::srgb(0.2, 1.0, 0.3),
+                    Color::srgb(0.1, 0.8, 1.0),
+                    Color::srgb(1.0, 0.95, 0.1),
+                    Color::srgb(0.75, 0.3, 1.0),
+                ],
+                ..Self::standard()
+            },
+            PaletteKind::Gold => Self {
+                background: Color::srgb(0.12, 0.09, 0.04),
+                frame: Color::srgb(0.7, 0.55, 0.2),
+                panel_header: Color::srgb(0.45, 0.35, 0.12),
+                accent: Color::srgb(1.0, 0.85, 0.35),
+                blocks: [
+                    Color::srgb(0.85, 0.35, 0.25),
+                    Color::srgb(0.55, 0.7, 0.35),
+                    Color::srgb(0.45, 0.6, 0.8),
+                    Color::srgb(1.0, 0.82, 0.3),
+                    Color::srgb(0.7, 0.5, 0.75),
+                ],
+                ..Self::standard()
+            },
+        }
+    }
+
+    fn standard() -> Self {
+        Self {
+            background: Color::srgb(0.17, 0.17, 0.18),
+            overlay: Color::srgba(0.02, 0.02, 0.03, 0.9),
+            pause_overlay: Color::srgba(0.02, 0.02, 0.03, 0.75),
+            heading: Color::srgb(0.9, 0.9, 0.95),
+            text: Color::srgb(0.7, 0.7, 0.75),
+            selected: Color::srgb(0.2, 0.9, 0.6),
+            disabled: Color::srgb(0.4, 0.4, 0.44),
+            warning: Color::srgb(1.0, 0.55, 0.4),
+            accent: Color::srgb(0.95, 0.75, 0.3),
+            danger: Color::srgb(0.95, 0.2, 0.2),
+            highlight: Color::srgb(1.0, 0.55, 0.2),
+            grid_cell: Color::srgba(0.1, 0.1, 0.12, 0.35),
+            frame: Color::srgb(0.12, 0.12, 0.16),
+            panel: Color::srgb(0.07, 0.07, 0.09),
+            panel_header: Color::srgb(0.12, 0.12, 0.16),
+            cursor: Color::srgb(1.0, 1.0, 1.0),
+            blocks: [
+                Color::srgb(0.9, 0.36, 0.5),
+                Color::srgb(0.18, 0.78, 0.5),
+                Color::srgb(0.36, 0.52, 0.96),
+                Color::srgb(0.95, 0.76, 0.28),
+                Color::srgb(0.62, 0.4, 0.9),
+            ],
+            garbage: Color::srgb(0.36, 0.38, 0.4),
+            garbage_cracked: Color::srgb(0.58, 0.6, 0.62),
+            garbage_edge: Color::srgb(0.2, 0.21, 0.23),
+            bomb: Color::srgb(0.12, 0.12, 0.14),
+            bomb_fuse: Color::srgb(1.0, 0.55, 0.1),
+            stone: Color::srgb(0.5, 0.42, 0.34),
+            stone_cracked: Color::srgb(0.68, 0.58, 0.47),
+            ice: Color::srgb(0.78, 0.92, 1.0),
+            popup_chain: Color::srgb(1.0, 0.62, 0.2),
+            popup_combo: Color::srgb(0.35, 0.9, 1.0),
+            popup_plain: Color::srgb(0.95, 0.95, 0.95),
+            popup_cancel: Color::srgb(0.55, 0.85, 1.0),
+            popup_counter: Color::srgb(1.0, 0.4, 0.55),
+        }
+    }
+
+    fn high_contrast() -> Self {
+        Self {
+            background: Color::BLACK,
+            overlay: Color::srgba(0.0, 0.0, 0.0, 0.95),
+            pause_overlay: Color::srgba(0.0, 0.0, 0.0, 0.85),
+            heading: Color::WHITE,
+            text: Color::srgb(0.9, 0.9, 0.9),
+            selected: Color::srgb(1.0, 1.0, 0.0),
+            disabled: Color::srgb(0.5, 0.5, 0.5),
+            warning: Color::srgb(1.0, 0.5, 0.3),
+            accent: Color::srgb(1.0, 0.85, 0.0),
+            danger: Color::srgb(1.0, 0.2, 0.2),
+            highlight: Color::srgb(1.0, 0.6, 0.0),
+            grid_cell: Color::srgba(0.15, 0.15, 0.15, 0.6),
+            frame: Color::WHITE,
+            panel: Color::BLACK,
+            panel_header: Color::srgb(0.25, 0.25, 0.25),
+            cursor: Color::WHITE,
+            blocks: [
+                Color::srgb(1.0, 0.1, 0.1),
+                Color::srgb(0.0, 0.9, 0.2),
+                Color::srgb(0.2, 0.45, 1.0),
+                Color::srgb(1.0, 0.95, 0.0),
+                Color::srgb(0.95, 0.3, 1.0),
+            ],
+            garbage: Color::srgb(0.5, 0.5, 0.5),
+            garbage_cracked: Color::srgb(0.8, 0.8, 0.8),
+            garbage_edge: Color::WHITE,
+            bomb: Color::srgb(0.1, 0.1, 0.1),
+            bomb_fuse: Color::srgb(1.0, 0.6, 0.0),
+            stone: Color::srgb(0.55, 0.35, 0.15),
+            stone_cracked: Color::srgb(0.85, 0.65, 0.4),
+            ice: Color::srgb(0.7, 0.95, 1.0),
+            popup_chain: Color::srgb(1.0, 0.6, 0.0),
+            popup_combo: Color::srgb(0.0, 1.0, 1.0),
+            popup_plain: Color::WHITE,
+            popup_cancel: Color::srgb(0.4, 0.9, 1.0),
+            popup_counter: Color::srgb(1.0, 0.3, 1.0),
+        }
+    }
+
+    fn light() -> Self {
+        Self {
+            background: Color::srgb(0.92, 0.92, 0.94),
+            overlay: Color::srgba(0.96, 0.96, 0.97, 0.92),
+            pause_overlay: Color::srgba(0.96, 0.96, 0.97, 0.8),
+            heading: Color::srgb(0.1, 0.1, 0.14),
+            text: Color::srgb(0.35, 0.35, 0.4),
+            selected: Color::srgb(0.05, 0.55, 0.35),
+            disabled: Color::srgb(0.65, 0.65, 0.7),
+            warning: Color::srgb(0.8, 0.3, 0.1),
+            accent: Color::srgb(0.7, 0.45, 0.0),
+            danger: Color::srgb(0.8, 0.1, 0.1),
+            highlight: Color::srgb(0.85, 0.4, 0.0),
+            grid_cell: Color::srgba(0.75, 0.75, 0.8, 0.35),
+            frame: Color::srgb(0.55, 0.55, 0.6),
+            panel: Color::srgb(0.85, 0.85, 0.88),
+            panel_header: Color::srgb(0.75, 0.75, 0.8),
+            cursor: Color::srgb(0.1, 0.1, 0.1),
+            garbage: Color::srgb(0.45, 0.47, 0.5),
+            garbage_cracked: Color::srgb(0.65, 0.67, 0.7),
+            garbage_edge: Color::srgb(0.25, 0.26, 0.3),
+            bomb: Color::srgb(0.2, 0.2, 0.22),
+            bomb_fuse: Color::srgb(0.95, 0.45, 0.05),
+            stone: Color::srgb(0.45, 0.38, 0.3),
+            stone_cracked: Color::srgb(0.62, 0.54, 0.44),
+            ice: Color::srgb(0.6, 0.82, 0.95),
+            popup_chain: Color::srgb(0.85, 0.45, 0.0),
+            popup_combo: Color::srgb(0.0, 0.55, 0.7),
+            popup_plain: Color::srgb(0.15, 0.15, 0.2),
+            popup_cancel: Color::srgb(0.1, 0.45, 0.75),
+            popup_counter: Color::srgb(0.75, 0.1, 0.35),
+            ..Self::standard()
+        }
+    }
+
+    pub fn block(&self, color: BlockColor) -> Color {
+        let index = match color {
+            BlockColor::Red => 0,
+            BlockColor::Green => 1,
+            BlockColor::Blue => 2,
+            BlockColor::Yellow => 3,
+            BlockColor::Purple => 4,
+        };
+        self.blocks[index]
+    }
+
+    pub fn cell(&self, block: Block) -> Color {
+        match block {
+            Block::Normal {
+                color,
+                status: BlockStatus::Iced,
+            } => self.block(color).mix(&self.ice, ICE_TINT),
+            Block::Normal { color, .. } | Block::Item { color, .. } => self.block(color),
+            Block::Garbage { cracked: true } => self.garbage_cracked,
+            Block::Garbage { cracked: false } => self.garbage,
+            Block::Bomb => self.bomb,
+            Block::Stone { cracked: false } => self.stone,
+            Block::Stone { cracked: true } => self.stone_cracked,
+        }
+    }
+
+    pub fn menu_item(&self, selected: bool) -> Color {
+        if selected { self.selected } else { self.text }
+    }
+}
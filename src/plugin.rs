@@ -0,0 +1,248 @@
+use bevy::prelude::*;
+
+use crate::cli::LaunchOptions;
+use crate::{
+    AppState, GameInitialized, GameSet, GarbageMotions, LocalControl, MenuItem, ObserverView,
+    PauseItem, ResultsItem, StickSettings, ViewState, achievements, add_simulation,
+    announce_match_result, apply_launch_options, apply_observer_layout, audio, bindings,
+    challenges, cleanup_game, cleanup_menu, cleanup_pause, cutscene, devices, drive_simulation,
+    fit_camera_to_window, handle_input, handle_mouse, handle_pause_input, handle_pause_request,
+    handle_results_input, handle_title_menu, haptics, howto, input_display, menu, profiles,
+    records, resize_player_views, settings, setup_camera, setup_game, setup_menu, setup_pause,
+    spawn_cancel_popups, spawn_counter_popups, spawn_item_popups, spawn_level_up_popups,
+    spawn_score_popups, spectacle, splash, split, tips, toggle_observer_view, trace,
+    track_garbage_motion, trials, tuning, update_board_arrangement, update_chain_charts,
+    update_chain_indicators, update_danger_visuals, update_floating_texts, update_game_over_timer,
+    update_garbage_previews, update_mouse_hover, update_observer_view, update_panel_layout,
+    update_results_menu, update_rise_meters, update_ui_text, update_visuals, versus, views_ready,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::{clips, ghost, netplay, rating, screenshots, spectate, stream};
+
+// Everything an embedding app needs to run the game next to its own systems. It
+// expects DefaultPlugins (or an equivalent window, asset and input setup) to be
+// added by the host. `networking` covers spectating, netplay and ratings,
+// `capture` screenshots and clips, and `streaming` the chat audience.
+#[derive(Clone, Debug)]
+pub struct TetanusAttackConfig {
+    pub spawn_camera: bool,
+    pub audio: bool,
+    pub networking: bool,
+    pub capture: bool,
+    pub streaming: bool,
+}
+
+impl Default for TetanusAttackConfig {
+    fn default() -> Self {
+        Self {
+            spawn_camera: true,
+            audio: true,
+            networking: true,
+            capture: true,
+            streaming: true,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct TetanusAttackPlugin {
+    pub config: TetanusAttackConfig,
+}
+
+impl Plugin for TetanusAttackPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            CorePlugin,
+            UiPlugin {
+                spawn_camera: self.config.spawn_camera,
+            },
+        ));
+        if self.config.audio {
+            app.add_plugins(AudioPlugin);
+        }
+
+        #[cfg(feature = "debug")]
+        app.add_plugins(crate::debug::DebugPlugin);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            if self.config.networking {
+                app.add_plugins((
+                    spectate::SpectatePlugin,
+                    netplay::NetplayPlugin,
+                    rating::RatingPlugin,
+                ));
+            }
+            if self.config.capture {
+                app.add_plugins((screenshots::ScreenshotsPlugin, clips::ClipsPlugin));
+            }
+            if self.config.streaming {
+                app.add_plugins(stream::StreamPlugin);
+            }
+        }
+    }
+}
+
+// The fixed-step simulation, app states, player input and everything that is
+// saved between sessions.
+pub struct CorePlugin;
+
+impl Plugin for CorePlugin {
+    fn build(&self, app: &mut App) {
+        add_simulation(app);
+        app.init_state::<AppState>()
+            .init_resource::<LaunchOptions>()
+            .insert_resource(GameInitialized::default())
+            .insert_resource(LocalControl::default())
+            .insert_resource(StickSettings::default())
+            .add_systems(Startup, (apply_launch_options, bindings::audit_bindings))
+            .configure_sets(
+                Update,
+                (
+                    GameSet::Input,
+                    GameSet::Simulation,
+                    GameSet::GarbageResolve,
+                    GameSet::Presentation,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Game)),
+            )
+            .add_systems(
+                Update,
+                (
+                    handle_input.run_if(not(resource_exists::<trace::InputPlayback>)),
+                    handle_pause_request,
+                )
+                    .in_set(GameSet::Input),
+            )
+            .add_systems(Update, drive_simulation.in_set(GameSet::Simulation))
+            .add_systems(
+                Update,
+                (announce_match_result, update_game_over_timer).in_set(GameSet::Presentation),
+            )
+            .add_plugins((
+                profiles::ProfilesPlugin,
+                trace::TracePlugin,
+                devices::DevicesPlugin,
+                haptics::HapticsPlugin,
+                settings::SettingsPlugin,
+                tuning::TuningPlugin,
+            ));
+    }
+}
+
+// Menus, boards, popups and the rest of what is drawn. Entering the game state
+// here is also what starts a match.
+pub struct UiPlugin {
+    pub spawn_camera: bool,
+}
+
+impl Default for UiPlugin {
+    fn default() -> Self {
+        Self { spawn_camera: true }
+    }
+}
+
+impl Plugin for UiPlugin {
+    fn build(&self, app: &mut App) {
+        if self.spawn_camera {
+            app.add_systems(Startup, setup_camera);
+        }
+        app.init_state::<ViewState>()
+            .configure_sets(Update, GameSet::Presentation.run_if(views_ready))
+            .insert_resource(ObserverView::default())
+            .insert_resource(GarbageMotions::default())
+            .add_systems(OnEnter(AppState::Title), (cleanup_game, setup_menu).chain())
+            .add_systems(OnExit(AppState::Title), cleanup_menu)
+            .add_systems(OnEnter(AppState::Game), setup_game)
+            .add_systems(OnEnter(AppState::Pause), setup_pause)
+            .add_systems(OnExit(AppState::Pause), cleanup_pause)
+            .add_plugins((
+                menu::MenuPlugin::<MenuItem>::default(),
+                menu::MenuPlugin::<PauseItem>::default(),
+                menu::MenuPlugin::<ResultsItem>::default(),
+            ))
+            .add_systems(
+                Update,
+                handle_title_menu
+                    .after(menu::MenuSet)
+                    .run_if(in_state(AppState::Title)),
+            )
+            .add_systems(
+                Update,
+                handle_pause_input
+                    .after(menu::MenuSet)
+                    .run_if(in_state(AppState::Pause)),
+            )
+            .add_systems(
+                Update,
+                (
+                    handle_mouse
+                        .run_if(not(resource_exists::<trace::InputPlayback>))
+                        .run_if(views_ready),
+                    handle_results_input.after(menu::MenuSet),
+                )
+                    .in_set(GameSet::Input),
+            )
+            .add_systems(
+                Update,
+                (
+                    update_results_menu,
+                    (
+                        toggle_observer_view,
+                        apply_observer_layout,
+                        update_board_arrangement,
+                        fit_camera_to_window,
+                        update_panel_layout,
+                        update_observer_view,
+                    )
+                        .chain(),
+                    (
+                        resize_player_views,
+                        track_garbage_motion,
+                        update_visuals,
+                        update_danger_visuals,
+                    )
+                        .chain(),
+                    update_ui_text,
+                    spawn_score_popups,
+                    spawn_cancel_popups,
+                    spawn_counter_popups,
+                    spawn_level_up_popups,
+                    spawn_item_popups,
+                    update_floating_texts,
+                    update_chain_indicators,
+                    update_garbage_previews,
+                    update_mouse_hover,
+                    update_chain_charts,
+                    update_rise_meters,
+                )
+                    .in_set(GameSet::Presentation),
+            )
+            .add_plugins((
+                achievements::AchievementsPlugin,
+                challenges::ChallengesPlugin,
+                trials::TrialsPlugin,
+                cutscene::CutscenePlugin,
+                split::SplitScreenPlugin,
+                spectacle::SpectaclePlugin,
+                input_display::InputDisplayPlugin,
+                records::RecordsPlugin,
+                tips::TipsPlugin,
+                versus::VersusPlugin,
+                splash::SplashPlugin,
+                howto::HowToPlugin,
+            ));
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_plugins(ghost::GhostPlugin);
+    }
+}
+
+// Generated stingers and the optional announcer voice pack.
+pub struct AudioPlugin;
+
+impl Plugin for AudioPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(audio::StingersPlugin);
+    }
+}
@@ -0,0 +1,109 @@
+use bevy::prelude::*;
+
+use crate::{BLOCK_INSET, CELL_SIZE, GameEntity};
+
+const CLEAR: Color = Color::srgba(0.0, 0.0, 0.0, 0.0);
+
+// A block's fill sprite and the border sprite parented under it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlockSprite {
+    pub fill: Entity,
+    pub edge: Entity,
+}
+
+impl BlockSprite {
+    fn spawn(commands: &mut Commands) -> Self {
+        let mut edge = Entity::PLACEHOLDER;
+        let fill = commands
+            .spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: CLEAR,
+                        custom_size: Some(Vec2::splat(CELL_SIZE - BLOCK_INSET)),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                GameEntity,
+            ))
+            .with_children(|parent| {
+                edge = parent
+                    .spawn(SpriteBundle {
+                        sprite: Sprite {
+                            color: CLEAR,
+                            ..Default::default()
+                        },
+                        transform: Transform::from_xyz(0.0, 0.0, -0.1),
+                        ..Default::default()
+                    })
+                    .id();
+            })
+            .id();
+        Self { fill, edge }
+    }
+
+    pub fn hide(self, sprite_query: &mut Query<&mut Sprite>) {
+        for entity in [self.fill, self.edge] {
+            if let Ok(mut sprite) = sprite_query.get_mut(entity) {
+                sprite.color = CLEAR;
+            }
+        }
+    }
+}
+
+// Board cells borrow a sprite only while they hold a block and hand it back
+// once the block clears or falls away, so a resized board just remaps its cells
+// and the pool only grows when a board holds more blocks than it ever has.
+pub struct BlockPool {
+    cells: Vec<Option<BlockSprite>>,
+    free: Vec<BlockSprite>,
+}
+
+impl BlockPool {
+    pub fn new(commands: &mut Commands, cells: usize, prewarm: usize) -> Self {
+        Self {
+            cells: vec![None; cells],
+            free: (0..prewarm).map(|_| BlockSprite::spawn(commands)).collect(),
+        }
+    }
+
+    pub fn cell_count(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn get(&self, idx: usize) -> Option<BlockSprite> {
+        self.cells.get(idx).copied().flatten()
+    }
+
+    pub fn in_use(&self) -> impl Iterator<Item = BlockSprite> + '_ {
+        self.cells.iter().flatten().copied()
+    }
+
+    // A sprite spawned here can't be reached through queries until commands
+    // apply, so it stays blank for its first frame.
+    pub fn acquire(&mut self, commands: &mut Commands, idx: usize) -> Option<BlockSprite> {
+        let slot = self.cells.get_mut(idx)?;
+        if slot.is_none() {
+            *slot = Some(
+                self.free
+                    .pop()
+                    .unwrap_or_else(|| BlockSprite::spawn(commands)),
+            );
+        }
+        *slot
+    }
+
+    pub fn release(&mut self, idx: usize) -> Option<BlockSprite> {
+        let sprite = self.cells.get_mut(idx)?.take()?;
+        self.free.push(sprite);
+        Some(sprite)
+    }
+
+    // Returns the sprites that were in use, for the caller to hide.
+    pub fn resize(&mut self, cells: usize) -> Vec<BlockSprite> {
+        let released: Vec<BlockSprite> = self.cells.drain(..).flatten().collect();
+        self.free.extend(&released);
+        self.cells = vec![None; cells];
+        released
+    }
+}
@@ -0,0 +1,522 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+
+use bevy::input::ButtonState;
+use bevy::input::gamepad::GamepadButtonType as Pad;
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::achievements::Achievements;
+use crate::challenges::ChallengeRecords;
+use crate::locale::Locale;
+use crate::menu::MenuInput;
+use crate::palette::Palette;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::rating::PlayerRating;
+use crate::records::RecordStore;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::settings::data_dir;
+use crate::settings::{Settings, cycle};
+use crate::tips::FirstTimeEvents;
+use crate::{
+    AppState, ClearEvent, CpuControl, GameMode, GameSet, LocalControl, MatchOver, PlayerId, Players,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+const PROFILES_FILE: &str = "profiles.json";
+#[cfg(not(target_arch = "wasm32"))]
+const PROFILES_DIR: &str = "profiles";
+#[cfg(not(target_arch = "wasm32"))]
+const STATS_FILE: &str = "stats.json";
+#[cfg(not(target_arch = "wasm32"))]
+const LEGACY_FILES: &[&str] = &["settings.json", "achievements.json", "rating.json"];
+const DEFAULT_PROFILE: &str = "PLAYER";
+const MAX_NAME_LEN: usize = 12;
+const HIGH_SCORES: usize = 5;
+const NAME_CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+
+pub struct ProfilesPlugin;
+
+impl Plugin for ProfilesPlugin {
+    fn build(&self, app: &mut App) {
+        let profiles = Profiles::load();
+        app.insert_resource(ProfileStats::load(&profiles))
+            .insert_resource(profiles)
+            .add_systems(OnEnter(AppState::Profiles), setup_profiles)
+            .add_systems(OnExit(AppState::Profiles), cleanup_profiles)
+            .add_systems(
+                Update,
+                (handle_profiles_input, refresh_profiles_text)
+                    .chain()
+                    .run_if(in_state(AppState::Profiles)),
+            )
+            .add_systems(Update, switch_profile.run_if(resource_changed::<Profiles>))
+            .add_systems(Update, track_profile_stats.in_set(GameSet::Presentation));
+    }
+}
+
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profiles {
+    pub active: String,
+    pub names: Vec<String>,
+}
+
+impl Default for Profiles {
+    fn default() -> Self {
+        Self {
+            active: DEFAULT_PROFILE.to_string(),
+            names: vec![DEFAULT_PROFILE.to_string()],
+        }
+    }
+}
+
+impl Profiles {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load() -> Self {
+        let path = data_dir().join(PROFILES_FILE);
+        let Ok(contents) = std::fs::read_to_string(&path) else {
+            let profiles = Self::default();
+            profiles.adopt_legacy_files();
+            profiles.save();
+            return profiles;
+        };
+        let mut profiles: Self = serde_json::from_str(&contents).unwrap_or_else(|err| {
+            warn!(
+                "ignoring unreadable profiles file {}: {err}",
+                path.display()
+            );
+            Self::default()
+        });
+        if !profiles.names.contains(&profiles.active) {
+            profiles.names.push(profiles.active.clone());
+        }
+        profiles
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn load() -> Self {
+        Self::default()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save(&self) {
+        let path = data_dir().join(PROFILES_FILE);
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let Ok(contents) = serde_json::to_string_pretty(self) else {
+            return;
+        };
+        if let Err(err) = std::fs::write(&path, contents) {
+            warn!("could not save profiles to {}: {err}", path.display());
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn save(&self) {}
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn dir(&self) -> PathBuf {
+        data_dir().join(PROFILES_DIR).join(&self.active)
+    }
+
+    // Records written before profiles existed move into the first profile.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn adopt_legacy_files(&self) {
+        let dir = self.dir();
+        let _ = std::fs::create_dir_all(&dir);
+        for file in LEGACY_FILES {
+            let legacy = data_dir().join(file);
+            if legacy.exists() && !dir.join(file).exists() {
+                let _ = std::fs::rename(&legacy, dir.join(file));
+            }
+        }
+    }
+
+    fn create(&mut self, name: String) {
+        if !self.names.contains(&name) {
+            self.names.push(name.clone());
+        }
+        self.active = name;
+    }
+}
+
+#[derive(Resource, Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProfileStats {
+    pub matches: u32,
+    pub wins: u32,
+    pub losses: u32,
+    pub play_seconds: f32,
+    pub total_score: u64,
+    pub best_chain: u32,
+    pub high_scores: Vec<u32>,
+}
+
+impl ProfileStats {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load(profiles: &Profiles) -> Self {
+        let path = profiles.dir().join(STATS_FILE);
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|err| {
+                warn!("ignoring unreadable stats file {}: {err}", path.display());
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn load(_profiles: &Profiles) -> Self {
+        Self::default()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save(&self, profiles: &Profiles) {
+        let path = profiles.dir().join(STATS_FILE);
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let Ok(contents) = serde_json::to_string_pretty(self) else {
+            return;
+        };
+        if let Err(err) = std::fs::write(&path, contents) {
+            warn!("could not save stats to {}: {err}", path.display());
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn save(&self, _profiles: &Profiles) {}
+
+    fn record_score(&mut self, score: u32) {
+        self.high_scores.push(score);
+        self.high_scores.sort_unstable_by(|a, b| b.cmp(a));
+        self.high_scores.truncate(HIGH_SCORES);
+    }
+
+    pub fn high_score(&self) -> u32 {
+        self.high_scores.first().copied().unwrap_or(0)
+    }
+}
+
+fn switch_profile(
+    mut commands: Commands,
+    profiles: Res<Profiles>,
+    mut loaded: Local<Option<String>>,
+) {
+    if loaded.as_deref() == Some(profiles.active.as_str()) {
+        return;
+    }
+    if loaded.replace(profiles.active.clone()).is_none() {
+        return;
+    }
+    commands.insert_resource(Settings::load(&profiles));
+    commands.insert_resource(Achievements::load(&profiles));
+    commands.insert_resource(ChallengeRecords::load(&profiles));
+    commands.insert_resource(ProfileStats::load(&profiles));
+    commands.insert_resource(RecordStore::load(&profiles));
+    commands.insert_resource(FirstTimeEvents::load(&profiles));
+    #[cfg(not(target_arch = "wasm32"))]
+    commands.insert_resource(PlayerRating::load(&profiles));
+}
+
+fn profile_owner(mode: GameMode, local: LocalControl, cpu: CpuControl) -> PlayerId {
+    match (mode, local.0) {
+        (GameMode::TwoPlayer, Some(id)) => id,
+        (GameMode::TwoPlayer, None) if cpu.controls(PlayerId::P1) => PlayerId::P2,
+        _ => PlayerId::P1,
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn track_profile_stats(
+    mut clears: EventReader<ClearEvent>,
+    players: Res<Players>,
+    match_over: Res<MatchOver>,
+    mode: Res<GameMode>,
+    local: Res<LocalControl>,
+    cpu: Res<CpuControl>,
+    profiles: Res<Profiles>,
+    mut stats: ResMut<ProfileStats>,
+    mut recorded: Local<bool>,
+) {
+    let owner = profile_owner(*mode, *local, *cpu);
+    for event in clears.read() {
+        if event.player == owner && event.chain > stats.best_chain {
+            stats.best_chain = event.chain;
+        }
+    }
+    if !match_over.active {
+        *recorded = false;
+        return;
+    }
+    if *recorded {
+        return;
+    }
+    *recorded = true;
+    let player = players.get(owner);
+    stats.matches += 1;
+    stats.play_seconds += player.elapsed;
+    stats.total_score += u64::from(player.score);
+    if *mode == GameMode::TwoPlayer {
+        match match_over.winner {
+            Some(winner) if winner == owner => stats.wins += 1,
+            Some(_) => stats.losses += 1,
+            None => {}
+        }
+    } else {
+        stats.record_score(player.score);
+    }
+    stats.save(&profiles);
+}
+
+#[derive(Resource)]
+struct ProfilesScreen {
+    root: Entity,
+    list: Entity,
+    status: Entity,
+    selection: usize,
+    entering: Option<String>,
+}
+
+fn setup_profiles(
+    mut commands: Commands,
+    profiles: Res<Profiles>,
+    palette: Res<Palette>,
+    locale: Res<Locale>,
+) {
+    let root = commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(0.0),
+                top: Val::Percent(0.0),
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(16.0),
+                ..Default::default()
+            },
+            background_color: BackgroundColor(palette.overlay),
+            ..Default::default()
+        })
+        .id();
+
+    let mut list = Entity::PLACEHOLDER;
+    let mut status = Entity::PLACEHOLDER;
+    commands.entity(root).with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            locale.get("profiles.title"),
+            TextStyle {
+                font: Default::default(),
+                font_size: 42.0,
+                color: palette.heading,
+            },
+        ));
+
+        list = parent
+            .spawn(TextBundle {
+                text: Text::default().with_justify(JustifyText::Center),
+                ..Default::default()
+            })
+            .id();
+
+        status = parent
+            .spawn(TextBundle {
+                text: Text::from_section(
+                    "",
+                    TextStyle {
+                        font: Default::default(),
+                        font_size: 18.0,
+                        color: palette.text,
+                    },
+                )
+                .with_justify(JustifyText::Center),
+                ..Default::default()
+            })
+            .id();
+    });
+
+    let selection = profiles
+        .names
+        .iter()
+        .position(|name| *name == profiles.active)
+        .unwrap_or(0);
+    commands.insert_resource(ProfilesScreen {
+        root,
+        list,
+        status,
+        selection,
+        entering: None,
+    });
+}
+
+fn cleanup_profiles(mut commands: Commands, screen: Res<ProfilesScreen>) {
+    commands.entity(screen.root).despawn_recursive();
+    commands.remove_resource::<ProfilesScreen>();
+}
+
+fn handle_profiles_input(
+    input: MenuInput,
+    mut typed: EventReader<KeyboardInput>,
+    mut screen: ResMut<ProfilesScreen>,
+    mut profiles: ResMut<Profiles>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let was_entering = screen.entering.is_some();
+    for event in typed.read() {
+        let Some(name) = screen.entering.as_mut() else {
+            continue;
+        };
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+        match &event.logical_key {
+            Key::Character(chars) => {
+                for c in chars.chars().filter(char::is_ascii_alphanumeric) {
+                    if name.len() < MAX_NAME_LEN {
+                        name.push(c.to_ascii_uppercase());
+                    }
+                }
+            }
+            Key::Backspace => {
+                name.pop();
+            }
+            Key::Escape => screen.entering = None,
+            Key::Enter if !name.is_empty() => {
+                let name = std::mem::take(name);
+                profiles.create(name);
+                profiles.save();
+                next_state.set(AppState::Title);
+                return;
+            }
+            _ => {}
+        }
+    }
+    if was_entering {
+        let Some(name) = screen.entering.as_mut() else {
+            return;
+        };
+        if edit_name_with_pad(&input, name) {
+            screen.entering = None;
+        } else if input.pressed(&[], &[Pad::South, Pad::Start]) && !name.is_empty() {
+            let name = std::mem::take(name);
+            profiles.create(name);
+            profiles.save();
+            next_state.set(AppState::Title);
+        }
+        return;
+    }
+
+    let nav = input.nav();
+    let count = profiles.names.len() + 1;
+    if nav.up {
+        screen.selection = (screen.selection + count - 1) % count;
+    }
+    if nav.down {
+        screen.selection = (screen.selection + 1) % count;
+    }
+    if nav.confirm {
+        match profiles.names.get(screen.selection).cloned() {
+            Some(name) => {
+                profiles.active = name;
+                profiles.save();
+                next_state.set(AppState::Title);
+            }
+            None => screen.entering = Some(String::new()),
+        }
+    } else if nav.cancel {
+        next_state.set(AppState::Title);
+    }
+}
+
+// Pads spell names one letter at a time; returns true when entry is cancelled.
+fn edit_name_with_pad(input: &MenuInput, name: &mut String) -> bool {
+    if input.pressed(&[], &[Pad::East]) {
+        return true;
+    }
+    let step = if input.pressed(&[], &[Pad::DPadUp]) {
+        1
+    } else if input.pressed(&[], &[Pad::DPadDown]) {
+        -1
+    } else {
+        0
+    };
+    if step != 0 {
+        let letter = match name.pop() {
+            Some(last) => cycle(NAME_CHARS, last as u8, step),
+            None => NAME_CHARS[0],
+        };
+        name.push(letter as char);
+    }
+    if input.pressed(&[], &[Pad::DPadRight]) && name.len() < MAX_NAME_LEN {
+        name.push(NAME_CHARS[0] as char);
+    }
+    if input.pressed(&[], &[Pad::DPadLeft, Pad::West]) {
+        name.pop();
+    }
+    false
+}
+
+fn refresh_profiles_text(
+    screen: Res<ProfilesScreen>,
+    profiles: Res<Profiles>,
+    stats: Res<ProfileStats>,
+    palette: Res<Palette>,
+    locale: Res<Locale>,
+    mut text_query: Query<&mut Text>,
+) {
+    if let Ok(mut text) = text_query.get_mut(screen.list) {
+        let new_entry = match &screen.entering {
+            Some(name) => locale.format("profiles.enter_name", &[("name", name)]),
+            None => locale.get("profiles.new").to_string(),
+        };
+        text.sections = profiles
+            .names
+            .iter()
+            .map(|name| {
+                if *name == profiles.active {
+                    locale.format("profiles.active", &[("name", name)])
+                } else {
+                    name.clone()
+                }
+            })
+            .chain(std::iter::once(new_entry))
+            .enumerate()
+            .map(|(index, label)| {
+                TextSection::new(
+                    format!("{label}\n"),
+                    TextStyle {
+                        font: Default::default(),
+                        font_size: 28.0,
+                        color: palette.menu_item(index == screen.selection),
+                    },
+                )
+            })
+            .collect();
+    }
+    if let Ok(mut text) = text_query.get_mut(screen.status) {
+        let hint = locale.get(if screen.entering.is_some() {
+            "profiles.name_hint"
+        } else {
+            "profiles.hint"
+        });
+        let summary = locale.format(
+            "profiles.stats",
+            &[
+                ("name", &profiles.active),
+                ("matches", &stats.matches),
+                ("wins", &stats.wins),
+                ("losses", &stats.losses),
+                ("chain", &stats.best_chain),
+                ("score", &stats.high_score()),
+                ("minutes", &((stats.play_seconds / 60.0).round() as u32)),
+            ],
+        );
+        text.sections[0].value = format!("{summary}\n{hint}");
+    }
+}
@@ -0,0 +1,27 @@
+use bevy::diagnostic::{Diagnostic, DiagnosticPath, RegisterDiagnostic};
+use bevy::prelude::*;
+use bevy::utils::Instant;
+
+pub const GRAVITY: DiagnosticPath = DiagnosticPath::const_new("sim/gravity");
+pub const CLEARS: DiagnosticPath = DiagnosticPath::const_new("sim/clears");
+pub const GARBAGE: DiagnosticPath = DiagnosticPath::const_new("sim/garbage");
+pub const VISUALS: DiagnosticPath = DiagnosticPath::const_new("render/visuals");
+
+// The heavy gameplay systems time themselves into these, and also open a span
+// of the same name for tracing builds (`bevy/trace_chrome`, `bevy/trace_tracy`).
+pub const TIMED: [(&str, DiagnosticPath); 4] = [
+    ("gravity", GRAVITY),
+    ("clears", CLEARS),
+    ("garbage", GARBAGE),
+    ("visuals", VISUALS),
+];
+
+pub fn register(app: &mut App) {
+    for (_, path) in TIMED {
+        app.register_diagnostic(Diagnostic::new(path).with_suffix("ms"));
+    }
+}
+
+pub fn millis_since(started: Instant) -> f64 {
+    started.elapsed().as_secs_f64() * 1000.0
+}
@@ -0,0 +1,197 @@
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::locale::Locale;
+use crate::palette::Palette;
+use crate::profiles::Profiles;
+use crate::{AppState, MenuRoot, setup_menu};
+
+const DEFAULT_RATING: i32 = 1500;
+const K_FACTOR: f32 = 32.0;
+const RATING_FILE: &str = "rating.json";
+const RATING_SERVER_ENV: &str = "TETANUS_RATING_SERVER";
+const SYNC_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct RatingPlugin;
+
+impl Plugin for RatingPlugin {
+    fn build(&self, app: &mut App) {
+        let rating = PlayerRating::load(app.world().resource::<Profiles>());
+        app.insert_resource(rating).add_systems(
+            OnEnter(AppState::Title),
+            show_rating_on_title.after(setup_menu),
+        );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatchOutcome {
+    Win,
+    Loss,
+    Draw,
+}
+
+impl MatchOutcome {
+    fn score(self) -> f32 {
+        match self {
+            MatchOutcome::Win => 1.0,
+            MatchOutcome::Loss => 0.0,
+            MatchOutcome::Draw => 0.5,
+        }
+    }
+}
+
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+pub struct PlayerRating {
+    pub id: String,
+    pub rating: i32,
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+}
+
+impl Default for PlayerRating {
+    fn default() -> Self {
+        Self {
+            id: format!(
+                "{:016x}{:016x}",
+                rand::random::<u64>(),
+                rand::random::<u64>()
+            ),
+            rating: DEFAULT_RATING,
+            wins: 0,
+            losses: 0,
+            draws: 0,
+        }
+    }
+}
+
+impl PlayerRating {
+    pub fn load(profiles: &Profiles) -> Self {
+        let path = profiles.dir().join(RATING_FILE);
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|err| {
+                warn!("ignoring unreadable rating file {}: {err}", path.display());
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self, profiles: &Profiles) {
+        let path = profiles.dir().join(RATING_FILE);
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let Ok(contents) = serde_json::to_string_pretty(self) else {
+            return;
+        };
+        if let Err(err) = std::fs::write(&path, contents) {
+            warn!("could not save rating to {}: {err}", path.display());
+        }
+    }
+
+    pub fn record(&mut self, opponent: i32, outcome: MatchOutcome) -> i32 {
+        let delta = rating_delta(self.rating, opponent, outcome);
+        self.rating += delta;
+        match outcome {
+            MatchOutcome::Win => self.wins += 1,
+            MatchOutcome::Loss => self.losses += 1,
+            MatchOutcome::Draw => self.draws += 1,
+        }
+        delta
+    }
+
+    pub fn summary(&self, locale: &Locale) -> String {
+        locale.format(
+            "rating.summary",
+            &[
+                ("rating", &self.rating),
+                ("wins", &self.wins),
+                ("losses", &self.losses),
+                ("draws", &self.draws),
+            ],
+        )
+    }
+
+    pub fn sync(&self) {
+        let Ok(url) = std::env::var(RATING_SERVER_ENV) else {
+            return;
+        };
+        let Ok(body) = serde_json::to_string(self) else {
+            return;
+        };
+        std::thread::spawn(move || {
+            if let Err(err) = post_json(&url, &body) {
+                warn!("rating sync to {url} failed: {err}");
+            }
+        });
+    }
+}
+
+pub fn expected_score(rating: i32, opponent: i32) -> f32 {
+    1.0 / (1.0 + 10f32.powf((opponent - rating) as f32 / 400.0))
+}
+
+pub fn rating_delta(rating: i32, opponent: i32, outcome: MatchOutcome) -> i32 {
+    (K_FACTOR * (outcome.score() - expected_score(rating, opponent))).round() as i32
+}
+
+fn post_json(url: &str, body: &str) -> Result<(), String> {
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or("only http:// rating servers are supported")?;
+    let (authority, path) = match rest.find('/') {
+        Some(index) => rest.split_at(index),
+        None => (rest, "/"),
+    };
+    let addr = if authority.contains(':') {
+        authority.to_string()
+    } else {
+        format!("{authority}:80")
+    };
+    let mut stream = TcpStream::connect(addr).map_err(|e| e.to_string())?;
+    stream
+        .set_read_timeout(Some(SYNC_TIMEOUT))
+        .map_err(|e| e.to_string())?;
+    let request = format!(
+        "POST {path} HTTP/1.1\r\nHost: {authority}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream
+        .write_all(request.as_bytes())
+        .map_err(|e| e.to_string())?;
+    let mut response = String::new();
+    let _ = stream.read_to_string(&mut response);
+    let status = response.lines().next().unwrap_or_default();
+    match status.split_whitespace().nth(1) {
+        Some(code) if code.starts_with('2') => Ok(()),
+        _ => Err(format!("unexpected response {status:?}")),
+    }
+}
+
+fn show_rating_on_title(
+    mut commands: Commands,
+    menu: Res<MenuRoot>,
+    rating: Res<PlayerRating>,
+    palette: Res<Palette>,
+    locale: Res<Locale>,
+) {
+    commands.entity(menu.0).with_children(|parent| {
+        parent.spawn(TextBundle {
+            text: Text::from_section(
+                rating.summary(&locale),
+                TextStyle {
+                    font: Default::default(),
+                    font_size: 18.0,
+                    color: palette.accent,
+                },
+            ),
+            ..Default::default()
+        });
+    });
+}
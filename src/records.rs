@@ -0,0 +1,384 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::locale::Locale;
+use crate::menu::{Menu, MenuSet};
+use crate::palette::Palette;
+use crate::profiles::Profiles;
+use crate::{
+    AppState, GRID_H, GRID_W, GameEntity, GameMode, GameSet, MatchResultEvent, MatchRules,
+    MenuItem, MenuRoot, Players, SoloMode,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+const RECORDS_FILE: &str = "records.json";
+const BANNER_SECONDS: f32 = 2.5;
+
+// Best score, longest chain and longest survival for every solo mode, start
+// speed and board size the profile has played.
+pub struct RecordsPlugin;
+
+impl Plugin for RecordsPlugin {
+    fn build(&self, app: &mut App) {
+        let store = RecordStore::load(app.world().resource::<Profiles>());
+        app.insert_resource(store)
+            .init_resource::<RecordRun>()
+            .add_event::<RecordBroken>()
+            .add_systems(OnEnter(AppState::Title), save_records)
+            .add_systems(
+                Update,
+                show_title_record
+                    .after(MenuSet)
+                    .run_if(in_state(AppState::Title)),
+            )
+            .add_systems(
+                Update,
+                (track_records, save_on_result, spawn_banners, update_banners)
+                    .chain()
+                    .in_set(GameSet::Presentation),
+            );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RecordKey {
+    pub mode: SoloMode,
+    pub speed: u32,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl RecordKey {
+    fn for_rules(rules: &MatchRules, mode: GameMode, width: usize, height: usize) -> Option<Self> {
+        let tracked = matches!(
+            rules.solo,
+            SoloMode::Endless | SoloMode::Survival | SoloMode::Marathon | SoloMode::Zen
+        );
+        (mode == GameMode::OnePlayer && tracked).then_some(Self {
+            mode: rules.solo,
+            speed: rules.start_level,
+            width,
+            height,
+        })
+    }
+
+    // ZEN never tops out, so time spent there is no survival.
+    fn tracks_survival(self) -> bool {
+        self.mode != SoloMode::Zen
+    }
+}
+
+impl fmt::Display for RecordKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mode = match self.mode {
+            SoloMode::Endless => "endless",
+            SoloMode::Survival => "survival",
+            SoloMode::Marathon => "marathon",
+            SoloMode::Zen => "zen",
+            SoloMode::Challenge(_) | SoloMode::Trial(_) => "other",
+        };
+        write!(f, "{mode}/lv{}/{}x{}", self.speed, self.width, self.height)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Record {
+    pub best_score: u32,
+    pub longest_chain: u32,
+    pub longest_survival: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecordKind {
+    Score,
+    Chain,
+    Survival,
+}
+
+impl RecordKind {
+    const ALL: [RecordKind; 3] = [RecordKind::Score, RecordKind::Chain, RecordKind::Survival];
+
+    fn label_key(self) -> &'static str {
+        match self {
+            RecordKind::Score => "records.score",
+            RecordKind::Chain => "records.chain",
+            RecordKind::Survival => "records.survival",
+        }
+    }
+}
+
+impl Record {
+    fn beats(&self, other: &Record, kind: RecordKind) -> bool {
+        match kind {
+            RecordKind::Score => self.best_score > other.best_score,
+            RecordKind::Chain => self.longest_chain > other.longest_chain,
+            RecordKind::Survival => self.longest_survival > other.longest_survival,
+        }
+    }
+
+    fn merge(&mut self, run: &Record) {
+        self.best_score = self.best_score.max(run.best_score);
+        self.longest_chain = self.longest_chain.max(run.longest_chain);
+        self.longest_survival = self.longest_survival.max(run.longest_survival);
+    }
+}
+
+#[derive(Resource, Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecordStore {
+    records: BTreeMap<String, Record>,
+    #[serde(skip)]
+    dirty: bool,
+}
+
+impl RecordStore {
+    pub fn get(&self, key: RecordKey) -> Option<Record> {
+        self.records.get(&key.to_string()).copied()
+    }
+
+    fn merge(&mut self, key: RecordKey, run: &Record) {
+        let entry = self.records.entry(key.to_string()).or_default();
+        let before = *entry;
+        entry.merge(run);
+        self.dirty |= *entry != before;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(profiles: &Profiles) -> Self {
+        let path = profiles.dir().join(RECORDS_FILE);
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|err| {
+                warn!("ignoring unreadable records {}: {err}", path.display());
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn load(_profiles: &Profiles) -> Self {
+        Self::default()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save(&mut self, profiles: &Profiles) {
+        if !self.dirty {
+            return;
+        }
+        self.dirty = false;
+        let path = profiles.dir().join(RECORDS_FILE);
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let Ok(contents) = serde_json::to_string_pretty(self) else {
+            return;
+        };
+        if let Err(err) = std::fs::write(&path, contents) {
+            warn!("could not save records to {}: {err}", path.display());
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn save(&mut self, _profiles: &Profiles) {
+        self.dirty = false;
+    }
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct RecordBroken(pub RecordKind);
+
+// The records as they stood when the current run began, so each one is
+// announced once, the moment the run passes it.
+#[derive(Resource, Default)]
+struct RecordRun {
+    key: Option<RecordKey>,
+    standing: Option<Record>,
+    elapsed: f32,
+    broken: Vec<RecordKind>,
+}
+
+fn track_records(
+    rules: Res<MatchRules>,
+    mode: Res<GameMode>,
+    players: Res<Players>,
+    mut store: ResMut<RecordStore>,
+    mut run: ResMut<RecordRun>,
+    mut broken: EventWriter<RecordBroken>,
+) {
+    let player = &players.p1;
+    let key = RecordKey::for_rules(&rules, *mode, player.grid.width, player.grid.height);
+    if run.key != key || player.elapsed < run.elapsed {
+        *run = RecordRun {
+            key,
+            standing: key.and_then(|key| store.get(key)),
+            ..Default::default()
+        };
+    }
+    run.elapsed = player.elapsed;
+    let Some(key) = key else {
+        return;
+    };
+    let current = Record {
+        best_score: player.score,
+        longest_chain: player.best_chain,
+        longest_survival: if key.tracks_survival() {
+            player.elapsed
+        } else {
+            0.0
+        },
+    };
+    if let Some(standing) = run.standing {
+        for kind in RecordKind::ALL {
+            let set = standing.beats(&Record::default(), kind);
+            if set && current.beats(&standing, kind) && !run.broken.contains(&kind) {
+                run.broken.push(kind);
+                broken.send(RecordBroken(kind));
+            }
+        }
+    }
+    store.merge(key, &current);
+}
+
+fn save_on_result(
+    mut results: EventReader<MatchResultEvent>,
+    profiles: Res<Profiles>,
+    mut store: ResMut<RecordStore>,
+) {
+    if results.read().count() > 0 {
+        store.save(&profiles);
+    }
+}
+
+fn save_records(profiles: Res<Profiles>, mut store: ResMut<RecordStore>) {
+    store.save(&profiles);
+}
+
+#[derive(Component)]
+struct RecordBanner {
+    timer: Timer,
+}
+
+fn spawn_banners(
+    mut commands: Commands,
+    mut events: EventReader<RecordBroken>,
+    palette: Res<Palette>,
+    locale: Res<Locale>,
+    banners: Query<(), With<RecordBanner>>,
+) {
+    for (stacked, RecordBroken(kind)) in (banners.iter().count()..).zip(events.read()) {
+        commands.spawn((
+            TextBundle::from_section(
+                locale.format("records.new", &[("record", &locale.get(kind.label_key()))]),
+                TextStyle {
+                    font: Default::default(),
+                    font_size: 26.0,
+                    color: palette.accent,
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(16.0 + stacked as f32 * 34.0),
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                ..Default::default()
+            })
+            .with_text_justify(JustifyText::Center),
+            RecordBanner {
+                timer: Timer::from_seconds(BANNER_SECONDS, TimerMode::Once),
+            },
+            GameEntity,
+        ));
+    }
+}
+
+fn update_banners(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut banners: Query<(Entity, &mut RecordBanner)>,
+) {
+    for (entity, mut banner) in &mut banners {
+        if banner.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+#[derive(Component)]
+struct TitleRecord;
+
+fn record_line(record: &Record, key: RecordKey, locale: &Locale) -> String {
+    let mut line = locale.format(
+        "records.line",
+        &[
+            ("score", &record.best_score),
+            ("chain", &record.longest_chain),
+        ],
+    );
+    if key.tracks_survival() {
+        line.push_str(&locale.format(
+            "records.time",
+            &[("time", &format!("{:.1}", record.longest_survival))],
+        ));
+    }
+    line
+}
+
+#[allow(clippy::too_many_arguments)]
+fn show_title_record(
+    mut commands: Commands,
+    menus: Query<&Menu<MenuItem>>,
+    root: Option<Res<MenuRoot>>,
+    rules: Res<MatchRules>,
+    store: Res<RecordStore>,
+    palette: Res<Palette>,
+    locale: Res<Locale>,
+    mut lines: Query<&mut Text, With<TitleRecord>>,
+) {
+    let Some(root) = root else {
+        return;
+    };
+    let key = match menus
+        .iter()
+        .find_map(|menu| menu.selected())
+        .map(|entry| entry.id)
+    {
+        Some(MenuItem::Solo(solo)) => RecordKey::for_rules(
+            &MatchRules { solo, ..*rules },
+            GameMode::OnePlayer,
+            GRID_W,
+            GRID_H,
+        ),
+        _ => None,
+    };
+    let value = match key {
+        Some(key) => match store.get(key) {
+            Some(record) => record_line(&record, key, &locale),
+            None => locale.get("records.none").to_string(),
+        },
+        None => String::new(),
+    };
+    let Ok(mut text) = lines.get_single_mut() else {
+        commands.entity(root.0).with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: Default::default(),
+                        font_size: 18.0,
+                        color: palette.accent,
+                    },
+                )
+                .with_text_justify(JustifyText::Center),
+                TitleRecord,
+            ));
+        });
+        return;
+    };
+    if text.sections[0].value != value {
+        text.sections[0].value = value;
+    }
+}
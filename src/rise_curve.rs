@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+const FALLBACK_INTERVAL: f32 = 2.5;
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct RiseStep {
+    pub interval: f32,
+    pub rows: u32,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RiseCurve {
+    pub levels: Vec<RiseStep>,
+}
+
+impl RiseCurve {
+    fn step(&self, level: u32) -> Option<RiseStep> {
+        let index = (level as usize).min(self.levels.len().checked_sub(1)?);
+        Some(self.levels[index])
+    }
+
+    pub fn interval(&self, level: u32) -> f32 {
+        self.step(level)
+            .map_or(FALLBACK_INTERVAL, |step| step.interval.max(f32::EPSILON))
+    }
+
+    pub fn rows_per_level(&self, level: u32) -> u32 {
+        self.step(level).map_or(u32::MAX, |step| step.rows.max(1))
+    }
+}
@@ -0,0 +1,137 @@
+//! Score formula for block clears.
+//!
+//! Every clear is worth `points_per_block` for each block removed. On top of
+//! that two bonuses are added:
+//!
+//! * **Combo bonus** — clearing 4 or more blocks at once awards the value from
+//!   the `combo` table (indexed by block count, starting at 4); beyond the table
+//!   the bonus keeps growing by `combo_step` per extra block.
+//! * **Chain bonus** — the n-th link of a chain (n >= 2) awards the value from
+//!   the `chain` table; past the table each link doubles the previous bonus,
+//!   capped at `chain_cap`.
+//!
+//! `score = cleared * points_per_block + combo_bonus(cleared) + chain_bonus(chain)`
+//!
+//! The tables live in the `scoring` section of `assets/tuning.ron`.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ScoreBreakdown {
+    pub base: u32,
+    pub combo: u32,
+    pub chain: u32,
+}
+
+impl ScoreBreakdown {
+    pub fn total(self) -> u32 {
+        self.base + self.combo + self.chain
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ScoreTables {
+    pub points_per_block: u32,
+    pub combo: Vec<u32>,
+    pub combo_step: u32,
+    pub chain: Vec<u32>,
+    pub chain_cap: u32,
+}
+
+impl ScoreTables {
+    pub fn combo_bonus(&self, cleared: u32) -> u32 {
+        if cleared < 4 {
+            return 0;
+        }
+        let idx = (cleared - 4) as usize;
+        match self.combo.get(idx) {
+            Some(bonus) => *bonus,
+            None => {
+                let last = self.combo.last().copied().unwrap_or(0);
+                let extra = (idx + 1 - self.combo.len()) as u32;
+                last + extra * self.combo_step
+            }
+        }
+    }
+
+    pub fn chain_bonus(&self, chain: u32) -> u32 {
+        if chain < 2 {
+            return 0;
+        }
+        let idx = (chain - 2) as usize;
+        match self.chain.get(idx) {
+            Some(bonus) => *bonus,
+            None => {
+                let last = self.chain.last().copied().unwrap_or(0);
+                let doublings = (idx + 1 - self.chain.len()) as u32;
+                last.saturating_mul(2u32.saturating_pow(doublings))
+                    .min(self.chain_cap)
+            }
+        }
+    }
+
+    pub fn score_clear(&self, cleared: u32, chain: u32) -> ScoreBreakdown {
+        if cleared == 0 {
+            return ScoreBreakdown::default();
+        }
+        ScoreBreakdown {
+            base: cleared * self.points_per_block,
+            combo: self.combo_bonus(cleared),
+            chain: self.chain_bonus(chain),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tuning::Tuning;
+
+    fn tables() -> ScoreTables {
+        Tuning::builtin().scoring.clone()
+    }
+
+    #[test]
+    fn plain_three_match_scores_base_only() {
+        let score = tables().score_clear(3, 1);
+        assert_eq!(score.base, 30);
+        assert_eq!(score.combo, 0);
+        assert_eq!(score.chain, 0);
+        assert_eq!(score.total(), 30);
+    }
+
+    #[test]
+    fn combo_bonus_follows_table() {
+        let tables = tables();
+        assert_eq!(tables.combo_bonus(3), 0);
+        assert_eq!(tables.combo_bonus(4), 20);
+        assert_eq!(tables.combo_bonus(5), 30);
+        assert_eq!(tables.combo_bonus(6), 50);
+        assert_eq!(tables.combo_bonus(13), 210);
+        assert_eq!(tables.combo_bonus(14), 250);
+        assert_eq!(tables.combo_bonus(15), 290);
+    }
+
+    #[test]
+    fn chain_bonus_grows_exponentially_past_table() {
+        let tables = tables();
+        assert_eq!(tables.chain_bonus(0), 0);
+        assert_eq!(tables.chain_bonus(1), 0);
+        assert_eq!(tables.chain_bonus(2), 50);
+        assert_eq!(tables.chain_bonus(7), 1200);
+        assert_eq!(tables.chain_bonus(8), 2400);
+        assert_eq!(tables.chain_bonus(9), 4800);
+        assert_eq!(tables.chain_bonus(64), tables.chain_cap);
+    }
+
+    #[test]
+    fn combo_within_chain_adds_both_bonuses() {
+        let score = tables().score_clear(4, 3);
+        assert_eq!(score.total(), 40 + 20 + 80);
+    }
+
+    #[test]
+    fn empty_clear_scores_nothing() {
+        assert_eq!(tables().score_clear(0, 5).total(), 0);
+    }
+}
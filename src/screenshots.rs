@@ -0,0 +1,70 @@
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use bevy::render::view::screenshot::ScreenshotManager;
+use bevy::window::PrimaryWindow;
+
+use crate::ResultsMenu;
+use crate::settings::{Settings, data_dir};
+
+const SCREENSHOT_DIR: &str = "screenshots";
+
+pub struct ScreenshotsPlugin;
+
+impl Plugin for ScreenshotsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (take_screenshot, capture_results));
+    }
+}
+
+fn take_screenshot(
+    keys: Res<ButtonInput<KeyCode>>,
+    window: Query<Entity, With<PrimaryWindow>>,
+    manager: ResMut<ScreenshotManager>,
+) {
+    if keys.just_pressed(KeyCode::F12) {
+        save_screenshot(&window, manager);
+    }
+}
+
+fn capture_results(
+    settings: Res<Settings>,
+    added: Query<(), Added<ResultsMenu>>,
+    window: Query<Entity, With<PrimaryWindow>>,
+    manager: ResMut<ScreenshotManager>,
+) {
+    if settings.auto_screenshot && !added.is_empty() {
+        save_screenshot(&window, manager);
+    }
+}
+
+fn save_screenshot(
+    window: &Query<Entity, With<PrimaryWindow>>,
+    mut manager: ResMut<ScreenshotManager>,
+) {
+    let Ok(window) = window.get_single() else {
+        return;
+    };
+    let dir = data_dir().join(SCREENSHOT_DIR);
+    if let Err(err) = std::fs::create_dir_all(&dir) {
+        warn!("could not create {}: {err}", dir.display());
+        return;
+    }
+    let path = screenshot_path(dir);
+    match manager.save_screenshot_to_disk(window, &path) {
+        Ok(()) => info!("saving screenshot to {}", path.display()),
+        Err(err) => warn!("could not take screenshot: {err}"),
+    }
+}
+
+fn screenshot_path(dir: PathBuf) -> PathBuf {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    dir.join(format!(
+        "tetanus-{}-{:03}.png",
+        now.as_secs(),
+        now.subsec_millis()
+    ))
+}
@@ -0,0 +1,734 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use bevy::window::{PrimaryWindow, WindowMode};
+use serde::{Deserialize, Serialize};
+
+use crate::achievements::{Achievements, palette_requirement, speed_requirement};
+use crate::cli::LaunchOptions;
+use crate::locale::{Language, Locale};
+use crate::menu::{Menu, MenuEntry, MenuEvent, MenuPlugin, MenuSet};
+use crate::palette::{Palette, PaletteKind};
+use crate::profiles::Profiles;
+use crate::{AppState, GRID_H, GRID_W, MatchRules, PanelSide, PlayerId};
+
+#[cfg(not(target_arch = "wasm32"))]
+const SETTINGS_FILE: &str = "settings.json";
+#[cfg(not(target_arch = "wasm32"))]
+const DATA_DIR_ENV: &str = "TETANUS_DATA_DIR";
+const RESOLUTIONS: &[(u32, u32)] = &[(1280, 720), (1600, 900), (1920, 1080), (2560, 1440)];
+const UI_SCALES: &[u32] = &[75, 100, 125, 150, 175, 200];
+pub const START_SPEEDS: &[u32] = &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+const DASH_CELLS: &[u32] = &[0, 2, 3, 4];
+const HOLD_SWAP_MS: &[u32] = &[0, 150, 250, 400];
+const BOMB_PERCENTS: &[u32] = &[0, 2, 5];
+const SOLO_SPEEDS: &[u32] = &[50, 60, 70, 80, 90, 100];
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        let settings = Settings::load(app.world().resource::<Profiles>());
+        app.insert_resource(Palette::new(settings.palette))
+            .insert_resource(ClearColor(Palette::new(settings.palette).background))
+            .insert_resource(Locale::new(settings.language))
+            .insert_resource(settings)
+            .add_plugins(MenuPlugin::<OptionsItem>::default())
+            .add_systems(OnEnter(AppState::Options), setup_options)
+            .add_systems(OnExit(AppState::Options), cleanup_options)
+            .add_systems(Update, toggle_fullscreen)
+            .add_systems(
+                Update,
+                (handle_options_input, refresh_options_text)
+                    .chain()
+                    .after(MenuSet)
+                    .run_if(in_state(AppState::Options)),
+            )
+            .add_systems(PreStartup, (apply_palette, apply_start_speed))
+            .add_systems(
+                PostUpdate,
+                (apply_window_settings, apply_ui_scale, apply_language)
+                    .run_if(resource_changed::<Settings>),
+            )
+            .add_systems(
+                PostUpdate,
+                (apply_palette, apply_start_speed)
+                    .run_if(resource_changed::<Settings>.or_else(resource_changed::<Achievements>)),
+            );
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DisplayMode {
+    #[default]
+    Windowed,
+    Borderless,
+    Fullscreen,
+}
+
+impl DisplayMode {
+    const ALL: [DisplayMode; 3] = [
+        DisplayMode::Windowed,
+        DisplayMode::Borderless,
+        DisplayMode::Fullscreen,
+    ];
+
+    fn label_key(self) -> &'static str {
+        match self {
+            DisplayMode::Windowed => "display.windowed",
+            DisplayMode::Borderless => "display.borderless",
+            DisplayMode::Fullscreen => "display.fullscreen",
+        }
+    }
+
+    fn window_mode(self) -> WindowMode {
+        match self {
+            DisplayMode::Windowed => WindowMode::Windowed,
+            DisplayMode::Borderless => WindowMode::BorderlessFullscreen,
+            DisplayMode::Fullscreen => WindowMode::Fullscreen,
+        }
+    }
+}
+
+// A mirrored board is drawn right to left, with left and right input swapped to
+// match; the simulation never sees the difference.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BoardLayout {
+    pub mirrored: bool,
+    pub panel: PanelSide,
+}
+
+impl BoardLayout {
+    const ALL: [BoardLayout; 4] = [
+        BoardLayout::new(false, PanelSide::Right),
+        BoardLayout::new(false, PanelSide::Left),
+        BoardLayout::new(true, PanelSide::Right),
+        BoardLayout::new(true, PanelSide::Left),
+    ];
+
+    const fn new(mirrored: bool, panel: PanelSide) -> Self {
+        Self { mirrored, panel }
+    }
+}
+
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub display: DisplayMode,
+    pub resolution: (u32, u32),
+    pub ui_scale_percent: u32,
+    pub reduced_motion: bool,
+    pub palette: PaletteKind,
+    pub language: Language,
+    pub start_speed: u32,
+    pub dash_cells: u32,
+    pub grab_cursor: bool,
+    pub hold_swap_ms: u32,
+    pub sticky_cursor: bool,
+    pub adaptive_difficulty: bool,
+    pub solo_speed_percent: u32,
+    pub bomb_percent: u32,
+    pub auto_screenshot: bool,
+    pub p1_board: BoardLayout,
+    pub p2_board: BoardLayout,
+    pub split_screen: bool,
+    pub chain_spectacle: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            display: DisplayMode::Windowed,
+            resolution: RESOLUTIONS[0],
+            ui_scale_percent: 100,
+            reduced_motion: false,
+            palette: PaletteKind::Standard,
+            language: Language::English,
+            start_speed: 0,
+            dash_cells: 0,
+            grab_cursor: false,
+            hold_swap_ms: 0,
+            sticky_cursor: false,
+            adaptive_difficulty: false,
+            solo_speed_percent: 100,
+            bomb_percent: 0,
+            auto_screenshot: false,
+            p1_board: BoardLayout::new(false, PanelSide::Right),
+            p2_board: BoardLayout::new(false, PanelSide::Left),
+            split_screen: false,
+            chain_spectacle: false,
+        }
+    }
+}
+
+impl Settings {
+    pub fn ui_scale(&self) -> f32 {
+        let first = UI_SCALES[0];
+        let last = UI_SCALES[UI_SCALES.len() - 1];
+        self.ui_scale_percent.clamp(first, last) as f32 / 100.0
+    }
+
+    pub fn dash_step(&self) -> i32 {
+        match self.dash_cells {
+            0 => GRID_W.max(GRID_H) as i32,
+            cells => cells as i32,
+        }
+    }
+
+    pub fn hold_swap_seconds(&self) -> Option<f32> {
+        (self.hold_swap_ms > 0).then(|| self.hold_swap_ms as f32 / 1000.0)
+    }
+
+    pub fn solo_time_scale(&self) -> f32 {
+        let first = SOLO_SPEEDS[0];
+        let last = SOLO_SPEEDS[SOLO_SPEEDS.len() - 1];
+        self.solo_speed_percent.clamp(first, last) as f32 / 100.0
+    }
+
+    pub fn board_layout(&self, id: PlayerId) -> BoardLayout {
+        match id {
+            PlayerId::P1 => self.p1_board,
+            PlayerId::P2 => self.p2_board,
+        }
+    }
+
+    fn board_layout_mut(&mut self, id: PlayerId) -> &mut BoardLayout {
+        match id {
+            PlayerId::P1 => &mut self.p1_board,
+            PlayerId::P2 => &mut self.p2_board,
+        }
+    }
+
+    pub fn bomb_rate(&self) -> f64 {
+        self.bomb_percent.min(100) as f64 / 100.0
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(profiles: &Profiles) -> Self {
+        let path = profiles.dir().join(SETTINGS_FILE);
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|err| {
+                warn!(
+                    "ignoring unreadable settings file {}: {err}",
+                    path.display()
+                );
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn load(_profiles: &Profiles) -> Self {
+        Self::default()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self, profiles: &Profiles) {
+        let path = profiles.dir().join(SETTINGS_FILE);
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let Ok(contents) = serde_json::to_string_pretty(self) else {
+            return;
+        };
+        if let Err(err) = std::fs::write(&path, contents) {
+            warn!("could not save settings to {}: {err}", path.display());
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn save(&self, _profiles: &Profiles) {}
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn data_dir() -> PathBuf {
+    std::env::var_os(DATA_DIR_ENV)
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".tetanus-attack"))
+        })
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+pub fn alt_held(keys: &ButtonInput<KeyCode>) -> bool {
+    keys.any_pressed([KeyCode::AltLeft, KeyCode::AltRight])
+}
+
+fn apply_window_settings(
+    settings: Res<Settings>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    let Ok(mut window) = windows.get_single_mut() else {
+        return;
+    };
+    window.mode = settings.display.window_mode();
+    let (width, height) = settings.resolution;
+    window.resolution.set(width as f32, height as f32);
+}
+
+fn apply_ui_scale(settings: Res<Settings>, mut ui_scale: ResMut<UiScale>) {
+    ui_scale.0 = settings.ui_scale();
+}
+
+fn apply_palette(
+    settings: Res<Settings>,
+    achievements: Res<Achievements>,
+    mut palette: ResMut<Palette>,
+    mut clear_color: ResMut<ClearColor>,
+) {
+    let kind = if achievements.meets(palette_requirement(settings.palette)) {
+        settings.palette
+    } else {
+        PaletteKind::Standard
+    };
+    *palette = Palette::new(kind);
+    clear_color.0 = palette.background;
+}
+
+fn apply_start_speed(
+    settings: Res<Settings>,
+    achievements: Res<Achievements>,
+    options: Res<LaunchOptions>,
+    mut rules: ResMut<MatchRules>,
+) {
+    if options.speed.is_some() {
+        return;
+    }
+    rules.start_level = if achievements.meets(speed_requirement(settings.start_speed)) {
+        settings.start_speed
+    } else {
+        0
+    };
+}
+
+fn apply_language(settings: Res<Settings>, mut locale: ResMut<Locale>) {
+    *locale = Locale::new(settings.language);
+}
+
+fn toggle_fullscreen(
+    keys: Res<ButtonInput<KeyCode>>,
+    profiles: Res<Profiles>,
+    mut settings: ResMut<Settings>,
+) {
+    if !(alt_held(&keys) && keys.just_pressed(KeyCode::Enter)) {
+        return;
+    }
+    settings.display = match settings.display {
+        DisplayMode::Windowed => DisplayMode::Borderless,
+        _ => DisplayMode::Windowed,
+    };
+    settings.save(&profiles);
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum OptionsItem {
+    Display,
+    Resolution,
+    UiScale,
+    ReducedMotion,
+    Palette,
+    StartSpeed,
+    AdaptiveDifficulty,
+    SoloSpeed,
+    CursorDash,
+    GrabCursor,
+    HoldSwap,
+    StickyCursor,
+    Bombs,
+    Board(PlayerId),
+    SplitScreen,
+    ChainSpectacle,
+    #[cfg(not(target_arch = "wasm32"))]
+    AutoScreenshot,
+    Language,
+}
+
+impl OptionsItem {
+    const ALL: &'static [OptionsItem] = &[
+        OptionsItem::Display,
+        OptionsItem::Resolution,
+        OptionsItem::UiScale,
+        OptionsItem::ReducedMotion,
+        OptionsItem::Palette,
+        OptionsItem::StartSpeed,
+        OptionsItem::AdaptiveDifficulty,
+        OptionsItem::SoloSpeed,
+        OptionsItem::CursorDash,
+        OptionsItem::GrabCursor,
+        OptionsItem::HoldSwap,
+        OptionsItem::StickyCursor,
+        OptionsItem::Bombs,
+        OptionsItem::Board(PlayerId::P1),
+        OptionsItem::Board(PlayerId::P2),
+        OptionsItem::SplitScreen,
+        OptionsItem::ChainSpectacle,
+        #[cfg(not(target_arch = "wasm32"))]
+        OptionsItem::AutoScreenshot,
+        OptionsItem::Language,
+    ];
+}
+
+#[derive(Resource)]
+struct OptionsScreen {
+    root: Entity,
+    title: Entity,
+    hint: Entity,
+}
+
+fn setup_options(mut commands: Commands, palette: Res<Palette>, locale: Res<Locale>) {
+    let root = commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(0.0),
+                top: Val::Percent(0.0),
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(16.0),
+                ..Default::default()
+            },
+            background_color: BackgroundColor(palette.overlay),
+            ..Default::default()
+        })
+        .id();
+
+    let mut title = Entity::PLACEHOLDER;
+    let mut hint = Entity::PLACEHOLDER;
+    commands.entity(root).with_children(|parent| {
+        title = parent
+            .spawn(TextBundle {
+                text: Text::from_section(
+                    locale.get("options.title"),
+                    TextStyle {
+                        font: Default::default(),
+                        font_size: 42.0,
+                        color: palette.heading,
+                    },
+                ),
+                ..Default::default()
+            })
+            .id();
+
+        let entries = OptionsItem::ALL
+            .iter()
+            .map(|item| MenuEntry::new(*item, ""))
+            .collect();
+        parent.spawn((
+            TextBundle {
+                text: Text::default().with_justify(JustifyText::Center),
+                ..Default::default()
+            },
+            Menu::new(entries),
+        ));
+
+        hint = parent
+            .spawn(TextBundle {
+                text: Text::from_section(
+                    locale.get("options.hint"),
+                    TextStyle {
+                        font: Default::default(),
+                        font_size: 18.0,
+                        color: palette.text,
+                    },
+                )
+                .with_justify(JustifyText::Center),
+                ..Default::default()
+            })
+            .id();
+    });
+
+    commands.insert_resource(OptionsScreen { root, title, hint });
+}
+
+fn cleanup_options(mut commands: Commands, screen: Res<OptionsScreen>) {
+    commands.entity(screen.root).despawn_recursive();
+    commands.remove_resource::<OptionsScreen>();
+}
+
+pub fn cycle<T: Copy + PartialEq>(values: &[T], current: T, step: isize) -> T {
+    let index = values
+        .iter()
+        .position(|value| *value == current)
+        .unwrap_or(0);
+    values[(index as isize + step).rem_euclid(values.len() as isize) as usize]
+}
+
+fn handle_options_input(
+    mut events: EventReader<MenuEvent<OptionsItem>>,
+    profiles: Res<Profiles>,
+    mut settings: ResMut<Settings>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for event in events.read() {
+        let (item, step) = match *event {
+            MenuEvent::Adjust(item, step) => (item, step),
+            MenuEvent::Confirm(item) => (item, 1),
+            MenuEvent::Cancel => {
+                next_state.set(AppState::Title);
+                continue;
+            }
+        };
+        match item {
+            OptionsItem::Display => {
+                settings.display = cycle(&DisplayMode::ALL, settings.display, step);
+            }
+            OptionsItem::Resolution => {
+                settings.resolution = cycle(RESOLUTIONS, settings.resolution, step);
+            }
+            OptionsItem::UiScale => {
+                settings.ui_scale_percent = cycle(UI_SCALES, settings.ui_scale_percent, step);
+            }
+            OptionsItem::ReducedMotion => settings.reduced_motion = !settings.reduced_motion,
+            OptionsItem::Palette => {
+                settings.palette = cycle(&PaletteKind::ALL, settings.palette, step);
+            }
+            OptionsItem::StartSpeed => {
+                settings.start_speed = cycle(START_SPEEDS, settings.start_speed, step);
+            }
+            OptionsItem::AdaptiveDifficulty => {
+                settings.adaptive_difficulty = !settings.adaptive_difficulty;
+            }
+            OptionsItem::SoloSpeed => {
+                settings.solo_speed_percent = cycle(SOLO_SPEEDS, settings.solo_speed_percent, step);
+            }
+            OptionsItem::CursorDash => {
+                settings.dash_cells = cycle(DASH_CELLS, settings.dash_cells, step);
+            }
+            OptionsItem::GrabCursor => settings.grab_cursor = !settings.grab_cursor,
+            OptionsItem::HoldSwap => {
+                settings.hold_swap_ms = cycle(HOLD_SWAP_MS, settings.hold_swap_ms, step);
+            }
+            OptionsItem::StickyCursor => settings.sticky_cursor = !settings.sticky_cursor,
+            OptionsItem::Bombs => {
+                settings.bomb_percent = cycle(BOMB_PERCENTS, settings.bomb_percent, step);
+            }
+            OptionsItem::Board(id) => {
+                let layout = settings.board_layout_mut(id);
+                *layout = cycle(&BoardLayout::ALL, *layout, step);
+            }
+            OptionsItem::SplitScreen => settings.split_screen = !settings.split_screen,
+            OptionsItem::ChainSpectacle => settings.chain_spectacle = !settings.chain_spectacle,
+            #[cfg(not(target_arch = "wasm32"))]
+            OptionsItem::AutoScreenshot => settings.auto_screenshot = !settings.auto_screenshot,
+            OptionsItem::Language => {
+                settings.language = cycle(&Language::ALL, settings.language, step);
+            }
+        }
+        settings.save(&profiles);
+    }
+}
+
+fn option_label(
+    item: OptionsItem,
+    settings: &Settings,
+    achievements: &Achievements,
+    locale: &Locale,
+) -> (String, bool) {
+    let label = match item {
+        OptionsItem::Display => locale.format(
+            "options.display",
+            &[("value", &locale.get(settings.display.label_key()))],
+        ),
+        OptionsItem::Resolution => {
+            let (width, height) = settings.resolution;
+            locale.format(
+                "options.resolution",
+                &[("width", &width), ("height", &height)],
+            )
+        }
+        OptionsItem::UiScale => {
+            locale.format("options.ui_scale", &[("value", &settings.ui_scale_percent)])
+        }
+        OptionsItem::ReducedMotion => locale.format(
+            "options.reduced_motion",
+            &[(
+                "value",
+                &locale.get(if settings.reduced_motion {
+                    "options.on"
+                } else {
+                    "options.off"
+                }),
+            )],
+        ),
+        OptionsItem::Palette => locale.format(
+            "options.palette",
+            &[("value", &locale.get(settings.palette.label_key()))],
+        ),
+        OptionsItem::StartSpeed => {
+            locale.format("options.start_speed", &[("value", &settings.start_speed)])
+        }
+        OptionsItem::AdaptiveDifficulty => locale.format(
+            "options.adaptive",
+            &[(
+                "value",
+                &locale.get(if settings.adaptive_difficulty {
+                    "options.on"
+                } else {
+                    "options.off"
+                }),
+            )],
+        ),
+        OptionsItem::SoloSpeed => locale.format(
+            "options.solo_speed",
+            &[("value", &settings.solo_speed_percent)],
+        ),
+        OptionsItem::CursorDash => match settings.dash_cells {
+            0 => locale.get("options.dash_edge").to_string(),
+            cells => locale.format("options.dash_cells", &[("value", &cells)]),
+        },
+        OptionsItem::GrabCursor => locale.format(
+            "options.cursor",
+            &[(
+                "value",
+                &locale.get(if settings.grab_cursor {
+                    "options.cursor_grab"
+                } else {
+                    "options.cursor_pair"
+                }),
+            )],
+        ),
+        OptionsItem::HoldSwap => {
+            let value = match settings.hold_swap_ms {
+                0 => locale.get("options.off").to_string(),
+                ms => locale.format("options.hold_swap_ms", &[("value", &ms)]),
+            };
+            locale.format("options.hold_swap", &[("value", &value)])
+        }
+        OptionsItem::StickyCursor => locale.format(
+            "options.sticky_cursor",
+            &[(
+                "value",
+                &locale.get(if settings.sticky_cursor {
+                    "options.on"
+                } else {
+                    "options.off"
+                }),
+            )],
+        ),
+        OptionsItem::Bombs => match settings.bomb_percent {
+            0 => locale.format("options.bombs", &[("value", &locale.get("options.off"))]),
+            percent => locale.format("options.bombs", &[("value", &format!("{percent}%"))]),
+        },
+        OptionsItem::Board(id) => {
+            let layout = settings.board_layout(id);
+            locale.format(
+                "options.board",
+                &[
+                    ("player", &id.label()),
+                    (
+                        "board",
+                        &locale.get(if layout.mirrored {
+                            "options.board_mirrored"
+                        } else {
+                            "options.board_standard"
+                        }),
+                    ),
+                    (
+                        "panel",
+                        &locale.get(match layout.panel {
+                            PanelSide::Left => "options.panel_left",
+                            PanelSide::Right => "options.panel_right",
+                        }),
+                    ),
+                ],
+            )
+        }
+        OptionsItem::SplitScreen => locale.format(
+            "options.split_screen",
+            &[(
+                "value",
+                &locale.get(if settings.split_screen {
+                    "options.on"
+                } else {
+                    "options.off"
+                }),
+            )],
+        ),
+        OptionsItem::ChainSpectacle => locale.format(
+            "options.chain_spectacle",
+            &[(
+                "value",
+                &locale.get(if settings.chain_spectacle {
+                    "options.on"
+                } else {
+                    "options.off"
+                }),
+            )],
+        ),
+        #[cfg(not(target_arch = "wasm32"))]
+        OptionsItem::AutoScreenshot => locale.format(
+            "options.auto_screenshot",
+            &[(
+                "value",
+                &locale.get(if settings.auto_screenshot {
+                    "options.on"
+                } else {
+                    "options.off"
+                }),
+            )],
+        ),
+        OptionsItem::Language => {
+            locale.format("options.language", &[("value", &settings.language.label())])
+        }
+    };
+    let requirement = match item {
+        OptionsItem::Palette => palette_requirement(settings.palette),
+        OptionsItem::StartSpeed => speed_requirement(settings.start_speed),
+        _ => None,
+    };
+    match requirement {
+        Some(achievement) if !achievements.has(achievement) => (
+            locale.format(
+                "options.locked",
+                &[
+                    ("option", &label),
+                    ("requirement", &achievement.name(locale)),
+                ],
+            ),
+            false,
+        ),
+        _ => (label, true),
+    }
+}
+
+fn refresh_options_text(
+    screen: Res<OptionsScreen>,
+    settings: Res<Settings>,
+    achievements: Res<Achievements>,
+    locale: Res<Locale>,
+    mut menus: Query<&mut Menu<OptionsItem>>,
+    mut text_query: Query<&mut Text>,
+) {
+    for (entity, key) in [
+        (screen.title, "options.title"),
+        (screen.hint, "options.hint"),
+    ] {
+        if let Ok(mut text) = text_query.get_mut(entity) {
+            text.sections[0].value = locale.get(key).to_string();
+        }
+    }
+    for mut menu in &mut menus {
+        let labels: Vec<_> = menu
+            .entries()
+            .iter()
+            .map(|entry| option_label(entry.id, &settings, &achievements, &locale))
+            .collect();
+        let stale = menu
+            .entries()
+            .iter()
+            .zip(&labels)
+            .any(|(entry, (label, enabled))| entry.label != *label || entry.enabled != *enabled);
+        if !stale {
+            continue;
+        }
+        for (entry, (label, enabled)) in menu.entries_mut().iter_mut().zip(labels) {
+            entry.label = label;
+            entry.enabled = enabled;
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::settings::Settings;
+use crate::split::BoardCamera;
+use crate::{
+    AppState, CELL_SIZE, GameEntity, GameMode, GameSet, PlayerId, PlayerViews, Players,
+    active_players, fit_camera_to_window, player_view, update_panel_layout,
+};
+
+const SPECTACLE_CHAIN: u32 = 5;
+const SPECTACLE_SECONDS: f32 = 1.6;
+const SLOW_SPEED: f32 = 0.35;
+// Share of the show spent at full slow-mo before easing back to normal speed.
+const SLOW_HOLD: f32 = 0.6;
+const SPECTACLE_ZOOM: f32 = 1.25;
+const FLASH_ALPHA: f32 = 0.6;
+const FLASH_SHARE: f32 = 0.3;
+
+// When a x5+ chain finishes, presentation slows down and the camera leans in on
+// the board that made it. Only virtual time slows: the simulation and input
+// repeat undo the slowdown, so neither board plays any slower.
+pub struct SpectaclePlugin;
+
+impl Plugin for SpectaclePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Spectacle>()
+            .add_systems(OnExit(AppState::Game), end_spectacle)
+            .add_systems(
+                Update,
+                (start_spectacle, run_spectacle)
+                    .chain()
+                    .after(fit_camera_to_window)
+                    .before(update_panel_layout)
+                    .in_set(GameSet::Presentation),
+            );
+    }
+}
+
+// Virtual time without the spectacle's slowdown, for anything that has to keep
+// real pace.
+pub fn unscaled(time: &Time<Virtual>) -> Duration {
+    match time.relative_speed() {
+        speed if speed > 0.0 && speed != 1.0 => time.delta().div_f32(speed),
+        _ => time.delta(),
+    }
+}
+
+struct Show {
+    player: PlayerId,
+    focus: Vec2,
+    started: f32,
+    flash: Option<Entity>,
+}
+
+#[derive(Resource, Default)]
+pub struct Spectacle {
+    chains: [u32; 2],
+    show: Option<Show>,
+    amount: f32,
+}
+
+impl Spectacle {
+    pub fn zoom(&self, player: PlayerId) -> f32 {
+        match &self.show {
+            Some(show) if show.player == player => 1.0 + (SPECTACLE_ZOOM - 1.0) * self.amount,
+            _ => 1.0,
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn start_spectacle(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    mode: Res<GameMode>,
+    players: Res<Players>,
+    views: Res<PlayerViews>,
+    real: Res<Time<Real>>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut spectacle: ResMut<Spectacle>,
+) {
+    for id in active_players(*mode) {
+        let player = players.get(*id);
+        let last = std::mem::replace(&mut spectacle.chains[*id as usize], player.chain_index);
+        if !settings.chain_spectacle || player.chain_index != 0 || last < SPECTACLE_CHAIN {
+            continue;
+        }
+        let Some(view) = player_view(&views, *id) else {
+            continue;
+        };
+        if let Some(flash) = spectacle
+            .show
+            .as_ref()
+            .and_then(|show| commands.get_entity(show.flash?))
+        {
+            flash.despawn_recursive();
+        }
+        let size = Vec2::new(player.grid.width as f32, player.grid.height as f32) * CELL_SIZE;
+        let flash = (!settings.reduced_motion).then(|| {
+            commands
+                .spawn((
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color: Color::WHITE.with_alpha(FLASH_ALPHA),
+                            custom_size: Some(size),
+                            ..Default::default()
+                        },
+                        transform: Transform::from_translation(view.origin.extend(4.0)),
+                        ..Default::default()
+                    },
+                    GameEntity,
+                ))
+                .id()
+        });
+        spectacle.show = Some(Show {
+            player: *id,
+            focus: view.origin,
+            started: real.elapsed_seconds(),
+            flash,
+        });
+        virtual_time.set_relative_speed(SLOW_SPEED);
+    }
+}
+
+// The window camera is refitted every frame, so the zoom only has to be applied
+// on top of that. Split screen cameras pick it up through Spectacle::zoom.
+#[allow(clippy::too_many_arguments)]
+fn run_spectacle(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    real: Res<Time<Real>>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut spectacle: ResMut<Spectacle>,
+    board_cameras: Query<(), With<BoardCamera>>,
+    mut cameras: Query<(&mut OrthographicProjection, &mut Transform), Without<BoardCamera>>,
+    mut sprites: Query<&mut Sprite>,
+) {
+    let Some(show) = &spectacle.show else {
+        return;
+    };
+    let t = (real.elapsed_seconds() - show.started) / SPECTACLE_SECONDS;
+    if t >= 1.0 {
+        finish(
+            &mut commands,
+            &mut virtual_time,
+            &mut spectacle,
+            &mut cameras,
+        );
+        return;
+    }
+    let speed = if t < SLOW_HOLD {
+        SLOW_SPEED
+    } else {
+        SLOW_SPEED + (1.0 - SLOW_SPEED) * (t - SLOW_HOLD) / (1.0 - SLOW_HOLD)
+    };
+    virtual_time.set_relative_speed(speed);
+    let amount = if settings.reduced_motion {
+        0.0
+    } else {
+        (t * std::f32::consts::PI).sin()
+    };
+    if let Some(mut sprite) = show.flash.and_then(|flash| sprites.get_mut(flash).ok()) {
+        sprite
+            .color
+            .set_alpha(FLASH_ALPHA * (1.0 - t / FLASH_SHARE).max(0.0));
+    }
+    if board_cameras.is_empty() {
+        let zoom = 1.0 + (SPECTACLE_ZOOM - 1.0) * amount;
+        for (mut projection, mut transform) in &mut cameras {
+            projection.scale /= zoom;
+            transform.translation.x = show.focus.x * amount;
+            transform.translation.y = show.focus.y * amount;
+        }
+    }
+    spectacle.amount = amount;
+}
+
+fn end_spectacle(
+    mut commands: Commands,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut spectacle: ResMut<Spectacle>,
+    mut cameras: Query<(&mut OrthographicProjection, &mut Transform), Without<BoardCamera>>,
+) {
+    spectacle.chains = [0; 2];
+    if spectacle.show.is_some() {
+        finish(
+            &mut commands,
+            &mut virtual_time,
+            &mut spectacle,
+            &mut cameras,
+        );
+    }
+}
+
+fn finish(
+    commands: &mut Commands,
+    virtual_time: &mut Time<Virtual>,
+    spectacle: &mut Spectacle,
+    cameras: &mut Query<(&mut OrthographicProjection, &mut Transform), Without<BoardCamera>>,
+) {
+    if let Some(flash) = spectacle
+        .show
+        .take()
+        .and_then(|show| commands.get_entity(show.flash?))
+    {
+        flash.despawn_recursive();
+    }
+    spectacle.amount = 0.0;
+    virtual_time.set_relative_speed(1.0);
+    for (_, mut transform) in cameras {
+        transform.translation.x = 0.0;
+        transform.translation.y = 0.0;
+    }
+}
@@ -0,0 +1,255 @@
+use std::io::ErrorKind;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use serde::Serialize;
+use tungstenite::handshake::MidHandshake;
+use tungstenite::handshake::server::{NoCallback, ServerHandshake};
+use tungstenite::protocol::WebSocketConfig;
+use tungstenite::{HandshakeError, Message, WebSocket};
+
+use crate::{
+    ClearEvent, GameMode, GameSet, GarbageCancelEvent, MatchOver, PlayerId, Players, active_players,
+};
+
+const SPECTATE_ADDR_ENV: &str = "TETANUS_SPECTATE_ADDR";
+const SNAPSHOT_SECONDS: f32 = 0.1;
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+// Snapshots go out ten times a second, so a spectator this far behind has
+// stopped reading and is dropped rather than buffered without end.
+const MAX_WRITE_BUFFER: usize = 256 * 1024;
+
+pub struct SpectatePlugin;
+
+impl Plugin for SpectatePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, start_spectator_server)
+            .add_systems(
+                Update,
+                broadcast_match
+                    .in_set(GameSet::Presentation)
+                    .run_if(resource_exists::<SpectatorServer>),
+            );
+    }
+}
+
+// A spectator whose handshake is still waiting on the client. It is retried
+// every frame without blocking and dropped once HANDSHAKE_TIMEOUT runs out.
+struct PendingClient {
+    peer: SocketAddr,
+    handshake: MidHandshake<ServerHandshake<TcpStream, NoCallback>>,
+    started: Instant,
+}
+
+#[derive(Resource)]
+pub struct SpectatorServer {
+    listener: TcpListener,
+    pending: Vec<PendingClient>,
+    clients: Vec<WebSocket<TcpStream>>,
+    snapshot_timer: Timer,
+}
+
+impl SpectatorServer {
+    pub fn bind(addr: &str) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            listener,
+            pending: Vec::new(),
+            clients: Vec::new(),
+            snapshot_timer: Timer::from_seconds(SNAPSHOT_SECONDS, TimerMode::Repeating),
+        })
+    }
+
+    fn accept_pending(&mut self) {
+        for client in std::mem::take(&mut self.pending) {
+            if client.started.elapsed() > HANDSHAKE_TIMEOUT {
+                warn!("spectator handshake with {} timed out", client.peer);
+                continue;
+            }
+            self.continue_handshake(client.peer, client.handshake.handshake(), client.started);
+        }
+        loop {
+            match self.listener.accept() {
+                Ok((stream, peer)) => {
+                    if let Err(err) = stream.set_nonblocking(true) {
+                        warn!("spectator handshake with {peer} failed: {err}");
+                        continue;
+                    }
+                    let config = WebSocketConfig {
+                        write_buffer_size: 0,
+                        max_write_buffer_size: MAX_WRITE_BUFFER,
+                        ..Default::default()
+                    };
+                    let handshake = tungstenite::accept_with_config(stream, Some(config));
+                    self.continue_handshake(peer, handshake, Instant::now());
+                }
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                Err(err) => {
+                    warn!("spectator accept failed: {err}");
+                    break;
+                }
+            }
+        }
+    }
+
+    fn continue_handshake(
+        &mut self,
+        peer: SocketAddr,
+        result: Result<
+            WebSocket<TcpStream>,
+            HandshakeError<ServerHandshake<TcpStream, NoCallback>>,
+        >,
+        started: Instant,
+    ) {
+        match result {
+            Ok(socket) => {
+                info!("spectator connected from {peer}");
+                self.clients.push(socket);
+            }
+            Err(HandshakeError::Interrupted(handshake)) => self.pending.push(PendingClient {
+                peer,
+                handshake,
+                started,
+            }),
+            Err(HandshakeError::Failure(err)) => {
+                warn!("spectator handshake with {peer} failed: {err}")
+            }
+        }
+    }
+
+    fn broadcast(&mut self, message: &SpectatorMessage) {
+        if self.clients.is_empty() {
+            return;
+        }
+        let Ok(payload) = serde_json::to_string(message) else {
+            return;
+        };
+        self.clients
+            .retain_mut(|socket| match socket.send(Message::text(payload.clone())) {
+                Ok(()) => true,
+                Err(tungstenite::Error::Io(err)) if err.kind() == ErrorKind::WouldBlock => true,
+                Err(tungstenite::Error::WriteBufferFull(_)) => {
+                    if let Ok(peer) = socket.get_ref().peer_addr() {
+                        warn!("spectator {peer} fell too far behind, dropping");
+                    }
+                    false
+                }
+                Err(_) => false,
+            });
+    }
+
+    fn drain_incoming(&mut self) {
+        self.clients.retain_mut(|socket| {
+            loop {
+                match socket.read() {
+                    Ok(Message::Close(_)) => break false,
+                    Ok(_) => continue,
+                    Err(tungstenite::Error::Io(err)) if err.kind() == ErrorKind::WouldBlock => {
+                        break true;
+                    }
+                    Err(_) => break false,
+                }
+            }
+        });
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum SpectatorMessage {
+    Snapshot {
+        match_over: bool,
+        winner: Option<&'static str>,
+        boards: Vec<BoardSnapshot>,
+    },
+    Clear {
+        player: &'static str,
+        chain: u32,
+        points: u32,
+    },
+    Cancel {
+        units: u32,
+    },
+}
+
+#[derive(Serialize)]
+struct BoardSnapshot {
+    player: &'static str,
+    width: usize,
+    height: usize,
+    rows: Vec<String>,
+    cursor: [usize; 2],
+    score: u32,
+    elapsed: f32,
+    chain: u32,
+    incoming: u32,
+    outgoing: u32,
+}
+
+fn start_spectator_server(mut commands: Commands) {
+    let Ok(addr) = std::env::var(SPECTATE_ADDR_ENV) else {
+        return;
+    };
+    match SpectatorServer::bind(&addr) {
+        Ok(server) => {
+            info!("spectator server listening on ws://{addr}");
+            commands.insert_resource(server);
+        }
+        Err(err) => error!("could not start spectator server on {addr}: {err}"),
+    }
+}
+
+fn broadcast_match(
+    time: Res<Time>,
+    mut server: ResMut<SpectatorServer>,
+    players: Res<Players>,
+    mode: Res<GameMode>,
+    match_over: Res<MatchOver>,
+    mut clear_events: EventReader<ClearEvent>,
+    mut cancel_events: EventReader<GarbageCancelEvent>,
+) {
+    server.accept_pending();
+    server.drain_incoming();
+
+    for event in clear_events.read() {
+        server.broadcast(&SpectatorMessage::Clear {
+            player: event.player.label(),
+            chain: event.chain,
+            points: event.score.total(),
+        });
+    }
+    for event in cancel_events.read() {
+        server.broadcast(&SpectatorMessage::Cancel { units: event.units });
+    }
+
+    if !server.snapshot_timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let boards = active_players(*mode)
+        .iter()
+        .map(|id| board_snapshot(*id, &players))
+        .collect();
+    server.broadcast(&SpectatorMessage::Snapshot {
+        match_over: match_over.active,
+        winner: match_over.winner.map(PlayerId::label),
+        boards,
+    });
+}
+
+fn board_snapshot(id: PlayerId, players: &Players) -> BoardSnapshot {
+    let player = players.get(id);
+    BoardSnapshot {
+        player: id.label(),
+        width: player.grid.width,
+        height: player.grid.height,
+        rows: player.grid.to_rows(),
+        cursor: [player.cursor.x, player.cursor.y],
+        score: player.score,
+        elapsed: player.elapsed,
+        chain: player.chain_index,
+        incoming: player.garbage_incoming(),
+        outgoing: player.garbage_outgoing,
+    }
+}
@@ -0,0 +1,195 @@
+use bevy::prelude::*;
+
+use crate::AppState;
+use crate::locale::Locale;
+use crate::menu::{Menu, MenuEntry, MenuEvent, MenuInput, MenuPlugin, MenuSet};
+use crate::palette::Palette;
+
+const SPLASH_SECONDS: f32 = 2.5;
+const SPLASH_FADE_SECONDS: f32 = 0.6;
+
+pub struct SplashPlugin;
+
+impl Plugin for SplashPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(MenuPlugin::<CreditsItem>::default())
+            .add_systems(OnEnter(AppState::Splash), setup_splash)
+            .add_systems(OnExit(AppState::Splash), cleanup_splash)
+            .add_systems(Update, update_splash.run_if(in_state(AppState::Splash)))
+            .add_systems(OnEnter(AppState::Credits), setup_credits)
+            .add_systems(OnExit(AppState::Credits), cleanup_credits)
+            .add_systems(
+                Update,
+                handle_credits_input
+                    .after(MenuSet)
+                    .run_if(in_state(AppState::Credits)),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum CreditsItem {
+    Back,
+}
+
+#[derive(Resource)]
+struct SplashScreen {
+    root: Entity,
+    texts: Vec<Entity>,
+    timer: Timer,
+}
+
+fn setup_splash(mut commands: Commands, palette: Res<Palette>, locale: Res<Locale>) {
+    let root = commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(0.0),
+                top: Val::Percent(0.0),
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(16.0),
+                ..Default::default()
+            },
+            background_color: BackgroundColor(palette.overlay),
+            ..Default::default()
+        })
+        .id();
+
+    let mut texts = Vec::new();
+    commands.entity(root).with_children(|parent| {
+        for (text, font_size, color) in [
+            (locale.get("title"), 64.0, palette.heading),
+            (locale.get("splash.tagline"), 20.0, palette.text),
+        ] {
+            let id = parent
+                .spawn(TextBundle::from_section(
+                    text,
+                    TextStyle {
+                        font: Default::default(),
+                        font_size,
+                        color: color.with_alpha(0.0),
+                    },
+                ))
+                .id();
+            texts.push(id);
+        }
+    });
+
+    commands.insert_resource(SplashScreen {
+        root,
+        texts,
+        timer: Timer::from_seconds(SPLASH_SECONDS, TimerMode::Once),
+    });
+}
+
+fn cleanup_splash(mut commands: Commands, screen: Res<SplashScreen>) {
+    commands.entity(screen.root).despawn_recursive();
+    commands.remove_resource::<SplashScreen>();
+}
+
+fn update_splash(
+    input: MenuInput,
+    time: Res<Time>,
+    mut screen: ResMut<SplashScreen>,
+    mut text_query: Query<&mut Text>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if screen.timer.tick(time.delta()).finished() || input.any_pressed() {
+        next_state.set(AppState::Title);
+        return;
+    }
+    let elapsed = screen.timer.elapsed_secs();
+    let alpha = (elapsed / SPLASH_FADE_SECONDS)
+        .min((SPLASH_SECONDS - elapsed) / SPLASH_FADE_SECONDS)
+        .clamp(0.0, 1.0);
+    for entity in &screen.texts {
+        if let Ok(mut text) = text_query.get_mut(*entity) {
+            let color = text.sections[0].style.color;
+            text.sections[0].style.color = color.with_alpha(alpha);
+        }
+    }
+}
+
+#[derive(Resource)]
+struct CreditsRoot(Entity);
+
+fn setup_credits(mut commands: Commands, palette: Res<Palette>, locale: Res<Locale>) {
+    let root = commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(0.0),
+                top: Val::Percent(0.0),
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(16.0),
+                ..Default::default()
+            },
+            background_color: BackgroundColor(palette.overlay),
+            ..Default::default()
+        })
+        .id();
+
+    commands.entity(root).with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            locale.get("credits.title"),
+            TextStyle {
+                font: Default::default(),
+                font_size: 42.0,
+                color: palette.heading,
+            },
+        ));
+
+        parent.spawn(TextBundle {
+            text: Text::from_section(
+                locale.get("credits.body"),
+                TextStyle {
+                    font: Default::default(),
+                    font_size: 20.0,
+                    color: palette.text,
+                },
+            )
+            .with_justify(JustifyText::Center),
+            ..Default::default()
+        });
+
+        parent.spawn((
+            TextBundle {
+                text: Text::default().with_justify(JustifyText::Center),
+                ..Default::default()
+            },
+            Menu::new(vec![MenuEntry::new(
+                CreditsItem::Back,
+                locale.get("credits.back"),
+            )]),
+        ));
+    });
+
+    commands.insert_resource(CreditsRoot(root));
+}
+
+fn cleanup_credits(mut commands: Commands, root: Res<CreditsRoot>) {
+    commands.entity(root.0).despawn_recursive();
+    commands.remove_resource::<CreditsRoot>();
+}
+
+fn handle_credits_input(
+    mut events: EventReader<MenuEvent<CreditsItem>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for event in events.read() {
+        match event {
+            MenuEvent::Confirm(CreditsItem::Back) | MenuEvent::Cancel => {
+                next_state.set(AppState::Title)
+            }
+            MenuEvent::Adjust(..) => {}
+        }
+    }
+}
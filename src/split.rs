@@ -0,0 +1,239 @@
+use bevy::prelude::*;
+use bevy::render::camera::{ClearColorConfig, Viewport};
+use bevy::render::view::RenderLayers;
+use bevy::window::PrimaryWindow;
+
+use crate::settings::Settings;
+use crate::spectacle::Spectacle;
+use crate::{
+    AppState, BoardArrangement, CELL_SIZE, FRAME_THICKNESS, GRID_H, GameEntity, GameMode, GameSet,
+    PlayerId, PlayerView, PlayerViews, Players, content_size, fit_scale, position_panel,
+    stack_in_danger, update_panel_layout,
+};
+
+const DANGER_ZOOM: f32 = 1.12;
+const ZOOM_RATE: f32 = 4.0;
+// Nothing is drawn on this layer, so the window camera keeps only the UI.
+const UI_ONLY_LAYER: usize = 31;
+
+// With split screen on, every board gets its own camera and viewport, fitted to
+// that board alone, and the window camera is left to draw the UI on top.
+pub struct SplitScreenPlugin;
+
+impl Plugin for SplitScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Title), restore_window_camera)
+            .add_systems(
+                Update,
+                (sync_board_cameras, place_board_cameras)
+                    .chain()
+                    .after(update_panel_layout)
+                    .in_set(GameSet::Presentation),
+            );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Board {
+    P1,
+    Second,
+}
+
+#[derive(Component)]
+pub struct BoardCamera {
+    board: Board,
+    zoom: f32,
+}
+
+#[derive(Component)]
+struct SplitHost;
+
+fn boards(views: &PlayerViews) -> Vec<(Board, &PlayerView)> {
+    let second = views.p2.as_ref().or(views.ghost.as_ref());
+    std::iter::once((Board::P1, &views.p1))
+        .chain(second.map(|view| (Board::Second, view)))
+        .collect()
+}
+
+fn sync_board_cameras(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    mode: Res<GameMode>,
+    views: Res<PlayerViews>,
+    board_cameras: Query<(Entity, &BoardCamera)>,
+    mut window_cameras: Query<(Entity, &mut Camera, Has<SplitHost>), Without<BoardCamera>>,
+) {
+    let wanted = if settings.split_screen && views.layout_mode(*mode) == GameMode::TwoPlayer {
+        boards(&views)
+    } else {
+        Vec::new()
+    };
+    let mut current: Vec<Board> = board_cameras
+        .iter()
+        .map(|(_, camera)| camera.board)
+        .collect();
+    current.sort();
+    if current == wanted.iter().map(|(board, _)| *board).collect::<Vec<_>>() {
+        return;
+    }
+    for (entity, _) in &board_cameras {
+        commands.entity(entity).despawn_recursive();
+    }
+    for (entity, mut camera, host) in &mut window_cameras {
+        if wanted.is_empty() {
+            if !host {
+                continue;
+            }
+            camera.clear_color = ClearColorConfig::Default;
+            commands
+                .entity(entity)
+                .remove::<(RenderLayers, SplitHost)>();
+        } else {
+            camera.clear_color = ClearColorConfig::None;
+            commands
+                .entity(entity)
+                .insert((RenderLayers::layer(UI_ONLY_LAYER), SplitHost));
+        }
+    }
+    let count = wanted.len();
+    for (order, (board, view)) in wanted.into_iter().enumerate() {
+        commands.spawn((
+            Camera2dBundle {
+                camera: Camera {
+                    order: order as isize - count as isize,
+                    clear_color: if order == 0 {
+                        ClearColorConfig::Default
+                    } else {
+                        ClearColorConfig::None
+                    },
+                    ..Default::default()
+                },
+                transform: Transform::from_translation(view.origin.extend(999.9)),
+                ..Default::default()
+            },
+            BoardCamera { board, zoom: 1.0 },
+            GameEntity,
+        ));
+    }
+}
+
+// Panes follow the boards' own arrangement: boards are read top to bottom, left
+// to right, and fill a grid of panes in the same order.
+fn panes(count: usize, arrangement: BoardArrangement) -> (u32, u32) {
+    let count = count.max(1) as u32;
+    let long = count.div_ceil((count as f32).sqrt() as u32);
+    let short = count.div_ceil(long);
+    match arrangement {
+        BoardArrangement::SideBySide => (long, short),
+        BoardArrangement::Stacked => (short, long),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn place_board_cameras(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    time: Res<Time>,
+    mode: Res<GameMode>,
+    settings: Res<Settings>,
+    players: Res<Players>,
+    views: Res<PlayerViews>,
+    spectacle: Res<Spectacle>,
+    ui_scale: Res<UiScale>,
+    mut cameras: Query<(
+        &mut BoardCamera,
+        &mut Camera,
+        &mut OrthographicProjection,
+        &mut Transform,
+    )>,
+    mut style_query: Query<&mut Style>,
+) {
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let physical = UVec2::new(window.physical_width(), window.physical_height());
+    if cameras.is_empty() || physical.x == 0 || physical.y == 0 {
+        return;
+    }
+    let mut boards = boards(&views);
+    boards.sort_by(|(_, a), (_, b)| {
+        b.origin
+            .y
+            .total_cmp(&a.origin.y)
+            .then(a.origin.x.total_cmp(&b.origin.x))
+    });
+    let (cols, rows) = panes(boards.len(), views.arrangement);
+    let pane = UVec2::new(physical.x / cols, physical.y / rows);
+    let logical = pane.as_vec2() / window.scale_factor();
+    let fit = fit_scale(
+        content_size(
+            GameMode::OnePlayer,
+            views.layout_panel_width(),
+            views.arrangement,
+        ),
+        logical,
+    );
+    let panel_h = GRID_H as f32 * CELL_SIZE + FRAME_THICKNESS * 2.0;
+    for (slot, (board, view)) in boards.into_iter().enumerate() {
+        let Some((mut board_camera, mut camera, mut projection, mut transform)) = cameras
+            .iter_mut()
+            .find(|(camera, ..)| camera.board == board)
+        else {
+            continue;
+        };
+        let player = match (board, *mode) {
+            (Board::P1, _) => Some(PlayerId::P1),
+            (Board::Second, GameMode::TwoPlayer) => Some(PlayerId::P2),
+            (Board::Second, GameMode::OnePlayer) => None,
+        };
+        let danger = player.is_some_and(|id| stack_in_danger(&players.get(id).grid));
+        let target = if danger && !settings.reduced_motion {
+            DANGER_ZOOM
+        } else {
+            1.0
+        };
+        board_camera.zoom +=
+            (target - board_camera.zoom) * (time.delta_seconds() * ZOOM_RATE).min(1.0);
+        let scale = fit / (board_camera.zoom * player.map_or(1.0, |id| spectacle.zoom(id)));
+        if projection.scale != scale {
+            projection.scale = scale;
+        }
+        let corner = UVec2::new(slot as u32 % cols, slot as u32 / cols) * pane;
+        let viewport = Viewport {
+            physical_position: corner,
+            physical_size: pane,
+            ..Default::default()
+        };
+        if camera
+            .viewport
+            .as_ref()
+            .map(|current| (current.physical_position, current.physical_size))
+            != Some((viewport.physical_position, viewport.physical_size))
+        {
+            camera.viewport = Some(viewport);
+        }
+        transform.translation.x = view.origin.x;
+        transform.translation.y = view.origin.y;
+        let screen_center = (corner.as_vec2() + pane.as_vec2() / 2.0) / window.scale_factor();
+        position_panel(
+            view,
+            screen_center,
+            view.origin,
+            scale,
+            ui_scale.0,
+            panel_h,
+            &mut style_query,
+        );
+    }
+}
+
+fn restore_window_camera(
+    mut commands: Commands,
+    mut cameras: Query<(Entity, &mut Camera), With<SplitHost>>,
+) {
+    for (entity, mut camera) in &mut cameras {
+        camera.clear_color = ClearColorConfig::Default;
+        commands
+            .entity(entity)
+            .remove::<(RenderLayers, SplitHost)>();
+    }
+}
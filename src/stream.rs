@@ -0,0 +1,273 @@
+use std::collections::HashMap;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use bevy::prelude::*;
+use rand::random;
+
+use crate::cli::LaunchOptions;
+use crate::locale::Locale;
+use crate::palette::Palette;
+use crate::{
+    CANCEL_POPUP_SECONDS, CELL_SIZE, GRID_H, GameMode, GameSet, LocalControl, MatchOver,
+    PlayerViews, Players, active_players, apply_launch_options, player_view, spawn_floating_text,
+};
+
+const DEFAULT_SERVER: &str = "irc.chat.twitch.tv:6667";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+const VOTE_SECONDS: f32 = 30.0;
+const WARNING_SECONDS: f32 = 3.0;
+const AUDIENCE_GARBAGE_UNITS: u32 = 6;
+const SPEED_BURST_SECONDS: f32 = 5.0;
+
+pub struct StreamPlugin;
+
+impl Plugin for StreamPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<AudienceEvent>()
+            .add_systems(Startup, connect_audience.after(apply_launch_options))
+            .add_systems(Update, read_chat.run_if(resource_exists::<Audience>))
+            .add_systems(
+                Update,
+                (run_audience_votes, spawn_audience_popups)
+                    .chain()
+                    .in_set(GameSet::Presentation)
+                    .run_if(resource_exists::<Audience>),
+            );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum AudienceAction {
+    Garbage,
+    SpeedBurst,
+}
+
+impl AudienceAction {
+    fn from_command(message: &str) -> Option<Self> {
+        match message.split_whitespace().next()? {
+            "!garbage" => Some(AudienceAction::Garbage),
+            "!speed" => Some(AudienceAction::SpeedBurst),
+            _ => None,
+        }
+    }
+
+    fn label_key(self) -> &'static str {
+        match self {
+            AudienceAction::Garbage => "audience.garbage",
+            AudienceAction::SpeedBurst => "audience.speed",
+        }
+    }
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+struct AudienceEvent {
+    action: AudienceAction,
+    landed: bool,
+}
+
+#[derive(Resource)]
+struct Audience {
+    stream: TcpStream,
+    inbound: Vec<u8>,
+    closed: bool,
+    votes: HashMap<String, AudienceAction>,
+    vote_timer: Timer,
+    pending: Option<(AudienceAction, Timer)>,
+}
+
+impl Audience {
+    fn send(&mut self, line: &str) {
+        if self
+            .stream
+            .write_all(format!("{line}\r\n").as_bytes())
+            .is_err()
+        {
+            self.closed = true;
+        }
+    }
+
+    fn tally(&mut self) -> Option<AudienceAction> {
+        let votes = std::mem::take(&mut self.votes);
+        let count = |action| votes.values().filter(|vote| **vote == action).count();
+        let (garbage, speed) = (
+            count(AudienceAction::Garbage),
+            count(AudienceAction::SpeedBurst),
+        );
+        match (garbage, speed) {
+            (0, 0) => None,
+            (garbage, speed) if speed > garbage => Some(AudienceAction::SpeedBurst),
+            _ => Some(AudienceAction::Garbage),
+        }
+    }
+}
+
+fn connect_audience(mut commands: Commands, options: Res<LaunchOptions>) {
+    let Some(channel) = &options.audience else {
+        return;
+    };
+    let server = options.audience_server.as_deref().unwrap_or(DEFAULT_SERVER);
+    let stream = server
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut addrs| addrs.next())
+        .ok_or_else(|| format!("could not resolve {server}"))
+        .and_then(|addr| {
+            TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT).map_err(|err| err.to_string())
+        });
+    let stream = match stream {
+        Ok(stream) => stream,
+        Err(err) => {
+            warn!("audience mode disabled: {err}");
+            return;
+        }
+    };
+    let mut audience = Audience {
+        stream,
+        inbound: Vec::new(),
+        closed: false,
+        votes: HashMap::new(),
+        vote_timer: Timer::from_seconds(VOTE_SECONDS, TimerMode::Repeating),
+        pending: None,
+    };
+    // Anonymous Twitch logins are any justinfan nick with no password.
+    audience.send(&format!("NICK justinfan{}", random::<u32>() % 100_000));
+    audience.send(&format!(
+        "JOIN #{}",
+        channel.trim_start_matches('#').to_lowercase()
+    ));
+    if audience.stream.set_nonblocking(true).is_err() {
+        warn!("audience mode disabled: could not read {server} without blocking");
+        return;
+    }
+    info!("audience mode listening to #{channel} on {server}");
+    commands.insert_resource(audience);
+}
+
+fn read_chat(mut audience: ResMut<Audience>) {
+    let mut buf = [0u8; 4096];
+    while !audience.closed {
+        match audience.stream.read(&mut buf) {
+            Ok(0) => audience.closed = true,
+            Ok(read) => audience.inbound.extend_from_slice(&buf[..read]),
+            Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+            Err(_) => audience.closed = true,
+        }
+    }
+    while let Some(end) = audience.inbound.iter().position(|byte| *byte == b'\n') {
+        let line: Vec<u8> = audience.inbound.drain(..=end).collect();
+        let line = String::from_utf8_lossy(&line);
+        let line = line.trim_end();
+        if let Some(token) = line.strip_prefix("PING ") {
+            audience.send(&format!("PONG {token}"));
+            continue;
+        }
+        if let Some((nick, message)) = parse_privmsg(line)
+            && let Some(action) = AudienceAction::from_command(message)
+        {
+            audience.votes.insert(nick.to_string(), action);
+        }
+    }
+}
+
+fn parse_privmsg(line: &str) -> Option<(&str, &str)> {
+    let (prefix, rest) = line.strip_prefix(':')?.split_once(' ')?;
+    let (_, message) = rest.strip_prefix("PRIVMSG ")?.split_once(" :")?;
+    let nick = prefix.split('!').next()?;
+    Some((nick, message))
+}
+
+// Votes only count towards local matches; a network match has to stay identical on
+// both ends, so the audience sits those out.
+fn run_audience_votes(
+    time: Res<Time>,
+    mode: Res<GameMode>,
+    local: Res<LocalControl>,
+    match_over: Res<MatchOver>,
+    mut audience: ResMut<Audience>,
+    mut players: ResMut<Players>,
+    mut events: EventWriter<AudienceEvent>,
+) {
+    if match_over.active || local.0.is_some() {
+        return;
+    }
+    let delta = time.delta();
+    if audience.vote_timer.tick(delta).just_finished()
+        && audience.pending.is_none()
+        && let Some(action) = audience.tally()
+    {
+        audience.pending = Some((
+            action,
+            Timer::from_seconds(WARNING_SECONDS, TimerMode::Once),
+        ));
+        events.send(AudienceEvent {
+            action,
+            landed: false,
+        });
+    }
+    let Some((action, timer)) = &mut audience.pending else {
+        return;
+    };
+    if !timer.tick(delta).finished() {
+        return;
+    }
+    let action = *action;
+    audience.pending = None;
+    for id in active_players(*mode) {
+        let player = players.get_mut(*id);
+        match action {
+            AudienceAction::Garbage => player.garbage_queue.push_back(AUDIENCE_GARBAGE_UNITS),
+            AudienceAction::SpeedBurst => player.speed_burst = SPEED_BURST_SECONDS,
+        }
+    }
+    events.send(AudienceEvent {
+        action,
+        landed: true,
+    });
+}
+
+fn spawn_audience_popups(
+    mut commands: Commands,
+    mut events: EventReader<AudienceEvent>,
+    mode: Res<GameMode>,
+    views: Res<PlayerViews>,
+    palette: Res<Palette>,
+    locale: Res<Locale>,
+) {
+    let grid_h = GRID_H as f32 * CELL_SIZE;
+    for event in events.read() {
+        let action = locale.get(event.action.label_key());
+        let (value, color) = if event.landed {
+            (
+                locale.format("popup.audience", &[("action", &action)]),
+                palette.popup_counter,
+            )
+        } else {
+            (
+                locale.format(
+                    "popup.audience_incoming",
+                    &[("action", &action), ("seconds", &WARNING_SECONDS)],
+                ),
+                palette.warning,
+            )
+        };
+        for id in active_players(*mode) {
+            let Some(view) = player_view(&views, *id) else {
+                continue;
+            };
+            spawn_floating_text(
+                &mut commands,
+                value.clone(),
+                Vec3::new(
+                    view.origin.x,
+                    view.origin.y + grid_h / 2.0 - CELL_SIZE * 3.0,
+                    5.0,
+                ),
+                color,
+                24.0,
+                CANCEL_POPUP_SECONDS * 2.0,
+            );
+        }
+    }
+}
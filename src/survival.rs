@@ -0,0 +1,42 @@
+use bevy::prelude::*;
+
+use crate::{GameMode, MatchOver, MatchRules, Players, SoloMode};
+
+const FIRST_WAVE_SECONDS: f32 = 15.0;
+const WAVE_INTERVAL_SECONDS: f32 = 16.0;
+const WAVE_INTERVAL_FACTOR: f32 = 0.92;
+const MIN_WAVE_INTERVAL_SECONDS: f32 = 5.0;
+const FIRST_WAVE_UNITS: u32 = 3;
+const WAVE_UNITS_STEP: u32 = 2;
+const MAX_WAVE_ROWS: u32 = 3;
+
+fn wave_due(wave: u32) -> f32 {
+    FIRST_WAVE_SECONDS
+        + (0..wave)
+            .map(|index| {
+                (WAVE_INTERVAL_SECONDS * WAVE_INTERVAL_FACTOR.powi(index as i32))
+                    .max(MIN_WAVE_INTERVAL_SECONDS)
+            })
+            .sum::<f32>()
+}
+
+fn wave_units(wave: u32, width: usize) -> u32 {
+    (FIRST_WAVE_UNITS + wave * WAVE_UNITS_STEP).min(width as u32 * MAX_WAVE_ROWS)
+}
+
+pub fn drop_survival_waves(
+    mut players: ResMut<Players>,
+    match_over: Res<MatchOver>,
+    rules: Res<MatchRules>,
+    mode: Res<GameMode>,
+) {
+    if match_over.active || *mode != GameMode::OnePlayer || rules.solo != SoloMode::Survival {
+        return;
+    }
+    let player = &mut players.p1;
+    while player.elapsed >= wave_due(player.waves_dropped) {
+        let units = wave_units(player.waves_dropped, player.grid.width);
+        player.garbage_queue.push_back(units);
+        player.waves_dropped += 1;
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::BTreeSet;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::locale::Locale;
+use crate::menu::MenuInput;
+use crate::palette::Palette;
+use crate::profiles::Profiles;
+use crate::{CpuControl, Feedback, FeedbackEvent, GameEntity, GameMode, GameSet, LocalControl};
+
+#[cfg(not(target_arch = "wasm32"))]
+const TIPS_FILE: &str = "first-time.json";
+const TIP_GRACE_SECONDS: f32 = 1.5;
+
+// One-off explanations shown the first time something happens to a profile.
+pub struct TipsPlugin;
+
+impl Plugin for TipsPlugin {
+    fn build(&self, app: &mut App) {
+        let seen = FirstTimeEvents::load(app.world().resource::<Profiles>());
+        app.insert_resource(seen).add_systems(
+            Update,
+            (show_garbage_tip, dismiss_tips)
+                .chain()
+                .in_set(GameSet::Presentation),
+        );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FirstTime {
+    GarbageReceived,
+}
+
+impl FirstTime {
+    fn id(self) -> &'static str {
+        match self {
+            FirstTime::GarbageReceived => "garbage_received",
+        }
+    }
+}
+
+#[derive(Resource, Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FirstTimeEvents {
+    seen: BTreeSet<String>,
+}
+
+impl FirstTimeEvents {
+    // Returns whether this was the first time, saving the profile's list if so.
+    pub fn mark(&mut self, event: FirstTime, profiles: &Profiles) -> bool {
+        if !self.seen.insert(event.id().to_string()) {
+            return false;
+        }
+        self.save(profiles);
+        true
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(profiles: &Profiles) -> Self {
+        let path = profiles.dir().join(TIPS_FILE);
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|err| {
+                warn!(
+                    "ignoring unreadable first-time list {}: {err}",
+                    path.display()
+                );
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn load(_profiles: &Profiles) -> Self {
+        Self::default()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save(&self, profiles: &Profiles) {
+        let path = profiles.dir().join(TIPS_FILE);
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let Ok(contents) = serde_json::to_string_pretty(self) else {
+            return;
+        };
+        if let Err(err) = std::fs::write(&path, contents) {
+            warn!(
+                "could not save first-time list to {}: {err}",
+                path.display()
+            );
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn save(&self, _profiles: &Profiles) {}
+}
+
+#[derive(Component)]
+struct Tip {
+    seconds: f32,
+}
+
+// The first garbage a person (not the CPU, and on this machine) takes in versus.
+#[allow(clippy::too_many_arguments)]
+fn show_garbage_tip(
+    mut commands: Commands,
+    mut feedback: EventReader<FeedbackEvent>,
+    mode: Res<GameMode>,
+    cpu: Res<CpuControl>,
+    local: Res<LocalControl>,
+    profiles: Res<Profiles>,
+    mut seen: ResMut<FirstTimeEvents>,
+    palette: Res<Palette>,
+    locale: Res<Locale>,
+) {
+    let landed = feedback.read().find(|event| {
+        event.kind == Feedback::GarbageLanded
+            && !cpu.controls(event.player)
+            && local.0.is_none_or(|id| id == event.player)
+    });
+    let Some(event) = landed else {
+        return;
+    };
+    if *mode != GameMode::TwoPlayer || !seen.mark(FirstTime::GarbageReceived, &profiles) {
+        return;
+    }
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Percent(8.0),
+                    width: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            Tip { seconds: 0.0 },
+            GameEntity,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        padding: UiRect::all(Val::Px(10.0)),
+                        max_width: Val::Percent(60.0),
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(6.0),
+                        ..Default::default()
+                    },
+                    background_color: BackgroundColor(palette.pause_overlay),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(
+                        TextBundle::from_section(
+                            locale.format("tips.garbage", &[("player", &event.player.label())]),
+                            TextStyle {
+                                font: Default::default(),
+                                font_size: 20.0,
+                                color: palette.text,
+                            },
+                        )
+                        .with_text_justify(JustifyText::Center),
+                    );
+                    parent.spawn(
+                        TextBundle::from_section(
+                            locale.get("tips.dismiss"),
+                            TextStyle {
+                                font: Default::default(),
+                                font_size: 14.0,
+                                color: palette.heading,
+                            },
+                        )
+                        .with_text_justify(JustifyText::Center),
+                    );
+                });
+        });
+}
+
+// Input during the first moment is still aimed at the board, so it only
+// dismisses the tip once the grace period is over.
+fn dismiss_tips(
+    mut commands: Commands,
+    time: Res<Time>,
+    input: MenuInput,
+    mut tips: Query<(Entity, &mut Tip)>,
+) {
+    for (entity, mut tip) in &mut tips {
+        tip.seconds += time.delta_seconds();
+        if tip.seconds >= TIP_GRACE_SECONDS && input.any_pressed() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -0,0 +1,382 @@
+use std::fmt::Write as _;
+use std::path::Path;
+
+use bevy::prelude::*;
+
+use crate::cli::LaunchOptions;
+use crate::garbage::GarbageRules;
+use crate::tuning::Tuning;
+use crate::{
+    AppState, CommandQueue, CpuControl, GRID_H, GRID_W, GameMode, GameSet, MatchRules, MatchSeed,
+    PlayerCommand, PlayerId, SimClock, apply_launch_options, setup_game,
+};
+
+const TRACE_MAGIC: &str = "TETANUS-TRACE";
+// Bumped whenever the header or the command encoding changes; older traces
+// are refused rather than replayed into a different match.
+const TRACE_VERSION: u32 = 2;
+
+pub struct TracePlugin;
+
+impl Plugin for TracePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, load_trace_from_args.after(apply_launch_options))
+            .add_systems(OnEnter(AppState::Game), schedule_playback.after(setup_game))
+            .add_systems(Update, dump_trace.in_set(GameSet::Presentation));
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TraceEntry {
+    pub frame: u64,
+    pub player: PlayerId,
+    pub command: PlayerCommand,
+}
+
+// Everything a match was started with rides in the header, so replaying a trace
+// rebuilds the same match instead of whatever the player has configured now.
+// `tuning` is the fingerprint of the table the match snapshotted.
+#[derive(Resource, Clone, Debug)]
+pub struct InputTrace {
+    pub seed: u64,
+    pub mode: GameMode,
+    pub rules: MatchRules,
+    pub garbage: GarbageRules,
+    pub board: (usize, usize),
+    pub tuning: u64,
+    pub entries: Vec<TraceEntry>,
+}
+
+impl Default for InputTrace {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            mode: GameMode::OnePlayer,
+            rules: MatchRules::default(),
+            garbage: GarbageRules::default(),
+            board: (GRID_W, GRID_H),
+            tuning: 0,
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl InputTrace {
+    pub fn begin(
+        &mut self,
+        seed: u64,
+        mode: GameMode,
+        rules: MatchRules,
+        garbage: GarbageRules,
+        board: (usize, usize),
+        tuning: u64,
+    ) {
+        self.seed = seed;
+        self.mode = mode;
+        self.rules = rules;
+        self.garbage = garbage;
+        self.board = board;
+        self.tuning = tuning;
+        self.entries.clear();
+    }
+
+    pub fn record(&mut self, frame: u64, player: PlayerId, command: PlayerCommand) {
+        self.entries.push(TraceEntry {
+            frame,
+            player,
+            command,
+        });
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        let mode = match self.mode {
+            GameMode::OnePlayer => "1p",
+            GameMode::TwoPlayer => "2p",
+        };
+        let _ = writeln!(out, "{TRACE_MAGIC} {TRACE_VERSION}");
+        let _ = writeln!(out, "seed {}", self.seed);
+        let _ = writeln!(out, "mode {mode}");
+        let _ = writeln!(out, "board {} {}", self.board.0, self.board.1);
+        let _ = writeln!(out, "tuning {:016x}", self.tuning);
+        let _ = writeln!(
+            out,
+            "rules {}",
+            ron::to_string(&self.rules).unwrap_or_default()
+        );
+        let _ = writeln!(
+            out,
+            "garbage {}",
+            ron::to_string(&self.garbage).unwrap_or_default()
+        );
+        for entry in &self.entries {
+            let _ = writeln!(
+                out,
+                "{} {} {}",
+                entry.frame,
+                entry.player.label(),
+                encode_command(entry.command)
+            );
+        }
+        out
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+        let version = lines
+            .next()
+            .and_then(|line| line.strip_prefix(TRACE_MAGIC))
+            .ok_or_else(|| format!("missing `{TRACE_MAGIC}` header"))?;
+        match version.trim().parse::<u32>() {
+            Ok(TRACE_VERSION) => {}
+            Ok(version) => {
+                return Err(format!(
+                    "trace format v{version} cannot be replayed, this build reads v{TRACE_VERSION}"
+                ));
+            }
+            Err(_) => return Err(format!("unreadable trace version `{}`", version.trim())),
+        }
+        let mut trace = InputTrace::default();
+        let (mut rules, mut garbage) = (None, None);
+        for (index, line) in lines.enumerate() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let bad = || format!("line {}: cannot parse `{line}`", index + 2);
+            match fields.as_slice() {
+                ["seed", seed] => trace.seed = seed.parse().map_err(|_| bad())?,
+                ["mode", "1p"] => trace.mode = GameMode::OnePlayer,
+                ["mode", "2p"] => trace.mode = GameMode::TwoPlayer,
+                ["board", width, height] => {
+                    trace.board = (
+                        width.parse().map_err(|_| bad())?,
+                        height.parse().map_err(|_| bad())?,
+                    );
+                }
+                ["tuning", hash] => {
+                    trace.tuning = u64::from_str_radix(hash, 16).map_err(|_| bad())?;
+                }
+                ["rules", ..] => rules = Some(parse_ron(line, "rules").ok_or_else(bad)?),
+                ["garbage", ..] => garbage = Some(parse_ron(line, "garbage").ok_or_else(bad)?),
+                [frame, player, action] => {
+                    let player = match *player {
+                        "P1" => PlayerId::P1,
+                        "P2" => PlayerId::P2,
+                        _ => return Err(bad()),
+                    };
+                    trace.entries.push(TraceEntry {
+                        frame: frame.parse().map_err(|_| bad())?,
+                        player,
+                        command: decode_command(action).ok_or_else(bad)?,
+                    });
+                }
+                _ => return Err(bad()),
+            }
+        }
+        trace.rules = rules.ok_or("missing `rules` line")?;
+        trace.garbage = garbage.ok_or("missing `garbage` line")?;
+        Ok(trace)
+    }
+}
+
+fn parse_ron<T: serde::de::DeserializeOwned>(line: &str, key: &str) -> Option<T> {
+    ron::from_str(line.strip_prefix(key)?).ok()
+}
+
+fn encode_command(command: PlayerCommand) -> String {
+    match command {
+        PlayerCommand::Move { dx: -1, dy: 0 } => "L".to_string(),
+        PlayerCommand::Move { dx: 1, dy: 0 } => "R".to_string(),
+        PlayerCommand::Move { dx: 0, dy: 1 } => "U".to_string(),
+        PlayerCommand::Move { dx: 0, dy: -1 } => "D".to_string(),
+        PlayerCommand::Move { dx, dy } => format!("M{dx},{dy}"),
+        PlayerCommand::Swap => "S".to_string(),
+        PlayerCommand::MoveTo { x, y } => format!("P{x},{y}"),
+        PlayerCommand::Rotate => "O".to_string(),
+        PlayerCommand::Raise => "^".to_string(),
+    }
+}
+
+fn decode_command(action: &str) -> Option<PlayerCommand> {
+    Some(match action {
+        "L" => PlayerCommand::Move { dx: -1, dy: 0 },
+        "R" => PlayerCommand::Move { dx: 1, dy: 0 },
+        "U" => PlayerCommand::Move { dx: 0, dy: 1 },
+        "D" => PlayerCommand::Move { dx: 0, dy: -1 },
+        "S" => PlayerCommand::Swap,
+        "O" => PlayerCommand::Rotate,
+        "^" => PlayerCommand::Raise,
+        _ => {
+            if let Some((x, y)) = action.strip_prefix('P').and_then(|pos| pos.split_once(',')) {
+                return Some(PlayerCommand::MoveTo {
+                    x: x.parse().ok()?,
+                    y: y.parse().ok()?,
+                });
+            }
+            let (dx, dy) = action.strip_prefix('M')?.split_once(',')?;
+            PlayerCommand::Move {
+                dx: dx.parse().ok()?,
+                dy: dy.parse().ok()?,
+            }
+        }
+    })
+}
+
+#[derive(Resource)]
+pub struct InputPlayback {
+    trace: InputTrace,
+    scheduled: bool,
+}
+
+pub fn read_trace(path: &Path) -> Result<InputTrace, String> {
+    let text = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+    InputTrace::parse(&text)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn load_trace_from_args(
+    mut commands: Commands,
+    options: Res<LaunchOptions>,
+    mut mode: ResMut<GameMode>,
+    mut seed: ResMut<MatchSeed>,
+    mut cpu: ResMut<CpuControl>,
+    mut rules: ResMut<MatchRules>,
+    mut garbage: ResMut<GarbageRules>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let Some(path) = &options.play_inputs else {
+        return;
+    };
+    match read_trace(path) {
+        Ok(trace) => {
+            info!(
+                "playing {} recorded inputs from {} (seed {})",
+                trace.entries.len(),
+                path.display(),
+                trace.seed
+            );
+            *mode = trace.mode;
+            seed.0 = Some(trace.seed);
+            *cpu = CpuControl::default();
+            *rules = trace.rules;
+            *garbage = trace.garbage;
+            commands.insert_resource(InputPlayback {
+                trace,
+                scheduled: false,
+            });
+            next_state.set(AppState::Game);
+        }
+        Err(err) => error!("could not load input trace {}: {err}", path.display()),
+    }
+}
+
+fn schedule_playback(
+    playback: Option<ResMut<InputPlayback>>,
+    mut queue: ResMut<CommandQueue>,
+    tuning: Res<Tuning>,
+) {
+    let Some(mut playback) = playback else {
+        return;
+    };
+    if playback.scheduled {
+        return;
+    }
+    playback.scheduled = true;
+    if playback.trace.tuning != tuning.fingerprint() {
+        warn!("the input trace was recorded with a different tuning table and may diverge");
+    }
+    for entry in &playback.trace.entries {
+        queue
+            .scheduled
+            .entry(entry.frame)
+            .or_default()
+            .push((entry.player, entry.command));
+    }
+}
+
+fn dump_trace(keys: Res<ButtonInput<KeyCode>>, trace: Res<InputTrace>, clock: Res<SimClock>) {
+    if !keys.just_pressed(KeyCode::F9) {
+        return;
+    }
+    let path = format!("tetanus-trace-{}-{}.txt", trace.seed, clock.frame);
+    match std::fs::write(&path, trace.to_text()) {
+        Ok(()) => info!("wrote {} inputs to {path}", trace.entries.len()),
+        Err(err) => warn!("could not write input trace {path}: {err}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SoloMode;
+    use crate::garbage::{CapCurve, GarbagePreset};
+    use crate::versus::VersusRules;
+
+    fn recorded() -> InputTrace {
+        let mut trace = InputTrace::default();
+        trace.begin(
+            42,
+            GameMode::TwoPlayer,
+            MatchRules {
+                start_level: 5,
+                versus: Some(VersusRules {
+                    items: true,
+                    ..Default::default()
+                }),
+                solo: SoloMode::Challenge(2),
+                bomb_rate: 0.25,
+                ..Default::default()
+            },
+            GarbageRules {
+                cap_curve: CapCurve::Soft,
+                ..GarbageRules::from_preset(GarbagePreset::Classic)
+            },
+            (8, 10),
+            Tuning::builtin().fingerprint(),
+        );
+        let commands = [
+            PlayerCommand::Move { dx: -1, dy: 0 },
+            PlayerCommand::Move { dx: 2, dy: -3 },
+            PlayerCommand::MoveTo { x: 4, y: 7 },
+            PlayerCommand::Swap,
+            PlayerCommand::Rotate,
+            PlayerCommand::Raise,
+        ];
+        for (frame, command) in commands.into_iter().enumerate() {
+            trace.record(frame as u64 * 3, PlayerId::P2, command);
+        }
+        trace
+    }
+
+    #[test]
+    fn header_and_inputs_survive_a_roundtrip() {
+        let trace = recorded();
+        let parsed = InputTrace::parse(&trace.to_text()).unwrap();
+        assert_eq!(parsed.seed, trace.seed);
+        assert_eq!(parsed.mode, trace.mode);
+        assert_eq!(parsed.rules, trace.rules);
+        assert_eq!(parsed.garbage, trace.garbage);
+        assert_eq!(parsed.board, trace.board);
+        assert_eq!(parsed.tuning, trace.tuning);
+        assert_eq!(parsed.to_text(), trace.to_text());
+    }
+
+    #[test]
+    fn malformed_traces_are_refused() {
+        let text = recorded().to_text();
+        let older = text.replacen(
+            &format!("{TRACE_MAGIC} {TRACE_VERSION}"),
+            &format!("{TRACE_MAGIC} 1"),
+            1,
+        );
+        assert!(InputTrace::parse(&older).unwrap_err().contains("v1"));
+        assert!(InputTrace::parse("seed 7\nmode 1p").is_err());
+        let without_rules: String = text
+            .lines()
+            .filter(|line| !line.starts_with("rules"))
+            .map(|line| format!("{line}\n"))
+            .collect();
+        assert!(InputTrace::parse(&without_rules).is_err());
+        assert!(InputTrace::parse(&format!("{text}9 P3 S\n")).is_err());
+        assert!(InputTrace::parse(&format!("{text}9 P1 Q\n")).is_err());
+        assert!(InputTrace::parse(&text.replace("rules (", "rules [")).is_err());
+    }
+}
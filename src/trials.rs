@@ -0,0 +1,402 @@
+use std::sync::OnceLock;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::challenges::{ChallengeRecords, stars_label};
+use crate::game::Block;
+use crate::garbage::GarbageRules;
+use crate::locale::Locale;
+use crate::menu::{Menu, MenuEntry, MenuEvent, MenuPlugin, MenuSet};
+use crate::palette::Palette;
+use crate::profiles::Profiles;
+use crate::{
+    AppState, CELL_SIZE, CpuControl, GameEntity, GameMode, GameSet, MatchOver, MatchOverTimer,
+    MatchRules, PlayerId, PlayerState, PlayerViews, Players, SoloMode, cursor_center,
+    local_start_state, player_view,
+};
+
+const RECORD_PREFIX: &str = "trial/";
+const GHOST_ALPHA: f32 = 0.35;
+
+pub struct TrialsPlugin;
+
+impl Plugin for TrialsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TrialRun>()
+            .add_plugins(MenuPlugin::<TrialItem>::default())
+            .add_systems(OnEnter(AppState::Trials), setup_select)
+            .add_systems(OnExit(AppState::Trials), cleanup_select)
+            .add_systems(
+                Update,
+                handle_select_input
+                    .after(MenuSet)
+                    .run_if(in_state(AppState::Trials)),
+            )
+            .add_systems(
+                Update,
+                (track_trial, show_solution_ghost, update_banner)
+                    .chain()
+                    .in_set(GameSet::Presentation),
+            );
+    }
+}
+
+// A trial starts from a fixed stack with a fixed sequence of rows rising under it.
+// Solutions are swaps of the pair whose left cell is [x, y] on that starting stack.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Trial {
+    id: String,
+    #[serde(default)]
+    pub speed: u32,
+    chain: u32,
+    stack: Vec<String>,
+    #[serde(default)]
+    rising: Vec<String>,
+    solution: Vec<[usize; 2]>,
+}
+
+impl Trial {
+    fn record_id(&self) -> String {
+        format!("{RECORD_PREFIX}{}", self.id)
+    }
+
+    // Matching the intended swap count earns all three stars; up to twice as many
+    // still earns two.
+    fn stars_for(&self, swaps: u32) -> u8 {
+        let intended = self.solution.len() as u32;
+        if swaps <= intended {
+            3
+        } else if swaps <= intended * 2 {
+            2
+        } else {
+            1
+        }
+    }
+}
+
+pub fn all() -> &'static [Trial] {
+    static TRIALS: OnceLock<Vec<Trial>> = OnceLock::new();
+    TRIALS.get_or_init(|| {
+        serde_json::from_str(include_str!("../assets/trials.json")).unwrap_or_else(|err| {
+            warn!("ignoring broken trial list: {err}");
+            Vec::new()
+        })
+    })
+}
+
+pub fn prepare(trial: &Trial, player: &mut PlayerState) {
+    player.grid.load_rows(&trial.stack);
+    let rising = trial
+        .rising
+        .iter()
+        .map(|row| row.chars().filter_map(Block::from_symbol).collect())
+        .collect();
+    player.grid.script_rows(rising);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TrialResult {
+    Passed(u8),
+    Failed,
+}
+
+#[derive(Resource, Default)]
+struct TrialRun {
+    result: Option<TrialResult>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct TrialItem(usize);
+
+#[derive(Resource)]
+struct SelectRoot(Entity);
+
+#[derive(Component)]
+struct TrialBanner;
+
+#[derive(Component)]
+struct TrialGhost;
+
+fn active_trial(rules: &MatchRules, mode: GameMode) -> Option<(usize, &'static Trial)> {
+    match rules.solo {
+        SoloMode::Trial(index) if mode == GameMode::OnePlayer => {
+            all().get(index).map(|trial| (index, trial))
+        }
+        _ => None,
+    }
+}
+
+fn setup_select(
+    mut commands: Commands,
+    records: Res<ChallengeRecords>,
+    palette: Res<Palette>,
+    locale: Res<Locale>,
+) {
+    let root = commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(0.0),
+                top: Val::Percent(0.0),
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(16.0),
+                ..Default::default()
+            },
+            background_color: BackgroundColor(palette.overlay),
+            ..Default::default()
+        })
+        .id();
+
+    commands.entity(root).with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            locale.get("trial.title"),
+            TextStyle {
+                font: Default::default(),
+                font_size: 42.0,
+                color: palette.heading,
+            },
+        ));
+
+        let entries = all()
+            .iter()
+            .enumerate()
+            .map(|(index, trial)| {
+                let label = locale.format(
+                    "trial.entry",
+                    &[
+                        ("number", &(index + 1)),
+                        ("chain", &trial.chain),
+                        ("swaps", &trial.solution.len()),
+                    ],
+                );
+                MenuEntry::new(
+                    TrialItem(index),
+                    format!("{label}  {}", stars_label(records.best(&trial.record_id()))),
+                )
+            })
+            .collect();
+        parent.spawn((
+            TextBundle {
+                text: Text::default().with_justify(JustifyText::Center),
+                ..Default::default()
+            },
+            Menu::new(entries),
+        ));
+
+        parent.spawn(TextBundle {
+            text: Text::from_section(
+                locale.get("trial.hint"),
+                TextStyle {
+                    font: Default::default(),
+                    font_size: 18.0,
+                    color: palette.text,
+                },
+            )
+            .with_justify(JustifyText::Center),
+            ..Default::default()
+        });
+    });
+
+    commands.insert_resource(SelectRoot(root));
+}
+
+fn cleanup_select(mut commands: Commands, root: Res<SelectRoot>) {
+    commands.entity(root.0).despawn_recursive();
+    commands.remove_resource::<SelectRoot>();
+}
+
+fn handle_select_input(
+    mut events: EventReader<MenuEvent<TrialItem>>,
+    gamepads: Res<Gamepads>,
+    mut mode: ResMut<GameMode>,
+    mut cpu: ResMut<CpuControl>,
+    mut rules: ResMut<MatchRules>,
+    mut garbage_rules: ResMut<GarbageRules>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for event in events.read() {
+        match *event {
+            MenuEvent::Confirm(TrialItem(index)) => {
+                *mode = GameMode::OnePlayer;
+                *cpu = CpuControl::default();
+                rules.versus = None;
+                rules.solo = SoloMode::Trial(index);
+                *garbage_rules = GarbageRules::default();
+                next_state.set(local_start_state(&gamepads));
+            }
+            MenuEvent::Cancel => next_state.set(AppState::Title),
+            MenuEvent::Adjust(..) => {}
+        }
+    }
+}
+
+// Only the first chain counts: once it settles the trial passes if it reached the
+// target length and fails otherwise. Topping out first also fails.
+#[allow(clippy::too_many_arguments)]
+fn track_trial(
+    players: Res<Players>,
+    rules: Res<MatchRules>,
+    mode: Res<GameMode>,
+    profiles: Res<Profiles>,
+    mut match_over: ResMut<MatchOver>,
+    mut match_over_timer: ResMut<MatchOverTimer>,
+    mut records: ResMut<ChallengeRecords>,
+    mut run: ResMut<TrialRun>,
+) {
+    let Some((_, trial)) = active_trial(&rules, *mode) else {
+        return;
+    };
+    if !match_over.active {
+        run.result = None;
+    } else {
+        if run.result.is_none() {
+            run.result = Some(TrialResult::Failed);
+        }
+        return;
+    }
+    let player = &players.p1;
+    if player.chain_counts.iter().sum::<u32>() == 0 {
+        return;
+    }
+    let passed = player.best_chain >= trial.chain;
+    let result = if passed {
+        TrialResult::Passed(trial.stars_for(player.swaps_made))
+    } else {
+        TrialResult::Failed
+    };
+    run.result = Some(result);
+    match_over.active = true;
+    match_over.winner = passed.then_some(PlayerId::P1);
+    match_over_timer.seconds = 0.0;
+    if let TrialResult::Passed(stars) = result {
+        records.record(&trial.record_id(), stars, &profiles);
+    }
+}
+
+// After a miss the board goes back to the starting stack with the intended swaps
+// drawn over it in order.
+#[allow(clippy::too_many_arguments)]
+fn show_solution_ghost(
+    mut commands: Commands,
+    mut players: ResMut<Players>,
+    rules: Res<MatchRules>,
+    mode: Res<GameMode>,
+    views: Res<PlayerViews>,
+    palette: Res<Palette>,
+    run: Res<TrialRun>,
+    ghosts: Query<Entity, With<TrialGhost>>,
+) {
+    if run.result != Some(TrialResult::Failed) {
+        for entity in &ghosts {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+    if !ghosts.is_empty() {
+        return;
+    }
+    let (Some((_, trial)), Some(view)) = (
+        active_trial(&rules, *mode),
+        player_view(&views, PlayerId::P1),
+    ) else {
+        return;
+    };
+    let player = &mut players.p1;
+    player.grid.load_rows(&trial.stack);
+    player.holding = false;
+    let color = palette.highlight.with_alpha(GHOST_ALPHA);
+    for (step, &[x, y]) in trial.solution.iter().enumerate() {
+        if !player.grid.in_bounds(x + 1, y) {
+            continue;
+        }
+        let center = cursor_center(&player.grid, x, y, view.origin, view.mirrored).truncate();
+        commands
+            .spawn(SpriteBundle {
+                sprite: Sprite {
+                    color,
+                    custom_size: Some(Vec2::new(CELL_SIZE * 2.0, CELL_SIZE)),
+                    ..Default::default()
+                },
+                transform: Transform::from_translation(center.extend(3.0)),
+                ..Default::default()
+            })
+            .insert((TrialGhost, GameEntity))
+            .with_children(|parent| {
+                parent.spawn(Text2dBundle {
+                    text: Text::from_section(
+                        (step + 1).to_string(),
+                        TextStyle {
+                            font: Default::default(),
+                            font_size: 20.0,
+                            color: palette.heading,
+                        },
+                    ),
+                    transform: Transform::from_xyz(0.0, 0.0, 0.1),
+                    ..Default::default()
+                });
+            });
+    }
+}
+
+fn update_banner(
+    mut commands: Commands,
+    rules: Res<MatchRules>,
+    mode: Res<GameMode>,
+    run: Res<TrialRun>,
+    palette: Res<Palette>,
+    locale: Res<Locale>,
+    mut banners: Query<&mut Text, With<TrialBanner>>,
+) {
+    let Some((index, trial)) = active_trial(&rules, *mode) else {
+        return;
+    };
+    let Ok(mut text) = banners.get_single_mut() else {
+        commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        top: Val::Px(12.0),
+                        width: Val::Percent(100.0),
+                        justify_content: JustifyContent::Center,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                GameEntity,
+            ))
+            .with_children(|parent| {
+                parent.spawn((
+                    TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font: Default::default(),
+                            font_size: 24.0,
+                            color: palette.heading,
+                        },
+                    )
+                    .with_text_justify(JustifyText::Center),
+                    TrialBanner,
+                ));
+            });
+        return;
+    };
+    let value = match run.result {
+        Some(TrialResult::Passed(stars)) => {
+            locale.format("trial.passed", &[("stars", &stars_label(stars))])
+        }
+        Some(TrialResult::Failed) => locale.get("trial.failed").to_string(),
+        None => locale.format(
+            "trial.goal",
+            &[("number", &(index + 1)), ("chain", &trial.chain)],
+        ),
+    };
+    if text.sections[0].value != value {
+        text.sections[0].value = value;
+    }
+}
@@ -0,0 +1,258 @@
+use std::fmt::Write as _;
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+
+use crate::cli::LaunchOptions;
+use crate::game::{Block, BlockColor};
+use crate::headless::HeadlessMatch;
+use crate::{
+    GameMode, PlayerCommand, PlayerId, PlayerState, SIM_TICK_HZ, active_players, cursor_span,
+};
+
+const RENDER_EVERY_FRAMES: u64 = 2;
+const ESC: u8 = 0x1b;
+const CTRL_C: u8 = 0x03;
+
+// Puts the terminal in raw mode for the lifetime of the guard; stty keeps this free
+// of extra dependencies and works over SSH.
+struct RawTerminal {
+    saved: String,
+}
+
+impl RawTerminal {
+    fn enable() -> Result<Self, String> {
+        let saved = stty(&["-g"])?;
+        stty(&["raw", "-echo"])?;
+        print!("\x1b[?25l\x1b[2J");
+        Ok(Self {
+            saved: saved.trim().to_string(),
+        })
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        let _ = stty(&[&self.saved]);
+        print!("\x1b[0m\x1b[?25h\r\n");
+        let _ = std::io::stdout().flush();
+    }
+}
+
+fn stty(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .output()
+        .map_err(|err| format!("could not run stty: {err}"))?;
+    if !output.status.success() {
+        return Err("stdin is not a terminal".to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn spawn_input_reader() -> Receiver<u8> {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut stdin = std::io::stdin();
+        let mut buf = [0u8; 64];
+        while let Ok(read) = stdin.read(&mut buf) {
+            if read == 0 || buf[..read].iter().any(|byte| sender.send(*byte).is_err()) {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+enum Key {
+    Char(u8),
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+fn read_keys(bytes: &mut Vec<u8>) -> Vec<Key> {
+    let mut keys = Vec::new();
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == ESC {
+            if index + 2 >= bytes.len() {
+                break;
+            }
+            let key = match (bytes[index + 1], bytes[index + 2]) {
+                (b'[', b'A') => Some(Key::Up),
+                (b'[', b'B') => Some(Key::Down),
+                (b'[', b'C') => Some(Key::Right),
+                (b'[', b'D') => Some(Key::Left),
+                _ => None,
+            };
+            keys.extend(key);
+            index += 3;
+        } else {
+            keys.push(Key::Char(bytes[index].to_ascii_lowercase()));
+            index += 1;
+        }
+    }
+    bytes.drain(..index);
+    keys
+}
+
+// P1 plays on the arrows or WASD with Space to swap and R to raise; a local second
+// player gets IJKL, U and O.
+fn key_command(key: &Key, two_local: bool) -> Option<(PlayerId, PlayerCommand)> {
+    let moves = |dx, dy| PlayerCommand::Move { dx, dy };
+    let (player, command) = match key {
+        Key::Up | Key::Char(b'w') => (PlayerId::P1, moves(0, 1)),
+        Key::Down | Key::Char(b's') => (PlayerId::P1, moves(0, -1)),
+        Key::Left | Key::Char(b'a') => (PlayerId::P1, moves(-1, 0)),
+        Key::Right | Key::Char(b'd') => (PlayerId::P1, moves(1, 0)),
+        Key::Char(b' ') => (PlayerId::P1, PlayerCommand::Swap),
+        Key::Char(b'r') => (PlayerId::P1, PlayerCommand::Raise),
+        Key::Char(b'i') if two_local => (PlayerId::P2, moves(0, 1)),
+        Key::Char(b'k') if two_local => (PlayerId::P2, moves(0, -1)),
+        Key::Char(b'j') if two_local => (PlayerId::P2, moves(-1, 0)),
+        Key::Char(b'l') if two_local => (PlayerId::P2, moves(1, 0)),
+        Key::Char(b'u') if two_local => (PlayerId::P2, PlayerCommand::Swap),
+        Key::Char(b'o') if two_local => (PlayerId::P2, PlayerCommand::Raise),
+        _ => return None,
+    };
+    Some((player, command))
+}
+
+pub fn run(options: &LaunchOptions) {
+    let terminal = match RawTerminal::enable() {
+        Ok(terminal) => terminal,
+        Err(err) => {
+            eprintln!("--tui needs an interactive terminal: {err}");
+            std::process::exit(1);
+        }
+    };
+    let input = spawn_input_reader();
+    let mut game = HeadlessMatch::new(options);
+    let two_local = game.mode == GameMode::TwoPlayer && !game.cpu.any();
+    let tick = Duration::from_secs(1) / SIM_TICK_HZ;
+    let mut pending = Vec::new();
+    let mut next_tick = Instant::now();
+
+    'game: loop {
+        pending.extend(input.try_iter());
+        for key in read_keys(&mut pending) {
+            if matches!(key, Key::Char(b'q') | Key::Char(CTRL_C)) {
+                break 'game;
+            }
+            if let Some((player, command)) = key_command(&key, two_local)
+                && !game.cpu.controls(player)
+            {
+                game.push(player, command);
+            }
+        }
+        game.step();
+        if game.frame().is_multiple_of(RENDER_EVERY_FRAMES) || game.match_over().active {
+            render(&game, "");
+        }
+        if game.match_over().active {
+            render(
+                &game,
+                &format!("result {}  -  press any key", game.result()),
+            );
+            let _ = input.recv();
+            break;
+        }
+        next_tick += tick;
+        if let Some(wait) = next_tick.checked_duration_since(Instant::now()) {
+            std::thread::sleep(wait);
+        } else {
+            next_tick = Instant::now();
+        }
+    }
+    drop(terminal);
+}
+
+fn render(game: &HeadlessMatch, footer: &str) {
+    let players = game.players();
+    let boards: Vec<&PlayerState> = active_players(game.mode)
+        .iter()
+        .map(|id| players.get(*id))
+        .collect();
+    let height = boards
+        .iter()
+        .map(|player| player.grid.height)
+        .max()
+        .unwrap_or(0);
+
+    let mut out = String::from("\x1b[H");
+    for player in &boards {
+        let _ = write!(
+            out,
+            "{:<width$}",
+            format!("score {} lv {}", player.score, player.rise_level),
+            width = player.grid.width * 2 + 4
+        );
+    }
+    out.push_str("\x1b[K\r\n");
+    for y in (0..height).rev() {
+        for player in &boards {
+            out.push('|');
+            for x in 0..player.grid.width {
+                let block = (y < player.grid.height)
+                    .then(|| player.grid.get(x, y))
+                    .flatten();
+                push_cell(&mut out, block, under_cursor(player, x, y), false);
+            }
+            out.push_str("|  ");
+        }
+        out.push_str("\x1b[K\r\n");
+    }
+    for player in &boards {
+        out.push('|');
+        for block in player.grid.next_row() {
+            push_cell(&mut out, Some(*block), false, true);
+        }
+        out.push_str("|  ");
+    }
+    out.push_str("\x1b[K\r\n");
+    for player in &boards {
+        let _ = write!(
+            out,
+            "{:<width$}",
+            format!("incoming {}", player.garbage_incoming()),
+            width = player.grid.width * 2 + 4
+        );
+    }
+    let _ = write!(out, "\x1b[K\r\n{footer}\x1b[K\r\n\x1b[J");
+    let mut stdout = std::io::stdout().lock();
+    let _ = stdout.write_all(out.as_bytes());
+    let _ = stdout.flush();
+}
+
+fn under_cursor(player: &PlayerState, x: usize, y: usize) -> bool {
+    let (span_x, span_y) = cursor_span(player);
+    (player.cursor.x..player.cursor.x + span_x).contains(&x)
+        && (player.cursor.y..player.cursor.y + span_y).contains(&y)
+}
+
+fn push_cell(out: &mut String, block: Option<Block>, cursor: bool, dim: bool) {
+    let style = match block {
+        Some(Block::Normal { color, .. } | Block::Item { color, .. }) => match color {
+            BlockColor::Red => "31",
+            BlockColor::Green => "32",
+            BlockColor::Yellow => "33",
+            BlockColor::Blue => "34",
+            BlockColor::Purple => "35",
+        },
+        Some(Block::Garbage { .. }) => "37",
+        Some(Block::Bomb) => "91",
+        Some(Block::Stone { .. }) => "90",
+        None => "0",
+    };
+    let symbol = block.map_or('.', |block| block.symbol());
+    let _ = write!(
+        out,
+        "\x1b[{style}{}{}m{symbol}{symbol}\x1b[0m",
+        if cursor { ";7" } else { "" },
+        if dim { ";2" } else { "" }
+    );
+}
@@ -0,0 +1,149 @@
+use std::sync::OnceLock;
+
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::rise_curve::RiseCurve;
+use crate::scoring::ScoreTables;
+
+const TUNING_ASSET: &str = "tuning.ron";
+const BUILTIN_TUNING: &str = include_str!("../assets/tuning.ron");
+
+// Loads `assets/tuning.ron` through the asset server into `LatestTuning`, and
+// with the file watcher on (the `debug` feature) saving the file updates it
+// again. Matches only pick it up when they start, see `LatestTuning`.
+pub struct TuningPlugin;
+
+impl Plugin for TuningPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Tuning>()
+            .register_asset_loader(TuningLoader)
+            .add_systems(Startup, load_tuning)
+            .add_systems(Update, apply_tuning);
+    }
+}
+
+// The table the current match plays by. It is copied from `LatestTuning` when
+// the match starts, so a match and its replay never see two different tables.
+#[derive(Asset, Resource, TypePath, Clone, Debug, Serialize, Deserialize)]
+pub struct Tuning {
+    pub rise: RiseCurve,
+    pub rise_pause: f32,
+    pub clear: ClearTiming,
+    pub top_out_grace: f32,
+    pub garbage_chain_cap: u32,
+    pub scoring: ScoreTables,
+}
+
+// Matched blocks flash together for `flash` seconds, then pop one after another
+// every `pop_interval`, so bigger clears hold the board for longer.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ClearTiming {
+    pub flash: f32,
+    pub pop_interval: f32,
+}
+
+impl ClearTiming {
+    pub fn duration(&self, blocks: usize) -> f32 {
+        self.flash + self.pop_interval * blocks as f32
+    }
+
+    pub fn pop_at(&self, order: usize) -> f32 {
+        self.flash + self.pop_interval * (order + 1) as f32
+    }
+}
+
+impl Tuning {
+    fn parse(text: &str, source: &str) -> Option<Self> {
+        match ron::from_str::<Tuning>(text) {
+            Ok(tuning) if !tuning.rise.levels.is_empty() => Some(tuning),
+            Ok(_) => {
+                warn!("ignoring tuning with an empty rise curve in {source}");
+                None
+            }
+            Err(err) => {
+                warn!("ignoring broken tuning in {source}: {err}");
+                None
+            }
+        }
+    }
+
+    pub fn builtin() -> &'static Self {
+        static BUILTIN: OnceLock<Tuning> = OnceLock::new();
+        BUILTIN.get_or_init(|| {
+            Self::parse(BUILTIN_TUNING, "the built-in table")
+                .expect("the built-in tuning table should parse")
+        })
+    }
+
+    // FNV-1a over the serialized table: stable across builds and platforms, so
+    // netplay peers and traces can check they agree without sending the file.
+    pub fn fingerprint(&self) -> u64 {
+        ron::to_string(self)
+            .unwrap_or_default()
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+            })
+    }
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        Self::builtin().clone()
+    }
+}
+
+// The newest table the asset server has handed over. It starts as the copy
+// built into the binary until `assets/tuning.ron` finishes loading.
+#[derive(Resource, Default)]
+pub struct LatestTuning(pub Tuning);
+
+struct TuningLoader;
+
+impl AssetLoader for TuningLoader {
+    type Asset = Tuning;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Tuning, Self::Error> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text).await?;
+        Tuning::parse(&text, TUNING_ASSET).ok_or_else(|| "unusable tuning table".into())
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &[TUNING_ASSET]
+    }
+}
+
+#[derive(Resource)]
+struct TuningHandle(Handle<Tuning>);
+
+fn load_tuning(mut commands: Commands, server: Res<AssetServer>) {
+    commands.insert_resource(TuningHandle(server.load(TUNING_ASSET)));
+}
+
+fn apply_tuning(
+    mut events: EventReader<AssetEvent<Tuning>>,
+    handle: Res<TuningHandle>,
+    assets: Res<Assets<Tuning>>,
+    mut latest: ResMut<LatestTuning>,
+) {
+    for event in events.read() {
+        if let AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } = *event
+            && id == handle.0.id()
+            && let Some(tuning) = assets.get(id)
+        {
+            info!("loaded {TUNING_ASSET}, used from the next match");
+            latest.0 = tuning.clone();
+        }
+    }
+}
@@ -0,0 +1,390 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::achievements::{Achievements, speed_requirement};
+use crate::game::MAX_COLORS;
+use crate::garbage::{
+    CHAIN_BONUSES, CHAIN_CAPS, COMBO_THRESHOLDS, CapCurve, GarbagePreset, GarbageRules,
+};
+use crate::locale::Locale;
+use crate::menu::{Menu, MenuEntry, MenuEvent, MenuPlugin, MenuSet};
+use crate::pacing::{
+    FLASH_SECONDS, GRAVITY_STEPS, HOVER_STEPS, POP_INTERVALS, Pacing, PacingPreset,
+};
+use crate::palette::Palette;
+use crate::settings::{START_SPEEDS, cycle};
+use crate::{AppState, MatchRules, PlayerId, local_start_state};
+
+const GARBAGE_PERCENTS: &[u32] = &[50, 100, 150, 200];
+const BEST_OF: &[u32] = &[1, 3, 5, 7];
+const COLOR_COUNTS: &[u8] = &[4, MAX_COLORS];
+
+pub struct VersusPlugin;
+
+impl Plugin for VersusPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(MenuPlugin::<VersusItem>::default())
+            .add_systems(OnEnter(AppState::Versus), setup_versus)
+            .add_systems(OnExit(AppState::Versus), cleanup_versus)
+            .add_systems(
+                Update,
+                (handle_versus_input, refresh_versus_text)
+                    .chain()
+                    .after(MenuSet)
+                    .run_if(in_state(AppState::Versus)),
+            );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VersusRules {
+    pub levels: [u32; 2],
+    pub garbage_percent: u32,
+    pub best_of: u32,
+    pub colors: u8,
+    pub vertical_swaps: bool,
+    pub items: bool,
+}
+
+impl Default for VersusRules {
+    fn default() -> Self {
+        Self {
+            levels: [0; 2],
+            garbage_percent: 100,
+            best_of: 1,
+            colors: MAX_COLORS,
+            vertical_swaps: false,
+            items: false,
+        }
+    }
+}
+
+impl VersusRules {
+    pub fn level(&self, id: PlayerId) -> u32 {
+        match id {
+            PlayerId::P1 => self.levels[0],
+            PlayerId::P2 => self.levels[1],
+        }
+    }
+
+    fn level_mut(&mut self, id: PlayerId) -> &mut u32 {
+        match id {
+            PlayerId::P1 => &mut self.levels[0],
+            PlayerId::P2 => &mut self.levels[1],
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum VersusItem {
+    Level(PlayerId),
+    Garbage,
+    Ruleset,
+    ChainBonus,
+    ComboFrom,
+    Cancel,
+    ChainCap,
+    CapCurve,
+    Pacing,
+    ClearFlash,
+    PopInterval,
+    Hover,
+    GravityStep,
+    BestOf,
+    Colors,
+    VerticalSwaps,
+    Items,
+    Start,
+}
+
+impl VersusItem {
+    const ALL: &'static [VersusItem] = &[
+        VersusItem::Level(PlayerId::P1),
+        VersusItem::Level(PlayerId::P2),
+        VersusItem::Garbage,
+        VersusItem::Ruleset,
+        VersusItem::ChainBonus,
+        VersusItem::ComboFrom,
+        VersusItem::Cancel,
+        VersusItem::ChainCap,
+        VersusItem::CapCurve,
+        VersusItem::Pacing,
+        VersusItem::ClearFlash,
+        VersusItem::PopInterval,
+        VersusItem::Hover,
+        VersusItem::GravityStep,
+        VersusItem::BestOf,
+        VersusItem::Colors,
+        VersusItem::VerticalSwaps,
+        VersusItem::Items,
+        VersusItem::Start,
+    ];
+}
+
+#[derive(Resource)]
+struct VersusScreen {
+    root: Entity,
+    setup: VersusRules,
+    garbage: GarbageRules,
+    pacing: Pacing,
+}
+
+fn setup_versus(
+    mut commands: Commands,
+    rules: Res<MatchRules>,
+    garbage_rules: Res<GarbageRules>,
+    palette: Res<Palette>,
+    locale: Res<Locale>,
+) {
+    let setup = rules.versus.unwrap_or(VersusRules {
+        levels: [rules.start_level; 2],
+        ..Default::default()
+    });
+    let root = commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(0.0),
+                top: Val::Percent(0.0),
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(16.0),
+                ..Default::default()
+            },
+            background_color: BackgroundColor(palette.overlay),
+            ..Default::default()
+        })
+        .id();
+
+    commands.entity(root).with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            locale.get("versus.title"),
+            TextStyle {
+                font: Default::default(),
+                font_size: 42.0,
+                color: palette.heading,
+            },
+        ));
+
+        let entries = VersusItem::ALL
+            .iter()
+            .map(|item| MenuEntry::new(*item, ""))
+            .collect();
+        parent.spawn((
+            TextBundle {
+                text: Text::default().with_justify(JustifyText::Center),
+                ..Default::default()
+            },
+            Menu::new(entries),
+        ));
+
+        parent.spawn(TextBundle {
+            text: Text::from_section(
+                locale.get("versus.hint"),
+                TextStyle {
+                    font: Default::default(),
+                    font_size: 18.0,
+                    color: palette.text,
+                },
+            )
+            .with_justify(JustifyText::Center),
+            ..Default::default()
+        });
+    });
+
+    commands.insert_resource(VersusScreen {
+        root,
+        setup,
+        garbage: *garbage_rules,
+        pacing: rules.pacing,
+    });
+}
+
+fn cleanup_versus(mut commands: Commands, screen: Res<VersusScreen>) {
+    commands.entity(screen.root).despawn_recursive();
+    commands.remove_resource::<VersusScreen>();
+}
+
+fn handle_versus_input(
+    mut events: EventReader<MenuEvent<VersusItem>>,
+    gamepads: Res<Gamepads>,
+    achievements: Res<Achievements>,
+    mut screen: ResMut<VersusScreen>,
+    mut rules: ResMut<MatchRules>,
+    mut garbage_rules: ResMut<GarbageRules>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for event in events.read() {
+        let (item, step) = match *event {
+            MenuEvent::Adjust(item, step) => (item, step),
+            MenuEvent::Confirm(VersusItem::Start) => {
+                rules.versus = Some(screen.setup);
+                rules.pacing = screen.pacing;
+                *garbage_rules = screen.garbage;
+                next_state.set(local_start_state(&gamepads));
+                continue;
+            }
+            MenuEvent::Confirm(item) => (item, 1),
+            MenuEvent::Cancel => {
+                next_state.set(AppState::Title);
+                continue;
+            }
+        };
+        let VersusScreen {
+            setup,
+            garbage,
+            pacing,
+            ..
+        } = &mut *screen;
+        match item {
+            VersusItem::Level(id) => {
+                let unlocked: Vec<u32> = START_SPEEDS
+                    .iter()
+                    .copied()
+                    .filter(|level| achievements.meets(speed_requirement(*level)))
+                    .collect();
+                let level = setup.level_mut(id);
+                *level = cycle(&unlocked, *level, step);
+            }
+            VersusItem::Garbage => {
+                setup.garbage_percent = cycle(GARBAGE_PERCENTS, setup.garbage_percent, step);
+            }
+            VersusItem::Ruleset => {
+                let preset = cycle(GarbagePreset::ALL, garbage.preset, step);
+                *garbage = match preset {
+                    GarbagePreset::Custom => GarbageRules { preset, ..*garbage },
+                    _ => GarbageRules::from_preset(preset),
+                };
+            }
+            VersusItem::ChainBonus => {
+                garbage.preset = GarbagePreset::Custom;
+                garbage.chain_bonus = cycle(CHAIN_BONUSES, garbage.chain_bonus, step);
+            }
+            VersusItem::ComboFrom => {
+                garbage.preset = GarbagePreset::Custom;
+                garbage.combo_threshold = cycle(COMBO_THRESHOLDS, garbage.combo_threshold, step);
+            }
+            VersusItem::Cancel => {
+                garbage.preset = GarbagePreset::Custom;
+                garbage.cancel = !garbage.cancel;
+            }
+            VersusItem::ChainCap => {
+                garbage.preset = GarbagePreset::Custom;
+                garbage.chain_cap = cycle(CHAIN_CAPS, garbage.chain_cap, step);
+            }
+            VersusItem::CapCurve => {
+                garbage.preset = GarbagePreset::Custom;
+                garbage.cap_curve = cycle(CapCurve::ALL, garbage.cap_curve, step);
+            }
+            VersusItem::Pacing => {
+                let preset = cycle(PacingPreset::ALL, pacing.preset, step);
+                *pacing = match preset {
+                    PacingPreset::Custom => Pacing { preset, ..*pacing },
+                    _ => Pacing::from_preset(preset),
+                };
+            }
+            VersusItem::ClearFlash => {
+                pacing.preset = PacingPreset::Custom;
+                pacing.clear.flash = cycle(FLASH_SECONDS, pacing.clear.flash, step);
+            }
+            VersusItem::PopInterval => {
+                pacing.preset = PacingPreset::Custom;
+                pacing.clear.pop_interval = cycle(POP_INTERVALS, pacing.clear.pop_interval, step);
+            }
+            VersusItem::Hover => {
+                pacing.preset = PacingPreset::Custom;
+                pacing.hover_steps = cycle(HOVER_STEPS, pacing.hover_steps, step);
+            }
+            VersusItem::GravityStep => {
+                pacing.preset = PacingPreset::Custom;
+                pacing.gravity_step = cycle(GRAVITY_STEPS, pacing.gravity_step, step);
+            }
+            VersusItem::BestOf => setup.best_of = cycle(BEST_OF, setup.best_of, step),
+            VersusItem::Colors => setup.colors = cycle(COLOR_COUNTS, setup.colors, step),
+            VersusItem::VerticalSwaps => setup.vertical_swaps = !setup.vertical_swaps,
+            VersusItem::Items => setup.items = !setup.items,
+            VersusItem::Start => {}
+        }
+    }
+}
+
+fn refresh_versus_text(
+    screen: Res<VersusScreen>,
+    locale: Res<Locale>,
+    mut menus: Query<&mut Menu<VersusItem>>,
+) {
+    if !screen.is_changed() && !locale.is_changed() {
+        return;
+    }
+    let setup = &screen.setup;
+    let garbage = &screen.garbage;
+    let pacing = &screen.pacing;
+    let seconds = |value: f32| format!("{value:.2}");
+    let on_off = |on: bool| locale.get(if on { "options.on" } else { "options.off" });
+    for mut menu in &mut menus {
+        for entry in menu.entries_mut() {
+            entry.label = match entry.id {
+                VersusItem::Level(id) => locale.format(
+                    "versus.level",
+                    &[("player", &id.label()), ("value", &setup.level(id))],
+                ),
+                VersusItem::Garbage => {
+                    locale.format("versus.garbage", &[("value", &setup.garbage_percent)])
+                }
+                VersusItem::Ruleset => locale.format(
+                    "versus.ruleset",
+                    &[("value", &locale.get(garbage.preset.label_key()))],
+                ),
+                VersusItem::ChainBonus => {
+                    locale.format("versus.chain_bonus", &[("value", &garbage.chain_bonus)])
+                }
+                VersusItem::ComboFrom => match garbage.combo_threshold {
+                    Some(threshold) => locale.format("versus.combo_from", &[("value", &threshold)]),
+                    None => locale.format("versus.combo_from", &[("value", &on_off(false))]),
+                },
+                VersusItem::Cancel => {
+                    locale.format("versus.cancel", &[("value", &on_off(garbage.cancel))])
+                }
+                VersusItem::ChainCap => {
+                    locale.format("versus.chain_cap", &[("value", &garbage.chain_cap)])
+                }
+                VersusItem::CapCurve => locale.format(
+                    "versus.cap_curve",
+                    &[("value", &locale.get(garbage.cap_curve.label_key()))],
+                ),
+                VersusItem::Pacing => locale.format(
+                    "versus.pacing",
+                    &[("value", &locale.get(pacing.preset.label_key()))],
+                ),
+                VersusItem::ClearFlash => locale.format(
+                    "versus.clear_flash",
+                    &[("value", &seconds(pacing.clear.flash))],
+                ),
+                VersusItem::PopInterval => locale.format(
+                    "versus.pop_interval",
+                    &[("value", &seconds(pacing.clear.pop_interval))],
+                ),
+                VersusItem::Hover => {
+                    locale.format("versus.hover", &[("value", &pacing.hover_steps)])
+                }
+                VersusItem::GravityStep => locale.format(
+                    "versus.gravity_step",
+                    &[("value", &seconds(pacing.gravity_step))],
+                ),
+                VersusItem::BestOf => locale.format("versus.best_of", &[("value", &setup.best_of)]),
+                VersusItem::Colors => locale.format("versus.colors", &[("value", &setup.colors)]),
+                VersusItem::VerticalSwaps => locale.format(
+                    "versus.vertical_swaps",
+                    &[("value", &on_off(setup.vertical_swaps))],
+                ),
+                VersusItem::Items => {
+                    locale.format("versus.items", &[("value", &on_off(setup.items))])
+                }
+                VersusItem::Start => locale.get("versus.start").to_string(),
+            };
+        }
+    }
+}